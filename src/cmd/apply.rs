@@ -0,0 +1,324 @@
+use std::str;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono::format::{Item, StrftimeItems};
+use csv;
+use rustc_serialize::{Decodable, Decoder};
+
+use CliResult;
+use config::{Config, Delimiter};
+use select::SelectColumns;
+use util;
+
+static USAGE: &'static str = "
+Applies an operation to every field in the selected columns.
+
+The operations are:
+
+    upper      Convert to uppercase.
+    lower      Convert to lowercase.
+    trim       Remove leading and trailing whitespace.
+    ltrim      Remove leading whitespace.
+    rtrim      Remove trailing whitespace.
+    squeeze    Replace every run of whitespace inside a field with a single
+               space.
+    len        Replace the field with its length, in characters.
+    datefmt    Parse a date with one of the formats given by --formats, and
+               write it in the format given by --output-format instead.
+
+Several operations can be given, separated by commas, in which case they are
+applied from left to right. The fields must be valid UTF-8.
+
+The date formats use the strftime syntax, e.g., '%Y-%m-%d %H:%M:%S'. (See
+https://docs.rs/chrono/0.4/chrono/format/strftime/index.html for details.)
+The formats are tried in order, and the first one that matches is used. Dates
+without a time are at midnight, and dates without a time zone are in the zone
+given by --timezone. Dates are always written in UTC. Empty fields are left
+as they are.
+
+  Normalize the case and whitespace of the 'name' column:
+  $ xsv apply trim,lower,squeeze name data.csv
+
+  Add a column with the length of each name:
+  $ xsv apply len name --new-column name_length data.csv
+
+  Normalize dates written as DD/MM/YYYY to YYYY-MM-DD:
+  $ xsv apply datefmt --formats '%d/%m/%Y' --output-format '%Y-%m-%d' \\
+        date data.csv
+
+Usage:
+    xsv apply [options] [--formats <fmt>]... <operation> <selection> [<input>]
+    xsv apply --help
+
+apply options:
+    -c, --new-column <name>  Append the result as a new column with this name
+                             instead of replacing the selected field. Only one
+                             column may be selected.
+    --formats <fmt>          A format to parse dates with. May be given more
+                             than once. By default, dates are parsed as
+                             '%Y-%m-%dT%H:%M:%S%z', '%Y-%m-%d %H:%M:%S' or
+                             '%Y-%m-%d'.
+    --output-format <fmt>    The format to write dates with.
+                             [default: %Y-%m-%dT%H:%M:%SZ]
+    --timezone <zone>        The time zone of dates without one, as an offset
+                             from UTC like '+02:00' or '-0500', or 'UTC'.
+                             [default: UTC]
+    --strict                 Fail on dates that don't match any format,
+                             instead of leaving them as they are.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. (i.e., The operation is applied to it,
+                           and no header is written for a new column.)
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_operation: Operations,
+    arg_selection: SelectColumns,
+    arg_input: Option<String>,
+    flag_new_column: Option<String>,
+    flag_formats: Vec<String>,
+    flag_output_format: String,
+    flag_timezone: Offset,
+    flag_strict: bool,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers)
+        .select(args.arg_selection.clone());
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+
+    let mut headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+    if args.flag_new_column.is_some() && sel.len() != 1 {
+        return fail!("Exactly one column must be selected with \
+                      --new-column.");
+    }
+    if !rconfig.no_headers {
+        if let Some(ref name) = args.flag_new_column {
+            headers.push_field(name.as_bytes());
+        }
+        wtr.write_record(&headers)?;
+    }
+
+    let ops = &args.arg_operation.0;
+    let dates = DateFormatter::new(&args)?;
+    let mut record = csv::ByteRecord::new();
+    let mut applied = csv::ByteRecord::new();
+    let mut fields: Vec<Option<String>> = vec![];
+    let mut i = 0;
+    while rdr.read_byte_record(&mut record)? {
+        i += 1;
+        fields.clear();
+        fields.resize(record.len(), None);
+        for &j in sel.iter() {
+            let field = match str::from_utf8(&record[j]) {
+                Ok(field) => field,
+                Err(_) => {
+                    return fail!(format!(
+                        "Field {} of record {} is not valid UTF-8.",
+                        j + 1, i));
+                }
+            };
+            let mut value = field.to_owned();
+            for op in ops {
+                match op.apply(&value, &dates) {
+                    Some(applied) => value = applied,
+                    None if args.flag_strict => {
+                        return fail!(format!(
+                            "Could not parse '{}' (field {} of record {}) \
+                             as a date.", value, j + 1, i));
+                    }
+                    None => {}
+                }
+            }
+            fields[j] = Some(value);
+        }
+
+        applied.clear();
+        if args.flag_new_column.is_some() {
+            applied.extend(&record);
+            applied.push_field(fields[sel[0]].as_ref().unwrap().as_bytes());
+        } else {
+            for (field, value) in record.iter().zip(&fields) {
+                match *value {
+                    None => applied.push_field(field),
+                    Some(ref value) => applied.push_field(value.as_bytes()),
+                }
+            }
+        }
+        wtr.write_byte_record(&applied)?;
+    }
+    Ok(wtr.flush()?)
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Operation {
+    Upper,
+    Lower,
+    Trim,
+    Ltrim,
+    Rtrim,
+    Squeeze,
+    Len,
+    DateFmt,
+}
+
+impl Operation {
+    /// Returns the result of the operation on `value`, or `None` if the
+    /// operation doesn't apply to it (e.g., it's not a date).
+    fn apply(&self, value: &str, dates: &DateFormatter) -> Option<String> {
+        Some(match *self {
+            Operation::Upper => value.to_uppercase(),
+            Operation::Lower => value.to_lowercase(),
+            Operation::Trim => value.trim().to_owned(),
+            Operation::Ltrim => value.trim_start().to_owned(),
+            Operation::Rtrim => value.trim_end().to_owned(),
+            Operation::Squeeze => squeeze(value),
+            Operation::Len => value.chars().count().to_string(),
+            Operation::DateFmt if value.is_empty() => String::new(),
+            Operation::DateFmt => return dates.reformat(value),
+        })
+    }
+}
+
+/// The operations given on the command line, in the order to apply them.
+struct Operations(Vec<Operation>);
+
+impl Decodable for Operations {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Operations, D::Error> {
+        let s = d.read_str()?;
+        let mut ops = vec![];
+        for name in s.split(',') {
+            ops.push(match name.trim() {
+                "upper" => Operation::Upper,
+                "lower" => Operation::Lower,
+                "trim" => Operation::Trim,
+                "ltrim" => Operation::Ltrim,
+                "rtrim" => Operation::Rtrim,
+                "squeeze" => Operation::Squeeze,
+                "len" => Operation::Len,
+                "datefmt" => Operation::DateFmt,
+                name => {
+                    let msg = format!("Unknown operation '{}'.", name);
+                    return Err(d.error(&*msg));
+                }
+            });
+        }
+        Ok(Operations(ops))
+    }
+}
+
+struct DateFormatter {
+    formats: Vec<String>,
+    output_format: String,
+    timezone: FixedOffset,
+}
+
+impl DateFormatter {
+    fn new(args: &Args) -> CliResult<DateFormatter> {
+        let mut formats = args.flag_formats.clone();
+        if formats.is_empty() {
+            formats = vec![
+                "%Y-%m-%dT%H:%M:%S%z".to_owned(),
+                "%Y-%m-%d %H:%M:%S".to_owned(),
+                "%Y-%m-%d".to_owned(),
+            ];
+        }
+        for fmt in formats.iter().chain(Some(&args.flag_output_format)) {
+            if StrftimeItems::new(fmt).any(|it| it == Item::Error) {
+                return fail!(format!("Invalid date format '{}'.", fmt));
+            }
+        }
+        Ok(DateFormatter {
+            formats: formats,
+            output_format: args.flag_output_format.clone(),
+            timezone: args.flag_timezone.0,
+        })
+    }
+
+    fn reformat(&self, value: &str) -> Option<String> {
+        self.parse(value).map(|dt| dt.format(&self.output_format).to_string())
+    }
+
+    fn parse(&self, value: &str) -> Option<DateTime<Utc>> {
+        for fmt in &self.formats {
+            if let Ok(dt) = DateTime::parse_from_str(value, fmt) {
+                return Some(dt.with_timezone(&Utc));
+            }
+            let local = NaiveDateTime::parse_from_str(value, fmt).ok()
+                .or_else(|| {
+                    NaiveDate::parse_from_str(value, fmt).ok()
+                        .and_then(|d| d.and_hms_opt(0, 0, 0))
+                });
+            if let Some(local) = local {
+                if let Some(dt) =
+                        self.timezone.from_local_datetime(&local).single() {
+                    return Some(dt.with_timezone(&Utc));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A fixed offset from UTC, e.g., `+02:00`.
+struct Offset(FixedOffset);
+
+impl Decodable for Offset {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Offset, D::Error> {
+        let s = d.read_str()?;
+        let msg = format!("Could not parse '{}' as a time zone.", s);
+        if s == "UTC" || s == "Z" {
+            return Ok(Offset(FixedOffset::east_opt(0).unwrap()));
+        }
+        let sign = match s.chars().next() {
+            Some('+') => 1,
+            Some('-') => -1,
+            _ => return Err(d.error(&*msg)),
+        };
+        let digits = s[1..].replace(":", "");
+        if !digits.bytes().all(|b| b.is_ascii_digit())
+                || (digits.len() != 2 && digits.len() != 4) {
+            return Err(d.error(&*msg));
+        }
+        let hours: i32 = digits[..2].parse().unwrap();
+        let minutes: i32 = digits[2..].parse().unwrap_or(0);
+        match FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)) {
+            Some(offset) => Ok(Offset(offset)),
+            None => Err(d.error(&*msg)),
+        }
+    }
+}
+
+/// Replaces every run of whitespace in `value` with a single space, keeping
+/// any leading or trailing whitespace as a single space too.
+fn squeeze(value: &str) -> String {
+    let mut squeezed = String::with_capacity(value.len());
+    let mut in_space = false;
+    for c in value.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                squeezed.push(' ');
+            }
+            in_space = true;
+        } else {
+            squeezed.push(c);
+            in_space = false;
+        }
+    }
+    squeezed
+}
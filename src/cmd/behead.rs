@@ -0,0 +1,46 @@
+use std::io::Write;
+
+use CliResult;
+use config::{Config, Delimiter};
+use util::{self, Recorder};
+
+static USAGE: &'static str = "
+Drops the header row from CSV data.
+
+Every other record is written exactly as it appears in the input, including
+its delimiters and quoting. This is useful for piping CSV data into tools that
+don't understand headers.
+
+Usage:
+    xsv behead [options] [<input>]
+    xsv behead --help
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    flag_output: Option<String>,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(false);
+
+    let mut rdr = rconfig.from_reader(Recorder::new(rconfig.io_reader()?));
+    rdr.byte_headers()?;
+    let start = rdr.position().byte() as usize;
+
+    let mut wtr = Config::new(&args.flag_output).io_writer()?;
+    rdr.into_inner().copy_from(start, &mut wtr)?;
+    wtr.flush()?;
+    Ok(())
+}
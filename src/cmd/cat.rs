@@ -15,8 +15,12 @@ reversed with the '--pad' flag.)
 When concatenating by row, all CSV data must have the same number of columns.
 If you need to rearrange the columns or fix the lengths of records, use the
 'select' or 'fixlengths' commands. Also, only the headers of the *first* CSV
-data given are used. Headers in subsequent inputs are ignored. (This behavior
-can be disabled with --no-headers.)
+data given are used. Headers in subsequent inputs are ignored, unless you set
+the --check-headers flag, which requires them to be the same as the first.
+(Headers aren't treated specially at all with --no-headers.)
+
+The '--group' flag adds a column to the start of each row with the input it
+came from, as given on the command line.
 
 Usage:
     xsv cat rows    [options] [<input>...]
@@ -27,6 +31,12 @@ cat options:
     -p, --pad              When concatenating columns, this flag will cause
                            all records to appear. It will pad each row if
                            other CSV data isn't long enough.
+    --check-headers        When concatenating rows, fail if an input has
+                           different headers than the first input.
+    --flexible             When concatenating rows, allow inputs with
+                           different numbers of columns.
+    -g, --group <name>     When concatenating rows, add a column called
+                           <name> with the input each row came from.
 
 Common options:
     -h, --help             Display this message
@@ -44,6 +54,9 @@ struct Args {
     cmd_columns: bool,
     arg_input: Vec<String>,
     flag_pad: bool,
+    flag_check_headers: bool,
+    flag_flexible: bool,
+    flag_group: Option<String>,
     flag_output: Option<String>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
@@ -70,14 +83,52 @@ impl Args {
 
     fn cat_rows(&self) -> CliResult<()> {
         let mut row = csv::ByteRecord::new();
-        let mut wtr = Config::new(&self.flag_output).writer()?;
-        for (i, conf) in self.configs()?.into_iter().enumerate() {
-            let mut rdr = conf.reader()?;
-            if i == 0 {
-                conf.write_headers(&mut rdr, &mut wtr)?;
+        let mut grouped = csv::ByteRecord::new();
+        let mut wtr = Config::new(&self.flag_output)
+            .flexible(self.flag_flexible)
+            .writer()?;
+        let mut names = self.arg_input.clone();
+        if names.is_empty() {
+            names.push("-".to_owned());
+        }
+        let mut first_headers: Option<csv::ByteRecord> = None;
+        let configs = self.configs()?;
+        for (i, conf) in configs.into_iter().enumerate() {
+            let mut rdr = conf.flexible(self.flag_flexible).reader()?;
+            let headers = rdr.byte_headers()?.clone();
+            // An empty input has no headers to write or compare.
+            if !self.flag_no_headers && !headers.is_empty() {
+                match first_headers {
+                    None => {
+                        if let Some(ref name) = self.flag_group {
+                            grouped.clear();
+                            grouped.push_field(name.as_bytes());
+                            grouped.extend(&headers);
+                            wtr.write_byte_record(&grouped)?;
+                        } else {
+                            wtr.write_byte_record(&headers)?;
+                        }
+                        first_headers = Some(headers);
+                    }
+                    Some(ref first) => {
+                        if self.flag_check_headers && *first != headers {
+                            return fail!(format!(
+                                "The headers of '{}' are different from \
+                                 the headers of '{}'.",
+                                names[i], names[0]));
+                        }
+                    }
+                }
             }
             while rdr.read_byte_record(&mut row)? {
-                wtr.write_byte_record(&row)?;
+                if self.flag_group.is_some() {
+                    grouped.clear();
+                    grouped.push_field(names[i].as_bytes());
+                    grouped.extend(&row);
+                    wtr.write_byte_record(&grouped)?;
+                } else {
+                    wtr.write_byte_record(&row)?;
+                }
             }
         }
         wtr.flush().map_err(From::from)
@@ -10,9 +10,16 @@ Prints a count of the number of records in the CSV data.
 Note that the count will not include the header row (unless --no-headers is
 given).
 
+When the CSV data has an index, the count is read from the index instead of
+scanning the data.
+
 Usage:
     xsv count [options] [<input>]
 
+count options:
+    --human                Print the count with thousands separators,
+                           e.g., 1,234,567.
+
 Common options:
     -h, --help             Display this message
     -n, --no-headers       When set, the first row will not be included in
@@ -24,6 +31,7 @@ Common options:
 #[derive(RustcDecodable)]
 struct Args {
     arg_input: Option<String>,
+    flag_human: bool,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
 }
@@ -47,5 +55,22 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
                 count
             }
         };
-    Ok(println!("{}", count))
+    if args.flag_human {
+        Ok(println!("{}", human(count)))
+    } else {
+        Ok(println!("{}", count))
+    }
+}
+
+/// Formats `n` with a comma between each group of three digits.
+fn human(n: u64) -> String {
+    let digits = n.to_string();
+    let mut formatted = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+    formatted
 }
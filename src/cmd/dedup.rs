@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+use std::str;
+
+use csv;
+
+use CliResult;
+use config::{Config, Delimiter};
+use select::{SelectColumns, Selection};
+use util;
+
+static USAGE: &'static str = "
+Removes duplicate rows from CSV data.
+
+Two rows are duplicates if they have the same values in the selected columns
+(all columns by default). Only the first occurrence of each row is kept, and
+rows are otherwise written in their original order.
+
+By default, this keeps the key of every distinct row in memory. If the CSV data
+is already sorted on the selected columns, use --sorted to instead run in
+constant memory by only comparing each row with the one before it.
+
+Usage:
+    xsv dedup [options] [<input>]
+    xsv dedup --help
+
+dedup options:
+    -s, --select <arg>     Select a subset of columns to compare.
+                           See 'xsv select --help' for the format details.
+    -N, --numeric          Compare fields that are numbers by their value,
+                           so that e.g. '1', '1.0' and '01' are the same.
+    --sorted               Assume that the CSV data is sorted on the selected
+                           columns, so that duplicates are always adjacent.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. Namely, it will be compared with the
+                           rest of the rows. Otherwise, the first row will
+                           always appear as the header row in the output.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+type ByteString = Vec<u8>;
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    flag_select: SelectColumns,
+    flag_numeric: bool,
+    flag_sorted: bool,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers)
+        .select(args.flag_select.clone());
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+
+    let headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+    rconfig.write_headers(&mut rdr, &mut wtr)?;
+
+    let mut record = csv::ByteRecord::new();
+    if args.flag_sorted {
+        let mut prev: Option<Vec<ByteString>> = None;
+        while rdr.read_byte_record(&mut record)? {
+            let key = args.key(&sel, &record);
+            if prev.as_ref() == Some(&key) {
+                continue;
+            }
+            wtr.write_byte_record(&record)?;
+            prev = Some(key);
+        }
+    } else {
+        let mut seen = HashSet::new();
+        while rdr.read_byte_record(&mut record)? {
+            if seen.insert(args.key(&sel, &record)) {
+                wtr.write_byte_record(&record)?;
+            }
+        }
+    }
+    Ok(wtr.flush()?)
+}
+
+impl Args {
+    /// The values of `record` that determine whether it is a duplicate.
+    fn key(&self, sel: &Selection, record: &csv::ByteRecord) -> Vec<ByteString> {
+        sel.select(record).map(|field| {
+            if self.flag_numeric {
+                normalize_number(field)
+            } else {
+                field.to_vec()
+            }
+        }).collect()
+    }
+}
+
+/// Rewrite `field` so that equal numbers have equal bytes. Fields that
+/// aren't numbers are returned unchanged.
+fn normalize_number(field: &[u8]) -> ByteString {
+    str::from_utf8(field).ok()
+        .and_then(|s| util::normalize_number(s.trim()))
+        .map_or_else(|| field.to_vec(), |n| n.into_bytes())
+}
@@ -0,0 +1,106 @@
+use csv;
+use rand::{self, Rng};
+
+use CliResult;
+use config::{Config, Delimiter};
+use util;
+
+static USAGE: &'static str = "
+Adds a column to the start of each record with its index, i.e., a row number.
+The first record has the index 0 by default, which can be changed with
+--start.
+
+Instead of an index, each record can be given the same value with --constant,
+or a random UUID (version 4) with --uuid.
+
+The new column is named 'index' (or 'uuid' with --uuid), unless the name is
+given with --column-name.
+
+Usage:
+    xsv enumerate [options] [<input>]
+    xsv enumerate --help
+
+enumerate options:
+    -c, --column-name <name>  The name of the new column.
+    --start <n>               The index of the first record. [default: 0]
+    --constant <value>        Fill the new column with this value instead
+                              of an index.
+    --uuid                    Fill the new column with a random UUID instead
+                              of an index.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers, and no header is written for the new
+                           column.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    flag_column_name: Option<String>,
+    flag_start: u64,
+    flag_constant: Option<String>,
+    flag_uuid: bool,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_constant.is_some() && args.flag_uuid {
+        return fail!("--constant and --uuid cannot be used together.");
+    }
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+
+    if !rconfig.no_headers {
+        let default_name = if args.flag_uuid { "uuid" } else { "index" };
+        let name = args.flag_column_name.as_ref().map_or(default_name, |s| s);
+        let mut headers = csv::ByteRecord::new();
+        headers.push_field(name.as_bytes());
+        headers.extend(rdr.byte_headers()?);
+        wtr.write_byte_record(&headers)?;
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut index = args.flag_start;
+    let mut record = csv::ByteRecord::new();
+    let mut enumerated = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        enumerated.clear();
+        if let Some(ref constant) = args.flag_constant {
+            enumerated.push_field(constant.as_bytes());
+        } else if args.flag_uuid {
+            enumerated.push_field(uuid(&mut rng).as_bytes());
+        } else {
+            enumerated.push_field(index.to_string().as_bytes());
+            index += 1;
+        }
+        enumerated.extend(&record);
+        wtr.write_byte_record(&enumerated)?;
+    }
+    Ok(wtr.flush()?)
+}
+
+/// Returns a random (version 4) UUID, e.g.,
+/// `1e5e2a4c-6b23-4d7e-9a3f-0c6f0e8b2d51`.
+fn uuid<R: Rng>(rng: &mut R) -> String {
+    let mut bytes = [0u8; 16];
+    rng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: Vec<String> =
+        bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}",
+            hex[0..4].concat(), hex[4..6].concat(), hex[6..8].concat(),
+            hex[8..10].concat(), hex[10..].concat())
+}
@@ -0,0 +1,168 @@
+use std::io;
+
+use csv;
+
+use CliResult;
+use config::{Config, Delimiter};
+use select::{SelectColumns, Selection};
+use util;
+
+static USAGE: &'static str = "
+Fills empty fields in the selected columns with the closest value above them
+in the same column, i.e., the last value that wasn't empty.
+
+With --backfill, empty fields are instead filled with the closest value below
+them. This requires buffering records in memory until the next value of every
+selected column (or the end of the group) is found.
+
+When --groupby is given, values aren't carried over from one group to the
+next, where a group is a run of consecutive records with the same values in
+the --groupby columns. (So the data should be sorted by those columns.)
+
+Any fields that are still empty afterwards, because there was no value to fill
+them with, are filled with --default, if given.
+
+  Fill the price of each day with the last known price:
+  $ xsv fill price prices.csv
+
+  Do the same for each stock separately:
+  $ xsv fill --groupby symbol price prices.csv
+
+Usage:
+    xsv fill [options] [--] <selection> [<input>]
+    xsv fill --help
+
+fill options:
+    -b, --backfill         Fill empty fields with the next value below them
+                           instead of the last value above them.
+    -g, --groupby <cols>   Don't fill values across groups of consecutive
+                           records with the same values in these columns.
+                           See 'xsv select --help' for the format details.
+    -v, --default <value>  Fill any fields that are still empty with this
+                           value.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. (i.e., It will be filled like any
+                           other record.)
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    arg_selection: SelectColumns,
+    flag_backfill: bool,
+    flag_groupby: Option<SelectColumns>,
+    flag_default: Option<String>,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers)
+        .select(args.arg_selection.clone());
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+
+    let headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+    let group_sel = match args.flag_groupby {
+        None => None,
+        Some(ref cols) => Some(cols.selection(&headers, !rconfig.no_headers)?),
+    };
+    rconfig.write_headers(&mut rdr, &mut wtr)?;
+
+    let default = args.flag_default.as_ref().map(|v| v.as_bytes().to_vec());
+    let mut filler = Filler::new(sel, default);
+    let mut group: Option<Vec<Vec<u8>>> = None;
+    let mut buffered = vec![];
+    let mut record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        if let Some(ref group_sel) = group_sel {
+            let key: Vec<Vec<u8>> =
+                group_sel.select(&record).map(|f| f.to_vec()).collect();
+            if group.as_ref().map_or(false, |group| *group != key) {
+                filler.backfill(&mut wtr, &mut buffered)?;
+                filler.reset();
+            }
+            group = Some(key);
+        }
+        if !args.flag_backfill {
+            wtr.write_byte_record(&filler.fill(&record))?;
+            continue;
+        }
+        buffered.push(record.clone());
+        // Once every selected field of a record has a value, none of the
+        // records above it need to look any further down.
+        if filler.sel.iter().all(|&i| !record[i].is_empty()) {
+            filler.backfill(&mut wtr, &mut buffered)?;
+            filler.reset();
+        }
+    }
+    filler.backfill(&mut wtr, &mut buffered)?;
+    Ok(wtr.flush()?)
+}
+
+struct Filler {
+    sel: Selection,
+    default: Option<Vec<u8>>,
+    /// The value to fill each selected column with, if one has been seen.
+    values: Vec<Option<Vec<u8>>>,
+}
+
+impl Filler {
+    fn new(sel: Selection, default: Option<Vec<u8>>) -> Filler {
+        let values = vec![None; sel.len()];
+        Filler { sel: sel, default: default, values: values }
+    }
+
+    /// Forgets every value seen so far.
+    fn reset(&mut self) {
+        for v in &mut self.values {
+            *v = None;
+        }
+    }
+
+    /// Returns a copy of `record` whose empty selected fields are filled
+    /// with the values seen so far, and then remembers its values.
+    fn fill(&mut self, record: &csv::ByteRecord) -> csv::ByteRecord {
+        let mut fields: Vec<Vec<u8>> =
+            record.iter().map(|f| f.to_vec()).collect();
+        for (j, &i) in self.sel.iter().enumerate() {
+            if record[i].is_empty() {
+                match self.values[j].as_ref().or(self.default.as_ref()) {
+                    None => {}
+                    Some(value) => fields[i] = value.clone(),
+                }
+            } else {
+                self.values[j] = Some(record[i].to_vec());
+            }
+        }
+        csv::ByteRecord::from(fields)
+    }
+
+    /// Fills and writes the `buffered` records, by filling them from the
+    /// last one to the first one.
+    fn backfill<W: io::Write>(
+        &mut self,
+        wtr: &mut csv::Writer<W>,
+        buffered: &mut Vec<csv::ByteRecord>,
+    ) -> CliResult<()> {
+        let filled: Vec<_> =
+            buffered.iter().rev().map(|r| self.fill(r)).collect();
+        for record in filled.iter().rev() {
+            wtr.write_byte_record(record)?;
+        }
+        buffered.clear();
+        Ok(())
+    }
+}
@@ -16,7 +16,9 @@ record size and one for the actual transform. Because of this, the input
 given must be a file and not stdin.
 
 Alternatively, if --length is set, then all records are forced to that length.
-This requires a single pass and can be done with stdin.
+This requires a single pass and can be done with stdin. Longer records are
+truncated, unless --no-truncate is set, in which case it is an error for a
+record to have a non-empty field past that length.
 
 Usage:
     xsv fixlengths [options] [<input>]
@@ -25,6 +27,8 @@ fixlengths options:
     -l, --length <arg>     Forcefully set the length of each record. If a
                            record is not the size given, then it is truncated
                            or expanded as appropriate.
+    --no-truncate          Fail instead of dropping non-empty fields from
+                           records that are longer than the length.
 
 Common options:
     -h, --help             Display this message
@@ -37,6 +41,7 @@ Common options:
 struct Args {
     arg_input: Option<String>,
     flag_length: Option<usize>,
+    flag_no_truncate: bool,
     flag_output: Option<String>,
     flag_delimiter: Option<Delimiter>,
 }
@@ -79,13 +84,19 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     let mut rdr = config.reader()?;
     let mut wtr = Config::new(&args.flag_output).writer()?;
-    for r in rdr.byte_records() {
+    for (i, r) in rdr.byte_records().enumerate() {
         let mut r = r?;
         if length >= r.len() {
             for _ in r.len()..length {
                 r.push_field(b"");
             }
         } else {
+            if args.flag_no_truncate
+                    && r.iter().skip(length).any(|f| !f.is_empty()) {
+                return fail!(format!(
+                    "Record {} has {} fields, which is more than the \
+                     length of {}.", i + 1, r.len(), length));
+            }
             r.truncate(length);
         }
         wtr.write_byte_record(&r)?;
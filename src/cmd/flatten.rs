@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::io::{self, Write};
 
+use csv;
 use tabwriter::TabWriter;
 
 use CliResult;
@@ -13,6 +14,11 @@ This mode is particularly useful for viewing one record at a time. Each
 record is separated by a special '#' character (on a line by itself), which
 can be changed with the --separator flag.
 
+The names and values of the fields are aligned in two columns. When a field
+separator is given, each name is instead followed by the separator and then
+the value, without any alignment, which is easier to process with tools like
+grep or cut.
+
 There is also a condensed view (-c or --condense) that will shorten the
 contents of each field to provide a summary view.
 
@@ -20,7 +26,7 @@ Usage:
     xsv flatten [options] [<input>]
 
 flatten options:
-    -c, --condense <arg>  Limits the length of each field to the value
+    -c, --condense <arg>   Limits the length of each field to the value
                            specified. If the field is UTF-8 encoded, then
                            <arg> refers to the number of code points.
                            Otherwise, it refers to the number of bytes.
@@ -28,6 +34,12 @@ flatten options:
                            When non-empty, a new line is automatically
                            appended to the separator.
                            [default: #]
+    -r, --record-separator <arg>
+                           The same as --separator, which it overrides.
+    -f, --field-separator <arg>
+                           A string of characters to write between the name
+                           and the value of each field, instead of aligning
+                           them.
 
 Common options:
     -h, --help             Display this message
@@ -43,6 +55,8 @@ struct Args {
     arg_input: Option<String>,
     flag_condense: Option<usize>,
     flag_separator: String,
+    flag_record_separator: Option<String>,
+    flag_field_separator: Option<String>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
 }
@@ -52,29 +66,46 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
         .no_headers(args.flag_no_headers);
-    let mut rdr = rconfig.reader()?;
-    let headers = rdr.byte_headers()?.clone();
-
-    let mut wtr = TabWriter::new(io::stdout());
-    let mut first = true;
-    for r in rdr.byte_records() {
-        if !first && !args.flag_separator.is_empty() {
-            writeln!(&mut wtr, "{}", args.flag_separator)?;
+    let rdr = rconfig.reader()?;
+    match args.flag_field_separator {
+        None => args.flatten(rdr, TabWriter::new(io::stdout()), "\t"),
+        Some(ref sep) => {
+            args.flatten(rdr, io::BufWriter::new(io::stdout()), sep)
         }
-        first = false;
-        let r = r?;
-        for (i, (header, field)) in headers.iter().zip(&r).enumerate() {
-            if rconfig.no_headers {
-                write!(&mut wtr, "{}", i)?;
-            } else {
-                wtr.write_all(&header)?;
+    }
+}
+
+impl Args {
+    fn flatten<R: io::Read, W: Write>(
+        &self,
+        mut rdr: csv::Reader<R>,
+        mut wtr: W,
+        field_separator: &str,
+    ) -> CliResult<()> {
+        let headers = rdr.byte_headers()?.clone();
+        let record_separator = self.flag_record_separator
+            .as_ref()
+            .unwrap_or(&self.flag_separator);
+        let mut first = true;
+        for r in rdr.byte_records() {
+            if !first && !record_separator.is_empty() {
+                writeln!(&mut wtr, "{}", record_separator)?;
+            }
+            first = false;
+            let r = r?;
+            for (i, (header, field)) in headers.iter().zip(&r).enumerate() {
+                if self.flag_no_headers {
+                    write!(&mut wtr, "{}", i)?;
+                } else {
+                    wtr.write_all(&header)?;
+                }
+                wtr.write_all(field_separator.as_bytes())?;
+                wtr.write_all(&*util::condense(
+                    Cow::Borrowed(&*field), self.flag_condense))?;
+                wtr.write_all(b"\n")?;
             }
-            wtr.write_all(b"\t")?;
-            wtr.write_all(&*util::condense(
-                Cow::Borrowed(&*field), args.flag_condense))?;
-            wtr.write_all(b"\n")?;
         }
+        wtr.flush()?;
+        Ok(())
     }
-    wtr.flush()?;
-    Ok(())
 }
@@ -13,6 +13,11 @@ pipe multiple xsv commands together. However, you may want the final result to
 have a specific delimiter or record separator, and this is where 'xsv fmt' is
 useful.
 
+By default, values are only quoted when they need to be. With --quote-never,
+values are never quoted, so any delimiters, quotes or line endings in them are
+written as is, unless an escape character is given. In that case, each of
+them (and the escape character itself) is preceded by the escape character.
+
 Usage:
     xsv fmt [options] [<input>]
 
@@ -20,11 +25,18 @@ fmt options:
     -t, --out-delimiter <arg>  The field delimiter for writing CSV data.
                                [default: ,]
     --crlf                     Use '\\r\\n' line endings in the output.
+    --terminator <arg>         The record terminator to write. This is either
+                               '\\r\\n' or a single ASCII character, where
+                               '\\n', '\\r' and '\\t' are also accepted.
+                               Line endings inside quoted values are never
+                               changed.
     --ascii                    Use ASCII field and record separators.
     --quote <arg>              The quote character to use. [default: \"]
     --quote-always             Put quotes around every value.
+    --quote-never              Never put quotes around values.
     --escape <arg>             The escape character to use. When not specified,
-                               quotes are escaped by doubling them.
+                               quotes are escaped by doubling them. (See
+                               above for how it works with --quote-never.)
 
 Common options:
     -h, --help             Display this message
@@ -38,17 +50,25 @@ struct Args {
     arg_input: Option<String>,
     flag_out_delimiter: Option<Delimiter>,
     flag_crlf: bool,
+    flag_terminator: Option<String>,
     flag_ascii: bool,
     flag_output: Option<String>,
     flag_delimiter: Option<Delimiter>,
     flag_quote: Delimiter,
     flag_quote_always: bool,
+    flag_quote_never: bool,
     flag_escape: Option<Delimiter>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
-
+    if args.flag_quote_always && args.flag_quote_never {
+        return fail!("--quote-always and --quote-never cannot be used \
+                      together.");
+    }
+    if args.flag_terminator.is_some() && (args.flag_crlf || args.flag_ascii) {
+        return fail!("--terminator cannot be used with --crlf or --ascii.");
+    }
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
         .no_headers(true);
@@ -61,9 +81,19 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
             .delimiter(Some(Delimiter(b'\x1f')))
             .terminator(csv::Terminator::Any(b'\x1e'));
     }
+    let terminator = match args.flag_terminator {
+        None => None,
+        Some(ref term) => Some(parse_terminator(term)?),
+    };
+    if let Some(term) = terminator {
+        wconfig = wconfig.terminator(term);
+    }
     if args.flag_quote_always {
         wconfig = wconfig.quote_style(csv::QuoteStyle::Always);
     }
+    if args.flag_quote_never {
+        wconfig = wconfig.quote_style(csv::QuoteStyle::Never);
+    }
     if let Some(escape) = args.flag_escape {
         wconfig = wconfig.escape(escape.as_byte()).double_quote(false);
     }
@@ -72,10 +102,66 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     let mut rdr = rconfig.reader()?;
     let mut wtr = wconfig.writer()?;
+    let escaper = match args.flag_escape {
+        Some(escape) if args.flag_quote_never => {
+            let mut special = vec![
+                escape.as_byte(), args.flag_quote.as_byte(),
+                wconfig.get_delimiter(), b'\r', b'\n',
+            ];
+            if args.flag_ascii {
+                special.push(b'\x1e');
+            }
+            if let Some(csv::Terminator::Any(b)) = terminator {
+                special.push(b);
+            }
+            Some(Escaper { escape: escape.as_byte(), special: special })
+        }
+        _ => None,
+    };
     let mut r = csv::ByteRecord::new();
     while rdr.read_byte_record(&mut r)? {
-        wtr.write_byte_record(&r)?;
+        match escaper {
+            None => wtr.write_byte_record(&r)?,
+            Some(ref escaper) => {
+                wtr.write_record(r.iter().map(|f| escaper.escape(f)))?
+            }
+        }
     }
     wtr.flush()?;
     Ok(())
 }
+
+/// Parses the argument of --terminator.
+fn parse_terminator(term: &str) -> CliResult<csv::Terminator> {
+    Ok(match term {
+        r"\r\n" | "\r\n" => csv::Terminator::CRLF,
+        r"\n" => csv::Terminator::Any(b'\n'),
+        r"\r" => csv::Terminator::Any(b'\r'),
+        r"\t" => csv::Terminator::Any(b'\t'),
+        s if s.len() == 1 => csv::Terminator::Any(s.as_bytes()[0]),
+        s => {
+            return fail!(format!("Could not convert '{}' to a record \
+                                  terminator.", s));
+        }
+    })
+}
+
+/// Escaper precedes every special byte in a field with an escape byte, for
+/// writing fields without quotes.
+struct Escaper {
+    escape: u8,
+    special: Vec<u8>,
+}
+
+impl Escaper {
+    fn escape(&self, field: &[u8]) -> Vec<u8> {
+        let mut escaped = Vec::with_capacity(field.len());
+        for &b in field {
+            if self.special.contains(&b) {
+                escaped.push(self.escape);
+            }
+            escaped.push(b);
+        }
+        escaped
+    }
+}
@@ -1,9 +1,13 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::collections::hash_map::{HashMap, Entry};
 use std::fs;
 use std::io;
+use std::str;
 
 use chan;
 use csv;
-use stats::{Frequencies, merge_all};
+use stats::{Commute, merge_all};
 use threadpool::ThreadPool;
 
 use CliResult;
@@ -19,15 +23,29 @@ The frequency table is formatted as CSV data:
 
     field,value,count
 
+With --percentage, a fourth column is added with the share of each value, in
+percent.
+
 By default, there is a row for the N most frequent values for each field in the
 data. The order and number of values can be tweaked with --asc and --limit,
 respectively.
 
 Since this computes an exact frequency table, memory proportional to the
-cardinality of each column is required.
+cardinality of each column is required. When that's too much, --approx
+computes an approximate frequency table with a bounded number of values
+instead. Its 'count' column is then replaced by 'count_lower' and
+'count_upper' columns, between which the real count of each value lies. (The
+values with the largest counts are the most likely to be kept and to have
+accurate counts.)
+
+Alternatively, --memory only switches to an approximate frequency table when
+a column has more than the given number of distinct values. Columns with
+fewer values are still counted exactly. The output has the 'count_lower' and
+'count_upper' columns if any of the tables is approximate, and a message is
+written to stderr.
 
 Usage:
-    xsv frequency [options] [<input>]
+    xsv frequency [options] [--null-value <arg>]... [<input>]
 
 frequency options:
     -s, --select <arg>     Select a subset of columns to compute frequencies
@@ -38,9 +56,52 @@ frequency options:
     -l, --limit <arg>      Limit the frequency table to the N most common
                            items. Set to '0' to disable a limit.
                            [default: 10]
+    --other                When --limit drops values from a frequency
+                           table, add a last row with the value 'Other (N)'
+                           whose count is the total count of the N values
+                           that were dropped.
     -a, --asc              Sort the frequency tables in ascending order by
                            count. The default is descending order.
+                           Values with the same count are always sorted in
+                           ascending order by value.
+    --sort <by>            How to order each frequency table for display,
+                           either by 'count' or by 'value'. Values are
+                           ordered as numbers if every value of the field is
+                           a number, and as strings otherwise. Whatever the
+                           display order is, the values kept by --limit are
+                           always the N most common ones (or the least
+                           common ones with --asc). [default: count]
+    --reverse              Reverse the display order of each frequency
+                           table.
     --no-nulls             Don't include NULLs in the frequency table.
+    -c, --combine          Count the combinations of values of the selected
+                           columns, instead of the values of each column.
+                           The table then has a column for each selected
+                           column instead of 'field' and 'value'.
+                           Combinations with a NULL are skipped when NULLs
+                           are excluded with --no-nulls.
+    --approx <k>           Keep at most k values in each frequency table.
+                           Its counts are then estimates, with an error of
+                           at most N/k where N is the total count.
+    --memory <n>           Count exactly, unless a frequency table gets more
+                           than n values. Then it is approximated as with
+                           '--approx n'.
+    -w, --weight <column>  Add up the numbers in this column for each value,
+                           instead of counting the records with the value.
+                           Records whose weight is empty or not a number are
+                           skipped. Counts are written as integers unless
+                           some weight isn't an integer.
+    --weight-missing-one   Give a weight of 1 to records whose weight is
+                           empty or not a number, instead of skipping them.
+    -p, --percentage       Add a 'percentage' column with each value's share
+                           of the total count of its field. NULLs are
+                           included in the total unless --no-nulls is set.
+    --decimals <n>         The number of decimals of the percentages.
+                           [default: 2]
+    --null-value <arg>     Treat fields with this value as NULL, in addition
+                           to empty fields. May be given more than once.
+    --ignore-case          Match the values given by --null-value
+                           case insensitively.
     -j, --jobs <arg>       The number of jobs to run in parallel.
                            This works better when the given CSV data has
                            an index already created. Note that a file handle
@@ -65,8 +126,20 @@ struct Args {
     arg_input: Option<String>,
     flag_select: SelectColumns,
     flag_limit: usize,
+    flag_other: bool,
     flag_asc: bool,
+    flag_sort: SortBy,
+    flag_reverse: bool,
     flag_no_nulls: bool,
+    flag_combine: bool,
+    flag_weight: Option<SelectColumns>,
+    flag_weight_missing_one: bool,
+    flag_approx: Option<usize>,
+    flag_memory: Option<usize>,
+    flag_percentage: bool,
+    flag_decimals: usize,
+    flag_null_value: Vec<String>,
+    flag_ignore_case: bool,
     flag_jobs: usize,
     flag_output: Option<String>,
     flag_no_headers: bool,
@@ -75,6 +148,15 @@ struct Args {
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_approx == Some(0) {
+        return fail!("--approx must be greater than 0.");
+    }
+    if args.flag_memory == Some(0) {
+        return fail!("--memory must be greater than 0.");
+    }
+    if args.flag_approx.is_some() && args.flag_memory.is_some() {
+        return fail!("--approx and --memory cannot be used together.");
+    }
     let rconfig = args.rconfig();
 
     let mut wtr = Config::new(&args.flag_output).writer()?;
@@ -83,26 +165,225 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         _ => args.sequential_ftables(),
     }?;
 
-    wtr.write_record(vec!["field", "value", "count"])?;
-    let head_ftables = headers.into_iter().zip(tables.into_iter());
-    for (i, (header, ftab)) in head_ftables.enumerate() {
-        let mut header = header.to_vec();
+    let names: Vec<ByteString> = headers.iter().enumerate().map(|(i, h)| {
         if rconfig.no_headers {
-            header = (i+1).to_string().into_bytes();
+            (i+1).to_string().into_bytes()
+        } else {
+            h.to_vec()
         }
-        for (value, count) in args.counts(&ftab).into_iter() {
-            let count = count.to_string();
-            let row = vec![&*header, &*value, count.as_bytes()];
+    }).collect();
+    let mut fields = if args.flag_combine {
+        names.clone()
+    } else {
+        vec![b"field".to_vec(), b"value".to_vec()]
+    };
+    let approx = args.flag_approx.is_some()
+                 || tables.iter().any(|ftab| ftab.slots.is_some());
+    if approx && args.flag_memory.is_some() {
+        werr!("Some frequency tables had more than {} values, so their \
+               counts are approximate.", args.flag_memory.unwrap());
+    }
+    if approx {
+        fields.push(b"count_lower".to_vec());
+        fields.push(b"count_upper".to_vec());
+    } else {
+        fields.push(b"count".to_vec());
+    }
+    if args.flag_percentage {
+        fields.push(b"percentage".to_vec());
+    }
+    wtr.write_record(fields)?;
+    let nkeys = if args.flag_combine { names.len() } else { 1 };
+    for (i, ftab) in tables.iter().enumerate() {
+        let (counts, total) = args.counts(ftab, nkeys);
+        for (values, count) in counts.into_iter() {
+            let mut row = vec![];
+            if !args.flag_combine {
+                row.push(names[i].clone());
+            }
+            row.extend(values);
+            if approx {
+                let lower = count.count - count.error;
+                row.push(ftab.format(lower).into_bytes());
+            }
+            row.push(ftab.format(count.count).into_bytes());
+            if args.flag_percentage {
+                row.push(args.percentage(count.count, total).into_bytes());
+            }
             wtr.write_record(row)?;
         }
     }
     Ok(())
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, RustcDecodable)]
+enum SortBy {
+    Count,
+    Value,
+}
+
 type ByteString = Vec<u8>;
 type Headers = csv::ByteRecord;
-type FTable = Frequencies<Vec<u8>>;
-type FTables = Vec<Frequencies<Vec<u8>>>;
+type FTables = Vec<FTable>;
+
+/// A frequency table, where the count of each value is the sum of the
+/// weights of its occurrences. (Every weight is 1 without --weight.)
+///
+/// With --approx, the table keeps at most `slots` values, using the
+/// Space-Saving algorithm: when a new value comes in and the table is full,
+/// it replaces the value with the smallest count, and inherits its count as
+/// the error of its own.
+///
+/// With --memory, the table is exact until it has more than `memory` values,
+/// and then it keeps `memory` values as with --approx.
+#[derive(Clone)]
+struct FTable {
+    counts: HashMap<ByteString, Count>,
+    /// The values ordered by count and then by value, so that the value to
+    /// replace is always the first one. It is only kept up to date when
+    /// `slots` is set.
+    by_count: BTreeSet<(Key, ByteString)>,
+    slots: Option<usize>,
+    memory: Option<usize>,
+    /// Whether any of the weights isn't an integer.
+    fractional: bool,
+}
+
+/// The count of a value, which is exact when `error` is zero. Otherwise, the
+/// real count is somewhere between `count - error` and `count`.
+#[derive(Clone, Copy, Debug, Default)]
+struct Count {
+    count: f64,
+    error: f64,
+}
+
+/// A count that can be ordered. (Counts are never NaN, since weights are
+/// finite.)
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+struct Key(f64);
+
+impl Eq for Key {}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Key) -> Ordering {
+        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl FTable {
+    fn new(slots: Option<usize>, memory: Option<usize>) -> FTable {
+        FTable {
+            counts: HashMap::new(),
+            by_count: BTreeSet::new(),
+            slots: slots,
+            memory: memory,
+            fractional: false,
+        }
+    }
+
+    fn add(&mut self, value: ByteString, weight: f64) {
+        if weight.fract() != 0.0 {
+            self.fractional = true;
+        }
+        if let Some(c) = self.counts.get_mut(&value) {
+            if self.slots.is_some() {
+                self.by_count.remove(&(Key(c.count), value.clone()));
+                self.by_count.insert((Key(c.count + weight), value));
+            }
+            c.count += weight;
+            return;
+        }
+        if self.memory.map_or(false, |m| self.counts.len() >= m)
+                && self.slots != self.memory {
+            self.slots = self.memory;
+            self.index();
+        }
+        let full = self.slots.map_or(false, |k| self.counts.len() >= k);
+        let mut new = Count { count: weight, error: 0.0 };
+        if full {
+            let evicted = self.evict();
+            new = Count {
+                count: evicted.count + weight,
+                error: evicted.count,
+            };
+        }
+        if self.slots.is_some() {
+            self.by_count.insert((Key(new.count), value.clone()));
+        }
+        self.counts.insert(value, new);
+    }
+
+    /// Orders all of the values by count, for when `slots` gets set.
+    fn index(&mut self) {
+        self.by_count = self.counts.iter()
+            .map(|(v, c)| (Key(c.count), v.clone()))
+            .collect();
+    }
+
+    /// Removes the value with the smallest count, and returns its count.
+    fn evict(&mut self) -> Count {
+        let first = self.by_count.iter().next().cloned().unwrap();
+        self.by_count.remove(&first);
+        self.counts.remove(&first.1).unwrap()
+    }
+
+    /// Returns the count that a value missing from this table may have.
+    fn missing(&self) -> f64 {
+        let full = self.slots.map_or(false, |k| self.counts.len() >= k);
+        if !full {
+            return 0.0;
+        }
+        self.by_count.iter().next().map_or(0.0, |&(Key(count), _)| count)
+    }
+
+    fn format(&self, count: f64) -> String {
+        if self.fractional {
+            count.to_string()
+        } else {
+            format!("{:.0}", count)
+        }
+    }
+}
+
+impl Commute for FTable {
+    fn merge(&mut self, other: FTable) {
+        // A value missing from one of the tables may still have occurred
+        // in its chunk, as much as that table's smallest count.
+        let (missing1, missing2) = (self.missing(), other.missing());
+        if other.slots.is_some() {
+            self.slots = other.slots;
+        }
+        for c in self.counts.values_mut() {
+            c.count += missing2;
+            c.error += missing2;
+        }
+        for (value, c2) in other.counts {
+            match self.counts.entry(value) {
+                Entry::Occupied(mut e) => {
+                    let c1 = e.get_mut();
+                    c1.count += c2.count - missing2;
+                    c1.error += c2.error - missing2;
+                }
+                Entry::Vacant(e) => {
+                    e.insert(Count {
+                        count: c2.count + missing1,
+                        error: c2.error + missing1,
+                    });
+                }
+            }
+        }
+        if self.memory.map_or(false, |m| self.counts.len() > m) {
+            self.slots = self.memory;
+        }
+        if let Some(k) = self.slots {
+            self.index();
+            while self.counts.len() > k {
+                self.evict();
+            }
+        }
+        self.fractional = self.fractional || other.fractional;
+    }
+}
 
 impl Args {
     fn rconfig(&self) -> Config {
@@ -112,34 +393,82 @@ impl Args {
             .select(self.flag_select.clone())
     }
 
-    fn counts(&self, ftab: &FTable) -> Vec<(ByteString, u64)> {
-        let mut counts = if self.flag_asc {
-            ftab.least_frequent()
-        } else {
-            ftab.most_frequent()
-        };
-        if self.flag_limit > 0 {
-            counts = counts.into_iter().take(self.flag_limit).collect();
-        }
-        counts.into_iter().map(|(bs, c)| {
-            if b"" == &**bs {
-                (b"(NULL)"[..].to_vec(), c)
+    /// Returns the rows of the frequency table of `ftab`, along with the
+    /// total count of all of its values.
+    ///
+    /// Each row has `nkeys` values, which is more than one only for the
+    /// table of tuples built by --combine.
+    fn counts(&self, ftab: &FTable, nkeys: usize)
+             -> (Vec<(Vec<ByteString>, Count)>, f64) {
+        let mut counts: Vec<_> =
+            ftab.counts.iter().map(|(v, &c)| (v, c)).collect();
+        // Break ties by the value itself, so that output is stable.
+        counts.sort_by(|&(v1, c1), &(v2, c2)| {
+            let by_count = if self.flag_asc {
+                c1.count.partial_cmp(&c2.count)
             } else {
-                (bs.clone(), c)
+                c2.count.partial_cmp(&c1.count)
+            };
+            by_count.unwrap_or(Ordering::Equal).then(v1.cmp(v2))
+        });
+        // Sum in a fixed order, so that weighted totals are stable too.
+        let total = counts.iter().map(|&(_, c)| c.count).sum::<f64>();
+        // Whether values are numbers is decided before applying --limit, so
+        // that the order doesn't depend on which values are dropped.
+        let numeric = !self.flag_combine && counts.iter().all(|&(v, _)| {
+            v.is_empty() || parse_number(v).is_some()
+        });
+        let mut other = None;
+        if self.flag_limit > 0 && counts.len() > self.flag_limit {
+            let rest = counts.split_off(self.flag_limit);
+            if self.flag_other {
+                let total = Count {
+                    count: rest.iter().map(|&(_, c)| c.count).sum(),
+                    error: rest.iter().map(|&(_, c)| c.error).sum(),
+                };
+                other = Some((format!("Other ({})", rest.len()), total));
             }
-        }).collect()
+        }
+        if self.flag_sort == SortBy::Value {
+            sort_by_value(&mut counts, numeric);
+        }
+        if self.flag_reverse {
+            counts.reverse();
+        }
+        let mut counts: Vec<_> = counts.into_iter().map(|(bs, c)| {
+            let values = if self.flag_combine {
+                split_tuple(bs)
+            } else {
+                vec![bs.clone()]
+            };
+            let values = values.into_iter().map(|v| {
+                if v.is_empty() { b"(NULL)"[..].to_vec() } else { v }
+            }).collect();
+            (values, c)
+        }).collect();
+        if let Some((value, total)) = other {
+            let mut values = vec![vec![]; nkeys];
+            values[0] = value.into_bytes();
+            counts.push((values, total));
+        }
+        (counts, total)
+    }
+
+    fn percentage(&self, count: f64, total: f64) -> String {
+        let pct = if total == 0.0 { 0.0 } else { 100.0 * count / total };
+        format!("{:.*}", self.flag_decimals, pct)
     }
 
     fn sequential_ftables(&self) -> CliResult<(Headers, FTables)> {
         let mut rdr = self.rconfig().reader()?;
-        let (headers, sel) = self.sel_headers(&mut rdr)?;
-        Ok((headers, self.ftables(&sel, rdr.byte_records())?))
+        let (headers, sel, weight) = self.sel_headers(&mut rdr)?;
+        Ok((headers, self.ftables(&sel, weight, rdr.byte_records())?))
     }
 
     fn parallel_ftables(&self, idx: &mut Indexed<fs::File, fs::File>)
                        -> CliResult<(Headers, FTables)> {
         let mut rdr = self.rconfig().reader()?;
-        let (headers, sel) = self.sel_headers(&mut rdr)?;
+        let (headers, sel, weight) = self.sel_headers(&mut rdr)?;
 
         if idx.count() == 0 {
             return Ok((headers, vec![]));
@@ -156,28 +485,61 @@ impl Args {
                 let mut idx = args.rconfig().indexed().unwrap().unwrap();
                 idx.seek((i * chunk_size) as u64).unwrap();
                 let it = idx.byte_records().take(chunk_size);
-                send.send(args.ftables(&sel, it).unwrap());
+                send.send((i, args.ftables(&sel, weight, it).unwrap()));
             });
         }
         drop(send);
-        Ok((headers, merge_all(recv.iter()).unwrap()))
+        // Merge the chunks in order, so that weighted counts are summed in
+        // the same order every time.
+        let mut chunks: Vec<_> = recv.iter().collect();
+        chunks.sort_by_key(|&(i, _)| i);
+        let tables = merge_all(chunks.into_iter().map(|(_, t)| t)).unwrap();
+        Ok((headers, tables))
     }
 
-    fn ftables<I>(&self, sel: &Selection, it: I) -> CliResult<FTables>
+    fn ftables<I>(&self, sel: &Selection, weight: Option<usize>, it: I)
+                 -> CliResult<FTables>
             where I: Iterator<Item=csv::Result<csv::ByteRecord>> {
         let null = &b""[..].to_vec();
+        let nulls = util::NullValues::new(
+            &self.flag_null_value, self.flag_ignore_case);
         let nsel = sel.normal();
+        let ntabs = if self.flag_combine { 1 } else { nsel.len() };
         let mut tabs: Vec<_> =
-            (0..nsel.len()).map(|_| Frequencies::new()).collect();
+            (0..ntabs).map(|_| FTable::new(self.flag_approx, self.flag_memory)).collect();
         for row in it {
             let row = row?;
+            let w = match weight {
+                None => 1.0,
+                Some(j) => {
+                    match parse_number(&trim(row[j].to_vec())) {
+                        Some(w) if w.is_finite() => w,
+                        _ if self.flag_weight_missing_one => 1.0,
+                        _ => continue,
+                    }
+                }
+            };
+            if self.flag_combine {
+                let fields: Vec<_> = nsel.select(row.into_iter())
+                                         .map(|f| trim(f.to_vec()))
+                                         .collect();
+                let has_null = fields.iter().any(|f| nulls.is_null(f));
+                if has_null && self.flag_no_nulls {
+                    continue;
+                }
+                let fields: Vec<_> = fields.into_iter().map(|f| {
+                    if nulls.is_null(&f) { vec![] } else { f }
+                }).collect();
+                tabs[0].add(join_tuple(&fields), w);
+                continue;
+            }
             for (i, field) in nsel.select(row.into_iter()).enumerate() {
                 let field = trim(field.to_vec());
-                if !field.is_empty() {
-                    tabs[i].add(field);
+                if !nulls.is_null(&field) {
+                    tabs[i].add(field, w);
                 } else {
                     if !self.flag_no_nulls {
-                        tabs[i].add(null.clone());
+                        tabs[i].add(null.clone(), w);
                     }
                 }
             }
@@ -186,15 +548,85 @@ impl Args {
     }
 
     fn sel_headers<R: io::Read>(&self, rdr: &mut csv::Reader<R>)
-                  -> CliResult<(csv::ByteRecord, Selection)> {
+                  -> CliResult<(csv::ByteRecord, Selection, Option<usize>)> {
         let headers = rdr.byte_headers()?;
         let sel = self.rconfig().selection(headers)?;
-        Ok((sel.select(headers).map(|h| h.to_vec()).collect(), sel))
+        let weight = match self.flag_weight {
+            None => None,
+            Some(ref cols) => {
+                let wsel = cols.selection(headers, !self.flag_no_headers)?;
+                if wsel.len() != 1 {
+                    return fail!("--weight must select exactly one column.");
+                }
+                Some(wsel[0])
+            }
+        };
+        let headers = sel.select(headers).map(|h| h.to_vec()).collect();
+        Ok((headers, sel, weight))
     }
 
     fn njobs(&self) -> usize {
-        if self.flag_jobs == 0 { util::num_cpus() } else { self.flag_jobs }
+        util::njobs(self.flag_jobs)
+    }
+}
+
+/// Encodes a tuple of values as one value, such that tuples compare in the
+/// same order as their encodings.
+///
+/// Values are separated by `\x00\x00`, and every `\x00` in a value is
+/// written as `\x00\x01`.
+fn join_tuple(values: &[ByteString]) -> ByteString {
+    let mut joined = vec![];
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            joined.extend_from_slice(b"\x00\x00");
+        }
+        for &b in value {
+            if b == b'\x00' {
+                joined.extend_from_slice(b"\x00\x01");
+            } else {
+                joined.push(b);
+            }
+        }
     }
+    joined
+}
+
+/// Decodes a tuple encoded by `join_tuple`.
+fn split_tuple(joined: &[u8]) -> Vec<ByteString> {
+    let mut values = vec![vec![]];
+    let mut bytes = joined.iter();
+    while let Some(&b) = bytes.next() {
+        if b != b'\x00' {
+            values.last_mut().unwrap().push(b);
+        } else if bytes.next() == Some(&b'\x00') {
+            values.push(vec![]);
+        } else {
+            values.last_mut().unwrap().push(b'\x00');
+        }
+    }
+    values
+}
+
+/// Sorts frequency table rows by their values, which are compared as numbers
+/// if `numeric` is true. NULLs always come last.
+fn sort_by_value(counts: &mut Vec<(&ByteString, Count)>, numeric: bool) {
+    counts.sort_by(|&(v1, _), &(v2, _)| {
+        match (v1.is_empty(), v2.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) if numeric => {
+                let (n1, n2) = (parse_number(v1), parse_number(v2));
+                n1.partial_cmp(&n2).unwrap_or(Ordering::Equal).then(v1.cmp(v2))
+            }
+            (false, false) => v1.cmp(v2),
+        }
+    });
+}
+
+fn parse_number(bs: &[u8]) -> Option<f64> {
+    str::from_utf8(bs).ok().and_then(|s| s.parse().ok())
 }
 
 fn trim(bs: ByteString) -> ByteString {
@@ -0,0 +1,334 @@
+use std::cell::Cell;
+use std::io::Read;
+use std::rc::Rc;
+
+use rustc_serialize::json::{self, JsonEvent, Parser, StackElement};
+
+use CliResult;
+use config::Config;
+use util;
+
+static USAGE: &'static str = "
+Converts JSON objects to CSV data.
+
+The input is either newline delimited JSON (NDJSON), i.e., one JSON object
+on each line, or a single JSON array of objects.
+
+Each object becomes a record. The headers are the keys of all the objects, in
+the order they're first seen, so objects don't need to have the same keys.
+Missing keys become empty fields, and so do null values.
+
+Numbers are written as they appear in the input, e.g., '1.0' stays '1.0'.
+Values that are objects or arrays are written as JSON. (Or, with --flatten,
+each nested value gets its own column, whose name is its path of keys and
+indices joined by dots, e.g., 'address.city' or 'tags.0'. Empty objects and
+arrays are written as '{}' and '[]' in their own column. It's an error if two
+values in one object have the same column, e.g., in {\"a.b\": 1,
+\"a\": {\"b\": 2}}.)
+
+Since the headers are only known at the end of the input, the whole input is
+held in memory.
+
+Usage:
+    xsv fromjson [options] [<input>]
+    xsv fromjson --help
+
+fromjson options:
+    --flatten              Give each value nested in an object or an array
+                           its own column.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    flag_flatten: bool,
+    flag_output: Option<String>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let mut input = String::new();
+    Config::new(&args.arg_input).io_reader()?.read_to_string(&mut input)?;
+
+    let objects = if input.trim_start().starts_with('[') {
+        parse_array(&input)?
+    } else {
+        let mut objects = vec![];
+        for (i, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse_object(line) {
+                Ok(object) => objects.push(object),
+                Err(err) => return fail!(format!("line {}: {}", i + 1, err)),
+            }
+        }
+        objects
+    };
+
+    let mut headers: Vec<String> = vec![];
+    let mut records = vec![];
+    for object in objects {
+        let mut fields = vec![];
+        if args.flag_flatten {
+            flatten("", &object, &mut fields);
+        } else if let Value::Object(members) = object {
+            for (key, value) in members {
+                fields.push((key, value.to_field()));
+            }
+        }
+        let mut record = vec![String::new(); headers.len()];
+        let mut seen = vec![false; headers.len()];
+        for (key, field) in fields {
+            let i = match headers.iter().position(|h| *h == key) {
+                Some(i) => i,
+                None => {
+                    headers.push(key);
+                    record.push(String::new());
+                    seen.push(false);
+                    headers.len() - 1
+                }
+            };
+            // With --flatten, e.g., {"a.b": 1, "a": {"b": 2}} has two values
+            // for the column 'a.b', and neither of them can be dropped.
+            if seen[i] && args.flag_flatten {
+                return fail!(format!(
+                    "object {}: more than one value flattens to the \
+                     column '{}'", records.len() + 1, headers[i]));
+            }
+            seen[i] = true;
+            record[i] = field;
+        }
+        records.push(record);
+    }
+
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+    // Without any keys, there's nothing to write, not even a header row.
+    if headers.is_empty() {
+        return Ok(wtr.flush()?);
+    }
+    wtr.write_record(&headers)?;
+    for mut record in records {
+        record.resize(headers.len(), String::new());
+        wtr.write_record(&record)?;
+    }
+    Ok(wtr.flush()?)
+}
+
+/// A JSON value, whose object members are kept in their original order.
+enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the CSV field for this value.
+    fn to_field(&self) -> String {
+        match *self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(ref n) => n.clone(),
+            Value::String(ref s) => s.clone(),
+            Value::Array(_) | Value::Object(_) => self.to_json(),
+        }
+    }
+
+    /// Returns this value as compact JSON.
+    fn to_json(&self) -> String {
+        match *self {
+            Value::Null => "null".to_owned(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(ref n) => n.clone(),
+            Value::String(ref s) => json::encode(s).unwrap(),
+            Value::Array(ref values) => {
+                let values: Vec<_> = values.iter().map(|v| v.to_json())
+                                                   .collect();
+                format!("[{}]", values.join(","))
+            }
+            Value::Object(ref members) => {
+                let members: Vec<_> = members.iter().map(|&(ref k, ref v)| {
+                    format!("{}:{}", json::encode(k).unwrap(), v.to_json())
+                }).collect();
+                format!("{{{}}}", members.join(","))
+            }
+        }
+    }
+}
+
+/// Adds the fields of `value` to `fields`, with a column for every value
+/// nested in it.
+fn flatten(path: &str, value: &Value, fields: &mut Vec<(String, String)>) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match *value {
+        Value::Object(ref members) if !members.is_empty() => {
+            for &(ref key, ref value) in members {
+                flatten(&join(key), value, fields);
+            }
+        }
+        Value::Array(ref values) if !values.is_empty() => {
+            for (i, value) in values.iter().enumerate() {
+                flatten(&join(&i.to_string()), value, fields);
+            }
+        }
+        // An empty object or array has no nested values, so it gets a column
+        // of its own, written as JSON. An empty object at the top has no
+        // columns at all.
+        Value::Object(_) if path.is_empty() => {}
+        _ => fields.push((path.to_owned(), value.to_field())),
+    }
+}
+
+/// Source gives the characters of the input to the parser, and keeps track of
+/// how far it has read, so that numbers can be copied as they are written.
+#[derive(Clone)]
+struct Source<'a> {
+    input: &'a str,
+    /// The byte offset of the end of what was read, and the length of the
+    /// last character read, which is the parser's lookahead.
+    read: Rc<Cell<(usize, usize)>>,
+}
+
+impl<'a> Source<'a> {
+    fn new(input: &'a str) -> Source<'a> {
+        Source { input: input, read: Rc::new(Cell::new((0, 0))) }
+    }
+
+    /// Returns the text of the number that the parser has just read.
+    fn number(&self) -> String {
+        let (pos, lookahead) = self.read.get();
+        let end = pos - lookahead;
+        let is_number = |c: char| {
+            c.is_digit(10) || c == '-' || c == '+' || c == '.'
+            || c == 'e' || c == 'E'
+        };
+        let start = self.input[..end]
+            .rfind(|c| !is_number(c))
+            .map_or(0, |i| i + 1);
+        self.input[start..end].to_owned()
+    }
+}
+
+impl<'a> Iterator for Source<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let (pos, _) = self.read.get();
+        match self.input[pos..].chars().next() {
+            None => {
+                self.read.set((pos, 0));
+                None
+            }
+            Some(c) => {
+                self.read.set((pos + c.len_utf8(), c.len_utf8()));
+                Some(c)
+            }
+        }
+    }
+}
+
+fn parse_array(input: &str) -> Result<Vec<Value>, String> {
+    let src = Source::new(input);
+    let mut parser = Parser::new(src.clone());
+    match parser.next() {
+        Some(JsonEvent::ArrayStart) => {}
+        Some(JsonEvent::Error(err)) => return Err(err.to_string()),
+        _ => return Err("expected a JSON array".to_owned()),
+    }
+    let mut objects = vec![];
+    loop {
+        match parser.next() {
+            Some(JsonEvent::ArrayEnd) => break,
+            Some(JsonEvent::ObjectStart) => {
+                let event = JsonEvent::ObjectStart;
+                objects.push(build(&mut parser, &src, event)?);
+            }
+            Some(JsonEvent::Error(err)) => return Err(err.to_string()),
+            _ => return Err("expected an array of JSON objects".to_owned()),
+        }
+    }
+    finish(parser)?;
+    Ok(objects)
+}
+
+fn parse_object(input: &str) -> Result<Value, String> {
+    let src = Source::new(input);
+    let mut parser = Parser::new(src.clone());
+    let object = match parser.next() {
+        Some(JsonEvent::ObjectStart) => {
+            build(&mut parser, &src, JsonEvent::ObjectStart)?
+        }
+        Some(JsonEvent::Error(err)) => return Err(err.to_string()),
+        _ => return Err("expected a JSON object".to_owned()),
+    };
+    finish(parser)?;
+    Ok(object)
+}
+
+/// Checks that nothing but whitespace follows the parsed value.
+fn finish<T: Iterator<Item=char>>(mut parser: Parser<T>)
+                                 -> Result<(), String> {
+    match parser.next() {
+        None => Ok(()),
+        Some(JsonEvent::Error(err)) => Err(err.to_string()),
+        Some(_) => Err("unexpected trailing characters".to_owned()),
+    }
+}
+
+/// Builds the value that starts with `event`, reading the rest of it from
+/// `parser`, which reads from `src`.
+fn build(parser: &mut Parser<Source>, src: &Source, event: JsonEvent)
+        -> Result<Value, String> {
+    Ok(match event {
+        JsonEvent::NullValue => Value::Null,
+        JsonEvent::BooleanValue(b) => Value::Bool(b),
+        JsonEvent::I64Value(_)
+        | JsonEvent::U64Value(_)
+        | JsonEvent::F64Value(_) => Value::Number(src.number()),
+        JsonEvent::StringValue(s) => Value::String(s),
+        JsonEvent::ArrayStart => {
+            let mut values = vec![];
+            loop {
+                match parser.next() {
+                    Some(JsonEvent::ArrayEnd) => break,
+                    Some(event) => values.push(build(parser, src, event)?),
+                    None => return Err("unexpected end of input".to_owned()),
+                }
+            }
+            Value::Array(values)
+        }
+        JsonEvent::ObjectStart => {
+            let mut members = vec![];
+            loop {
+                let event = match parser.next() {
+                    Some(JsonEvent::ObjectEnd) => break,
+                    Some(event) => event,
+                    None => return Err("unexpected end of input".to_owned()),
+                };
+                let key = match parser.stack().top() {
+                    Some(StackElement::Key(key)) => key.to_owned(),
+                    _ => String::new(),
+                };
+                members.push((key, build(parser, src, event)?));
+            }
+            Value::Object(members)
+        }
+        JsonEvent::ArrayEnd | JsonEvent::ObjectEnd => {
+            return Err("unexpected end of a value".to_owned());
+        }
+        JsonEvent::Error(err) => return Err(err.to_string()),
+    })
+}
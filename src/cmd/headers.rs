@@ -12,8 +12,10 @@ Prints the fields of the first row in the CSV data.
 These names can be used in commands like 'select' to refer to columns in the
 CSV data.
 
-Note that multiple CSV files may be given to this command. This is useful with
-the --intersect flag.
+Note that multiple CSV files may be given to this command. By default, the
+headers of each of them are printed one after the other. With --union, each
+header is only printed once, and with --intersect, only the headers that are
+in every input are printed (in the order of the first input).
 
 Usage:
     xsv headers [options] [<input>...]
@@ -22,8 +24,10 @@ headers options:
     -j, --just-names       Only show the header names (hide column index).
                            This is automatically enabled if more than one
                            input is given.
-    --intersect            Shows the intersection of all headers in all of
-                           the inputs given.
+    --intersect            Shows the headers that are in all of the inputs
+                           given.
+    --union                Shows every header in any of the inputs given,
+                           but only once.
 
 Common options:
     -h, --help             Display this message
@@ -36,20 +40,29 @@ struct Args {
     arg_input: Vec<String>,
     flag_just_names: bool,
     flag_intersect: bool,
+    flag_union: bool,
     flag_delimiter: Option<Delimiter>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_intersect && args.flag_union {
+        return fail!("--intersect and --union cannot be used together.");
+    }
     let configs = util::many_configs(
         &*args.arg_input, args.flag_delimiter, true)?;
 
     let num_inputs = configs.len();
     let mut headers: Vec<Vec<u8>> = vec![];
-    for conf in configs.into_iter() {
+    for (i, conf) in configs.into_iter().enumerate() {
         let mut rdr = conf.reader()?;
-        for header in rdr.byte_headers()?.iter() {
-            if !args.flag_intersect
+        let next = rdr.byte_headers()?;
+        if args.flag_intersect && i > 0 {
+            headers.retain(|h| next.iter().any(|header| &**h == header));
+            continue;
+        }
+        for header in next.iter() {
+            if !(args.flag_union || args.flag_intersect)
                 || !headers.iter().any(|h| &**h == header)
             {
                 headers.push(header.to_vec());
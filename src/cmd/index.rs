@@ -2,6 +2,7 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
+use csv;
 use csv_index::RandomAccessSimple;
 
 use CliResult;
@@ -18,6 +19,12 @@ automatically used by commands that can benefit from it. If the original CSV
 data changes after the index is made, commands that try to use it will result
 in an error (you have to regenerate the index before it can be used again).
 
+With --check, no index is created. Instead, the existing index is checked: it
+is out of date if the CSV data was modified after it (which is what other
+commands check before using it), or if the number of records in it doesn't
+match the number of records in the CSV data. If it is missing or out of date,
+then this command fails.
+
 Usage:
     xsv index [options] <input>
     xsv index --help
@@ -26,7 +33,10 @@ index options:
     -o, --output <file>    Write index to <file> instead of <input>.idx.
                            Generally, this is not currently useful because
                            the only way to use an index is if it is specially
-                           named <input>.idx.
+                           named <input>.idx. With --check, this is the
+                           index to check.
+    --check                Check the existing index instead of creating one.
+    --print-count          Print the number of records in the index.
 
 Common options:
     -h, --help             Display this message
@@ -38,6 +48,8 @@ Common options:
 struct Args {
     arg_input: String,
     flag_output: Option<String>,
+    flag_check: bool,
+    flag_print_count: bool,
     flag_delimiter: Option<Delimiter>,
 }
 
@@ -46,13 +58,48 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     let pidx = match args.flag_output {
         None => util::idx_path(&Path::new(&args.arg_input)),
-        Some(p) => PathBuf::from(&p),
+        Some(ref p) => PathBuf::from(p),
     };
 
-    let rconfig = Config::new(&Some(args.arg_input))
-                         .delimiter(args.flag_delimiter);
-    let mut rdr = rconfig.reader_file()?;
-    let mut wtr = io::BufWriter::new(fs::File::create(&pidx)?);
-    RandomAccessSimple::create(&mut rdr, &mut wtr)?;
+    let rconfig = Config::new(&Some(args.arg_input.clone()))
+                         .delimiter(args.flag_delimiter)
+                         .idx_path(Some(pidx.clone()));
+    if !args.flag_check {
+        let mut rdr = rconfig.reader_file()?;
+        let mut wtr = io::BufWriter::new(fs::File::create(&pidx)?);
+        RandomAccessSimple::create(&mut rdr, &mut wtr)?;
+        if !args.flag_print_count {
+            return Ok(());
+        }
+    }
+
+    if !pidx.exists() {
+        return fail!(format!("{}: The index doesn't exist. Please create \
+                              it with 'xsv index'.", pidx.display()));
+    }
+    let idx = match rconfig.indexed() {
+        Ok(Some(idx)) => idx,
+        Ok(None) => unreachable!(),
+        Err(err) => {
+            return fail!(format!("{}: {}", pidx.display(), err));
+        }
+    };
+    if args.flag_check {
+        let mut rdr = rconfig.reader_file()?;
+        let mut record = csv::ByteRecord::new();
+        let mut count = 0u64;
+        while rdr.read_byte_record(&mut record)? {
+            count += 1;
+        }
+        if count != idx.count() {
+            return fail!(format!(
+                "{}: The index has {} records, but the CSV file has {}. \
+                 Please re-create the index.",
+                pidx.display(), idx.count(), count));
+        }
+    }
+    if args.flag_print_count {
+        println!("{}", idx.count());
+    }
     Ok(())
 }
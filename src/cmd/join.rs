@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::hash_map::{HashMap, Entry};
 use std::fmt;
 use std::fs;
@@ -22,7 +23,8 @@ intersection of rows on the keys specified.
 
 Joins are always done by ignoring leading and trailing whitespace. By default,
 joins are done case sensitively, but this can be disabled with the --no-case
-flag.
+flag. Keys are normalized when the hash index is built, so fields that are
+empty or only whitespace are null keys, which only match when --nulls is set.
 
 The columns arguments specify the columns to join for each input. Columns can
 be referenced by name or index, starting at 1. Specify multiple columns by
@@ -30,12 +32,47 @@ separating them with a comma. Specify a range of columns with `-`. Both
 columns1 and columns2 must specify exactly the same number of columns.
 (See 'xsv select --help' for the full syntax.)
 
+When both inputs are already sorted by their join columns, the --sorted flag
+does a merge join instead, which reads both inputs once and only keeps the
+rows of the current key in memory. The keys are compared after they're
+normalized (trimmed, and lowercased with --no-case), field by field, as raw
+bytes. This is only the order produced by 'xsv sort' on the same columns when
+the keys don't have leading or trailing whitespace and --no-case isn't used,
+and otherwise, the inputs must be sorted by their normalized keys. An input
+that isn't sorted in this order is an error. --sorted can be used with inner,
+left, right and full joins.
+
+The --range flag does an interval join instead: <columns1> must be a single
+column with numbers, and <columns2> two columns with the low and high ends of
+ranges of numbers. Each row of the first data set is joined with every row of
+the second data set whose range contains its number, including both ends.
+Rows with fields that aren't numbers never match. Only inner and left joins
+can be done this way.
+
+  Find the country of each IP address, given as a number:
+  $ xsv join --range ip visits.csv low,high countries.csv
+
+More lookup files can be joined in the same invocation with '--and', which
+takes the columns to join and the file. Each file is then joined, in order,
+with the rows joined so far, where the columns are selected both in those
+rows and in the file. The columns of each file are prefixed by the value of
+the --prefix-right flag when a column with the same name was already joined
+(or always, with --prefix-all), and the columns joined so far keep their
+names.
+Only inner and left joins can be done this way.
+
+  Attach both the customer and the product of each sale:
+  $ xsv join customer sales.csv customer customers.csv --and \\
+      product products.csv
+
 Usage:
-    xsv join [options] <columns1> <input1> <columns2> <input2>
+    xsv join [options] <columns1> <input1> <columns2> <input2> [--and <columns3> <input3>]...
+    xsv join [options] --cross <input1> <input2>
     xsv join --help
 
 join options:
     --no-case              When set, joins are done case insensitively.
+    --ignore-case          An alias for --no-case.
     --left                 Do a 'left outer' join. This returns all rows in
                            first CSV data set, including rows with no
                            corresponding row in the second data set. When no
@@ -56,10 +93,47 @@ join options:
                            data sets given. The number of rows return is
                            equal to N * M, where N and M correspond to the
                            number of rows in the given data sets, respectively.
+                           The columns don't need to be given, and are
+                           ignored. The second data set is read again for
+                           each row of the first one.
+    --semi                 Do a 'semi' join. This returns the rows in the
+                           first CSV data set that have a corresponding row
+                           in the second data set, each only once. Only the
+                           columns of the first data set are written.
+    --anti                 Do an 'anti' join. This returns the rows in the
+                           first CSV data set that have no corresponding row
+                           in the second data set. Only the columns of the
+                           first data set are written.
+    --and                  Join another file, see above.
+    --sorted               Do a merge join of inputs that are sorted by
+                           their join columns.
+    --numeric              Compare keys that are numbers by their value,
+                           e.g., 007 matches 7, and 1e3 matches 1000.0.
+                           Keys that aren't numbers are compared as usual.
+    --range                Join numbers to the ranges that contain them.
+                           See above.
+    --unmatched-left <file>   Also write the rows of the first data set that
+                              don't match any row of the second data set to
+                              <file>.
+    --unmatched-right <file>  Also write the rows of the second data set that
+                              don't match any row of the first data set to
+                              <file>.
+    --unicode-case         When set, joins are done case insensitively with
+                           Unicode case folding, e.g., STRASSE matches
+                           straße. Keys that aren't valid UTF-8 are compared
+                           as they are.
     --nulls                When set, joins will work on empty fields.
                            Otherwise, empty fields are completely ignored.
                            (In fact, any row that has an empty field in the
                            key specified is ignored.)
+    --prefix-left <s>      Prefix the names of columns from the first data
+                           set with <s> when the second data set has a
+                           column with the same name.
+    --prefix-right <s>     Prefix the names of columns from the second data
+                           set with <s> when the first data set has a
+                           column with the same name.
+    --prefix-all           Prefix the names of all columns, not only the
+                           ones that appear in both data sets.
 
 Common options:
     -h, --help             Display this message
@@ -79,43 +153,100 @@ struct Args {
     arg_input1: String,
     arg_columns2: SelectColumns,
     arg_input2: String,
+    arg_columns3: Vec<SelectColumns>,
+    arg_input3: Vec<String>,
     flag_left: bool,
     flag_right: bool,
     flag_full: bool,
     flag_cross: bool,
+    flag_semi: bool,
+    flag_anti: bool,
+    flag_sorted: bool,
+    flag_numeric: bool,
+    flag_range: bool,
+    flag_unmatched_left: Option<String>,
+    flag_unmatched_right: Option<String>,
     flag_output: Option<String>,
     flag_no_headers: bool,
     flag_no_case: bool,
+    flag_ignore_case: bool,
+    flag_unicode_case: bool,
     flag_nulls: bool,
+    flag_prefix_left: Option<String>,
+    flag_prefix_right: Option<String>,
+    flag_prefix_all: bool,
     flag_delimiter: Option<Delimiter>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    // Check the flags before opening any of the output files, so that a
+    // mistake doesn't truncate them.
+    args.check_flags()?;
     let mut state = args.new_io_state()?;
+    if !args.arg_input3.is_empty() {
+        let lookups = args.lookups(&mut state)?;
+        state.write_chain_headers(&lookups)?;
+        return state.chain_join(lookups, args.flag_left);
+    }
+    if args.flag_range {
+        state.write_headers()?;
+        return state.range_join(args.flag_left);
+    }
+    if args.flag_sorted {
+        return match (args.flag_left, args.flag_right, args.flag_full) {
+            (false, false, false) => {
+                state.write_headers()?;
+                state.merge_join(false, false)
+            }
+            (true, false, false) => {
+                state.write_headers()?;
+                state.merge_join(true, false)
+            }
+            (false, true, false) => {
+                state.write_headers()?;
+                state.merge_join(false, true)
+            }
+            (false, false, true) => {
+                state.write_headers()?;
+                state.merge_join(true, true)
+            }
+            _ => fail!("Please pick exactly one join operation."),
+        };
+    }
     match (
         args.flag_left,
         args.flag_right,
         args.flag_full,
         args.flag_cross,
+        args.flag_semi,
+        args.flag_anti,
     ) {
-        (true, false, false, false) => {
+        (true, false, false, false, false, false) => {
             state.write_headers()?;
             state.outer_join(false)
         }
-        (false, true, false, false) => {
+        (false, true, false, false, false, false) => {
             state.write_headers()?;
             state.outer_join(true)
         }
-        (false, false, true, false) => {
+        (false, false, true, false, false, false) => {
             state.write_headers()?;
             state.full_outer_join()
         }
-        (false, false, false, true) => {
+        (false, false, false, true, false, false) => {
             state.write_headers()?;
             state.cross_join()
         }
-        (false, false, false, false) => {
+        (false, false, false, false, true, false) => {
+            state.write_headers1()?;
+            state.semi_join(false)
+        }
+        (false, false, false, false, false, true) => {
+            state.write_headers1()?;
+            state.semi_join(true)
+        }
+        (false, false, false, false, false, false) => {
             state.write_headers()?;
             state.inner_join()
         }
@@ -130,15 +261,93 @@ struct IoState<R, W: io::Write> {
     rdr2: csv::Reader<R>,
     sel2: Selection,
     no_headers: bool,
-    casei: bool,
+    casei: Case,
+    numeric: bool,
     nulls: bool,
+    prefix_left: Option<String>,
+    prefix_right: Option<String>,
+    prefix_all: bool,
+    /// Where to write the unmatched rows of each data set, if anywhere.
+    unmatched1: Option<csv::Writer<W>>,
+    unmatched2: Option<csv::Writer<W>>,
 }
 
 impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
     fn write_headers(&mut self) -> CliResult<()> {
         if !self.no_headers {
-            let mut headers = self.rdr1.byte_headers()?.clone();
-            headers.extend(self.rdr2.byte_headers()?.iter());
+            let headers1 = self.rdr1.byte_headers()?.clone();
+            let headers2 = self.rdr2.byte_headers()?.clone();
+            let headers = self.join_headers(&headers1, &headers2);
+            self.wtr.write_record(&headers)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the headers of the first two data sets followed by the
+    /// headers of each lookup file.
+    fn write_chain_headers(&mut self, lookups: &[Lookup<R>]) -> CliResult<()> {
+        if !self.no_headers {
+            let headers1 = self.rdr1.byte_headers()?.clone();
+            let headers2 = self.rdr2.byte_headers()?.clone();
+            let mut headers = self.join_headers(&headers1, &headers2);
+            // Collisions are found among the original names, so that the
+            // names of earlier columns aren't prefixed again.
+            let mut raw = headers1;
+            raw.extend(&headers2);
+            for lookup in lookups {
+                for name in lookup.headers.iter() {
+                    headers.push_field(&self.decorate(
+                        name, &self.prefix_right, &raw));
+                }
+                raw.extend(&lookup.headers);
+            }
+            self.wtr.write_record(&headers)?;
+        }
+        Ok(())
+    }
+
+    /// Concatenates `headers1` and `headers2`, with the names that appear
+    /// in both prefixed according to `--prefix-left` and `--prefix-right`.
+    fn join_headers(
+        &self,
+        headers1: &csv::ByteRecord,
+        headers2: &csv::ByteRecord,
+    ) -> csv::ByteRecord {
+        let mut headers = csv::ByteRecord::new();
+        for name in headers1.iter() {
+            headers.push_field(&self.decorate(
+                name, &self.prefix_left, headers2));
+        }
+        for name in headers2.iter() {
+            headers.push_field(&self.decorate(
+                name, &self.prefix_right, headers1));
+        }
+        headers
+    }
+
+    /// Returns `name` with `prefix` prepended if `--prefix-all` is set or
+    /// the other data set has a column with the same name.
+    fn decorate(
+        &self,
+        name: &[u8],
+        prefix: &Option<String>,
+        others: &csv::ByteRecord,
+    ) -> ByteString {
+        match *prefix {
+            Some(ref prefix)
+                if self.prefix_all || others.iter().any(|o| o == name) => {
+                let mut decorated = prefix.as_bytes().to_vec();
+                decorated.extend_from_slice(name);
+                decorated
+            }
+            _ => name.to_vec(),
+        }
+    }
+
+    /// Writes only the headers of the first data set.
+    fn write_headers1(&mut self) -> CliResult<()> {
+        if !self.no_headers {
+            let headers = self.rdr1.byte_headers()?.clone();
             self.wtr.write_record(&headers)?;
         }
         Ok(())
@@ -147,14 +356,20 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
     fn inner_join(mut self) -> CliResult<()> {
         let mut scratch = csv::ByteRecord::new();
         let mut validx = ValueIndex::new(
-            self.rdr2, &self.sel2, self.casei, self.nulls)?;
+            self.rdr2, &self.sel2, self.casei, self.numeric, self.nulls)?;
+        let mut matched2 = vec![false; validx.num_rows];
         for row in self.rdr1.byte_records() {
             let row = row?;
-            let key = get_row_key(&self.sel1, &row, self.casei);
+            let key = get_row_key(&self.sel1, &row, self.casei, self.numeric);
             match validx.values.get(&key) {
-                None => continue,
+                None => {
+                    if let Some(ref mut wtr) = self.unmatched1 {
+                        wtr.write_byte_record(&row)?;
+                    }
+                }
                 Some(rows) => {
                     for &rowi in rows.iter() {
+                        matched2[rowi] = true;
                         validx.idx.seek(rowi as u64)?;
 
                         validx.idx.read_byte_record(&mut scratch)?;
@@ -164,22 +379,25 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
                 }
             }
         }
-        Ok(())
+        finish_unmatched(self.unmatched1, self.unmatched2, &mut validx,
+                         &matched2)
     }
 
     fn outer_join(mut self, right: bool) -> CliResult<()> {
         if right {
             ::std::mem::swap(&mut self.rdr1, &mut self.rdr2);
             ::std::mem::swap(&mut self.sel1, &mut self.sel2);
+            ::std::mem::swap(&mut self.unmatched1, &mut self.unmatched2);
         }
 
         let mut scratch = csv::ByteRecord::new();
         let (_, pad2) = self.get_padding()?;
         let mut validx = ValueIndex::new(
-            self.rdr2, &self.sel2, self.casei, self.nulls)?;
+            self.rdr2, &self.sel2, self.casei, self.numeric, self.nulls)?;
+        let mut matched2 = vec![false; validx.num_rows];
         for row in self.rdr1.byte_records() {
             let row = row?;
-            let key = get_row_key(&self.sel1, &row, self.casei);
+            let key = get_row_key(&self.sel1, &row, self.casei, self.numeric);
             match validx.values.get(&key) {
                 None => {
                     if right {
@@ -187,9 +405,13 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
                     } else {
                         self.wtr.write_record(row.iter().chain(&pad2))?;
                     }
+                    if let Some(ref mut wtr) = self.unmatched1 {
+                        wtr.write_byte_record(&row)?;
+                    }
                 }
                 Some(rows) => {
                     for &rowi in rows.iter() {
+                        matched2[rowi] = true;
                         validx.idx.seek(rowi as u64)?;
                         let row1 = row.iter();
                         validx.idx.read_byte_record(&mut scratch)?;
@@ -202,24 +424,28 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
                 }
             }
         }
-        Ok(())
+        finish_unmatched(self.unmatched1, self.unmatched2, &mut validx,
+                         &matched2)
     }
 
     fn full_outer_join(mut self) -> CliResult<()> {
         let mut scratch = csv::ByteRecord::new();
         let (pad1, pad2) = self.get_padding()?;
         let mut validx = ValueIndex::new(
-            self.rdr2, &self.sel2, self.casei, self.nulls)?;
+            self.rdr2, &self.sel2, self.casei, self.numeric, self.nulls)?;
 
         // Keep track of which rows we've written from rdr2.
         let mut rdr2_written: Vec<_> =
             repeat(false).take(validx.num_rows).collect();
         for row1 in self.rdr1.byte_records() {
             let row1 = row1?;
-            let key = get_row_key(&self.sel1, &row1, self.casei);
+            let key = get_row_key(&self.sel1, &row1, self.casei, self.numeric);
             match validx.values.get(&key) {
                 None => {
                     self.wtr.write_record(row1.iter().chain(&pad2))?;
+                    if let Some(ref mut wtr) = self.unmatched1 {
+                        wtr.write_byte_record(&row1)?;
+                    }
                 }
                 Some(rows) => {
                     for &rowi in rows.iter() {
@@ -242,6 +468,182 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
                 self.wtr.write_record(pad1.iter().chain(&scratch))?;
             }
         }
+        finish_unmatched(self.unmatched1, self.unmatched2, &mut validx,
+                         &rdr2_written)
+    }
+
+    /// Writes the rows of the first data set that have a match in the
+    /// second one, or, when `anti` is true, those that don't.
+    fn semi_join(mut self, anti: bool) -> CliResult<()> {
+        let validx = ValueIndex::new(
+            self.rdr2, &self.sel2, self.casei, self.numeric, self.nulls)?;
+        for row in self.rdr1.byte_records() {
+            let row = row?;
+            let key = get_row_key(&self.sel1, &row, self.casei, self.numeric);
+            if validx.values.contains_key(&key) != anti {
+                self.wtr.write_record(&row)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Joins inputs that are both sorted by their keys, by reading them in
+    /// lockstep one group of rows with the same key at a time.
+    ///
+    /// Unmatched rows of the first input are written when `left` is true,
+    /// and those of the second input when `right` is true.
+    fn merge_join(mut self, left: bool, right: bool) -> CliResult<()> {
+        let (pad1, pad2) = self.get_padding()?;
+        let mut groups1 =
+            SortedGroups::new(self.rdr1, self.sel1, self.casei, "first");
+        let mut groups2 =
+            SortedGroups::new(self.rdr2, self.sel2, self.casei, "second");
+        let mut group1 = groups1.next_group()?;
+        let mut group2 = groups2.next_group()?;
+        loop {
+            let ord = match (&group1, &group2) {
+                (&None, &None) => break,
+                (&Some(_), &None) => Ordering::Less,
+                (&None, &Some(_)) => Ordering::Greater,
+                (&Some(ref g1), &Some(ref g2)) => {
+                    // Null keys never match, so write them out right away.
+                    if !self.nulls && g1.is_null() {
+                        Ordering::Less
+                    } else if !self.nulls && g2.is_null() {
+                        Ordering::Greater
+                    } else {
+                        g1.key.cmp(&g2.key)
+                    }
+                }
+            };
+            match ord {
+                Ordering::Less => {
+                    for row1 in &group1.as_ref().unwrap().rows {
+                        if left {
+                            self.wtr.write_record(row1.iter().chain(&pad2))?;
+                        }
+                        if let Some(ref mut wtr) = self.unmatched1 {
+                            wtr.write_byte_record(row1)?;
+                        }
+                    }
+                    group1 = groups1.next_group()?;
+                }
+                Ordering::Greater => {
+                    for row2 in &group2.as_ref().unwrap().rows {
+                        if right {
+                            self.wtr.write_record(pad1.iter().chain(row2))?;
+                        }
+                        if let Some(ref mut wtr) = self.unmatched2 {
+                            wtr.write_byte_record(row2)?;
+                        }
+                    }
+                    group2 = groups2.next_group()?;
+                }
+                Ordering::Equal => {
+                    {
+                        let rows1 = &group1.as_ref().unwrap().rows;
+                        let rows2 = &group2.as_ref().unwrap().rows;
+                        for row1 in rows1 {
+                            for row2 in rows2 {
+                                self.wtr.write_record(
+                                    row1.iter().chain(row2))?;
+                            }
+                        }
+                    }
+                    group1 = groups1.next_group()?;
+                    group2 = groups2.next_group()?;
+                }
+            }
+        }
+        for wtr in self.unmatched1.iter_mut().chain(&mut self.unmatched2) {
+            wtr.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Joins the first data set with the second, and then the result with
+    /// each of `lookups` in turn.
+    fn chain_join(
+        mut self,
+        lookups: Vec<Lookup<R>>,
+        left: bool,
+    ) -> CliResult<()> {
+        let (_, pad2) = self.get_padding()?;
+        let validx = ValueIndex::new(
+            self.rdr2, &self.sel2, self.casei, self.numeric, self.nulls)?;
+        let mut stages = vec![Lookup {
+            validx: validx,
+            sel: self.sel1,
+            headers: csv::ByteRecord::new(),
+            pad: pad2,
+        }];
+        stages.extend(lookups);
+
+        let mut scratch = csv::ByteRecord::new();
+        let (mut rows, mut next) = (vec![], vec![]);
+        for row in self.rdr1.byte_records() {
+            rows.clear();
+            rows.push(row?);
+            for stage in &mut stages {
+                next.clear();
+                for row in &rows {
+                    let key = get_row_key(
+                        &stage.sel, row, self.casei, self.numeric);
+                    match stage.validx.values.get(&key) {
+                        None => {
+                            if left {
+                                let mut joined = row.clone();
+                                joined.extend(&stage.pad);
+                                next.push(joined);
+                            }
+                        }
+                        Some(rowis) => {
+                            for &rowi in rowis.iter() {
+                                stage.validx.idx.seek(rowi as u64)?;
+                                stage.validx.idx
+                                     .read_byte_record(&mut scratch)?;
+                                let mut joined = row.clone();
+                                joined.extend(&scratch);
+                                next.push(joined);
+                            }
+                        }
+                    }
+                }
+                ::std::mem::swap(&mut rows, &mut next);
+                if rows.is_empty() {
+                    break;
+                }
+            }
+            for row in &rows {
+                self.wtr.write_byte_record(row)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Joins each number in the first data set with the ranges that
+    /// contain it in the second data set.
+    fn range_join(mut self, left: bool) -> CliResult<()> {
+        let (_, pad2) = self.get_padding()?;
+        let ranges = Ranges::new(&mut self.rdr2, &self.sel2)?;
+        let mut found = vec![];
+        for row in self.rdr1.byte_records() {
+            let row = row?;
+            found.clear();
+            let n = self.sel1.select(&row).next().and_then(parse_f64);
+            if let Some(n) = n {
+                ranges.containing(n, &mut found);
+            }
+            if found.is_empty() {
+                if left {
+                    self.wtr.write_record(row.iter().chain(&pad2))?;
+                }
+                continue;
+            }
+            for &i in &found {
+                self.wtr.write_record(row.iter().chain(&ranges.rows[i]))?;
+            }
+        }
         Ok(())
     }
 
@@ -277,6 +679,49 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
 }
 
 impl Args {
+    /// Returns an error if the flags pick an invalid combination of join
+    /// operations.
+    fn check_flags(&self) -> CliResult<()> {
+        let ops = [
+            self.flag_left, self.flag_right, self.flag_full,
+            self.flag_cross, self.flag_semi, self.flag_anti,
+        ];
+        if ops.iter().filter(|&&op| op).count() > 1 {
+            return fail!("Please pick exactly one join operation.");
+        }
+        if (self.flag_unmatched_left.is_some()
+                || self.flag_unmatched_right.is_some())
+            && (!self.arg_input3.is_empty() || self.flag_range
+                || self.flag_cross || self.flag_semi || self.flag_anti) {
+            return fail!("--unmatched-left and --unmatched-right can only be \
+                          used with inner, left, right and full joins.");
+        }
+        if !self.arg_input3.is_empty() {
+            if self.flag_right || self.flag_full || self.flag_cross
+                    || self.flag_semi || self.flag_anti || self.flag_sorted
+                    || self.flag_range {
+                return fail!("--and can only be used with inner and left \
+                              joins.");
+            }
+        } else if self.flag_range {
+            if self.flag_right || self.flag_full || self.flag_cross
+                    || self.flag_semi || self.flag_anti || self.flag_sorted {
+                return fail!("--range can only be used with inner and left \
+                              joins.");
+            }
+        } else if self.flag_sorted {
+            if self.flag_numeric {
+                return fail!("--sorted and --numeric cannot be used \
+                              together.");
+            }
+            if self.flag_cross || self.flag_semi || self.flag_anti {
+                return fail!("--sorted can only be used with inner, left, \
+                              right and full joins.");
+            }
+        }
+        Ok(())
+    }
+
     fn new_io_state(&self)
         -> CliResult<IoState<fs::File, Box<io::Write+'static>>> {
         let rconf1 = Config::new(&Some(self.arg_input1.clone()))
@@ -292,6 +737,22 @@ impl Args {
         let mut rdr2 = rconf2.reader_file()?;
         let (sel1, sel2) = self.get_selections(
             &rconf1, &mut rdr1, &rconf2, &mut rdr2)?;
+        let unmatched1 = match self.flag_unmatched_left {
+            None => None,
+            Some(ref path) => {
+                let mut wtr = Config::new(&Some(path.clone())).writer()?;
+                rconf1.write_headers(&mut rdr1, &mut wtr)?;
+                Some(wtr)
+            }
+        };
+        let unmatched2 = match self.flag_unmatched_right {
+            None => None,
+            Some(ref path) => {
+                let mut wtr = Config::new(&Some(path.clone())).writer()?;
+                rconf2.write_headers(&mut rdr2, &mut wtr)?;
+                Some(wtr)
+            }
+        };
         Ok(IoState {
             wtr: Config::new(&self.flag_output).writer()?,
             rdr1: rdr1,
@@ -299,11 +760,62 @@ impl Args {
             rdr2: rdr2,
             sel2: sel2,
             no_headers: rconf1.no_headers,
-            casei: self.flag_no_case,
+            casei: if self.flag_unicode_case {
+                Case::Fold
+            } else if self.flag_no_case || self.flag_ignore_case {
+                Case::Lower
+            } else {
+                Case::Sensitive
+            },
+            numeric: self.flag_numeric,
             nulls: self.flag_nulls,
+            prefix_left: self.flag_prefix_left.clone(),
+            prefix_right: self.flag_prefix_right.clone(),
+            prefix_all: self.flag_prefix_all,
+            unmatched1: unmatched1,
+            unmatched2: unmatched2,
         })
     }
 
+    /// Opens and indexes each of the files given with '--and'.
+    fn lookups<W: io::Write>(
+        &self,
+        state: &mut IoState<fs::File, W>,
+    ) -> CliResult<Vec<Lookup<fs::File>>> {
+        // The headers of the rows joined so far, to select the key columns
+        // of each lookup in.
+        let mut joined = state.rdr1.byte_headers()?.clone();
+        joined.extend(state.rdr2.byte_headers()?);
+
+        let mut lookups = vec![];
+        for (cols, input) in self.arg_columns3.iter().zip(&self.arg_input3) {
+            let rconf = Config::new(&Some(input.clone()))
+                .delimiter(self.flag_delimiter)
+                .no_headers(self.flag_no_headers)
+                .select(cols.clone());
+            let mut rdr = rconf.reader_file()?;
+            let headers = rdr.byte_headers()?.clone();
+            let sel = cols.selection(&joined, !rconf.no_headers)?;
+            let sel_lookup = rconf.selection(&headers)?;
+            if sel.len() != sel_lookup.len() {
+                return fail!(format!(
+                    "Column selections must have the same number of \
+                     columns, but found column selections with {} and {} \
+                     columns for '{}'.", sel.len(), sel_lookup.len(), input));
+            }
+            let validx = ValueIndex::new(
+                rdr, &sel_lookup, state.casei, state.numeric, state.nulls)?;
+            joined.extend(&headers);
+            lookups.push(Lookup {
+                validx: validx,
+                sel: sel,
+                pad: repeat(b"").take(headers.len()).collect(),
+                headers: headers,
+            });
+        }
+        Ok(lookups)
+    }
+
     fn get_selections<R: io::Read>(
         &self,
         rconf1: &Config, rdr1: &mut csv::Reader<R>,
@@ -313,7 +825,18 @@ impl Args {
         let headers2 = rdr2.byte_headers()?;
         let select1 = rconf1.selection(&*headers1)?;
         let select2 = rconf2.selection(&*headers2)?;
-        if select1.len() != select2.len() {
+        if self.flag_range {
+            if select1.len() != 1 || select2.len() != 2 {
+                return fail!(format!(
+                    "--range needs one column in the first data set and two \
+                     columns in the second, but found column selections \
+                     with {} and {} columns.",
+                    select1.len(), select2.len()));
+            }
+            return Ok((select1, select2));
+        }
+        // The columns of a cross join are ignored.
+        if select1.len() != select2.len() && !self.flag_cross {
             return fail!(format!(
                 "Column selections must have the same number of columns, \
                  but found column selections with {} and {} columns.",
@@ -323,6 +846,219 @@ impl Args {
     }
 }
 
+/// Writes the rows of `validx` that weren't `matched` to `unmatched2`, and
+/// flushes both writers of unmatched rows.
+fn finish_unmatched<R: io::Read + io::Seek, W: io::Write>(
+    unmatched1: Option<csv::Writer<W>>,
+    unmatched2: Option<csv::Writer<W>>,
+    validx: &mut ValueIndex<R>,
+    matched: &[bool],
+) -> CliResult<()> {
+    if let Some(mut wtr) = unmatched1 {
+        wtr.flush()?;
+    }
+    if let Some(mut wtr) = unmatched2 {
+        let mut row = csv::ByteRecord::new();
+        for (i, &m) in matched.iter().enumerate() {
+            if !m {
+                validx.idx.seek(i as u64)?;
+                validx.idx.read_byte_record(&mut row)?;
+                wtr.write_byte_record(&row)?;
+            }
+        }
+        wtr.flush()?;
+    }
+    Ok(())
+}
+
+/// The rows of a data set with numeric ranges, sorted by the low end of
+/// their ranges.
+///
+/// The sorted ranges are an implicit binary search tree: the root of the
+/// ranges in `lo..hi` is the one at `(lo + hi) / 2`, and the ranges before
+/// and after it are its subtrees. Each node also knows the greatest high end
+/// in its subtree, so that lookups can skip the subtrees that don't reach a
+/// number, however wide the other ranges are.
+struct Ranges {
+    /// The low end, the high end and the row index of each range.
+    ranges: Vec<(f64, f64, usize)>,
+    /// The greatest high end in the subtree of each range.
+    max_high: Vec<f64>,
+    rows: Vec<csv::ByteRecord>,
+}
+
+impl Ranges {
+    fn new<R: io::Read>(
+        rdr: &mut csv::Reader<R>,
+        sel: &Selection,
+    ) -> CliResult<Ranges> {
+        let mut ranges = vec![];
+        let mut rows = vec![];
+        for row in rdr.byte_records() {
+            let row = row?;
+            let bounds: Vec<_> = sel.select(&row).map(parse_f64).collect();
+            if let (Some(low), Some(high)) = (bounds[0], bounds[1]) {
+                ranges.push((low, high, rows.len()));
+                rows.push(row);
+            }
+        }
+        ranges.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut max_high = vec![::std::f64::NEG_INFINITY; ranges.len()];
+        fill_max_high(&ranges, &mut max_high, 0, ranges.len());
+        Ok(Ranges { ranges: ranges, max_high: max_high, rows: rows })
+    }
+
+    /// Finds the row indices of the ranges that contain `n`, in the order
+    /// of the rows.
+    fn containing(&self, n: f64, found: &mut Vec<usize>) {
+        self.search(n, 0, self.ranges.len(), found);
+        // The ranges are found in the order of their low ends.
+        if found.len() > 1 {
+            found.sort_unstable();
+        }
+    }
+
+    /// Finds the ranges in the subtree of `lo..hi` that contain `n`.
+    fn search(&self, n: f64, lo: usize, hi: usize, found: &mut Vec<usize>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_high[mid] < n {
+            // None of the ranges in this subtree reach `n`.
+            return;
+        }
+        self.search(n, lo, mid, found);
+        let (low, high, row) = self.ranges[mid];
+        if low <= n {
+            if high >= n {
+                found.push(row);
+            }
+            // The ranges after this one start later, so they can only
+            // contain `n` if this one starts at or before it.
+            self.search(n, mid + 1, hi, found);
+        }
+    }
+}
+
+/// Sets the greatest high end in the subtree of each of the ranges in
+/// `lo..hi`, and returns the greatest one of all of them.
+fn fill_max_high(
+    ranges: &[(f64, f64, usize)],
+    max_high: &mut [f64],
+    lo: usize,
+    hi: usize,
+) -> f64 {
+    if lo >= hi {
+        return ::std::f64::NEG_INFINITY;
+    }
+    let mid = lo + (hi - lo) / 2;
+    let max = ranges[mid].1
+        .max(fill_max_high(ranges, max_high, lo, mid))
+        .max(fill_max_high(ranges, max_high, mid + 1, hi));
+    max_high[mid] = max;
+    max
+}
+
+fn parse_f64(field: &[u8]) -> Option<f64> {
+    match str::from_utf8(field).ok().and_then(|s| s.trim().parse().ok()) {
+        Some(n) if !f64::is_nan(n) => Some(n),
+        _ => None,
+    }
+}
+
+/// A file to join with the rows joined so far.
+struct Lookup<R> {
+    validx: ValueIndex<R>,
+    /// The key columns in the rows joined so far.
+    sel: Selection,
+    headers: csv::ByteRecord,
+    pad: csv::ByteRecord,
+}
+
+/// Consecutive rows of a sorted input with the same key.
+struct Group {
+    key: Vec<ByteString>,
+    rows: Vec<csv::ByteRecord>,
+}
+
+impl Group {
+    fn is_null(&self) -> bool {
+        self.key.iter().any(|f| f.is_empty())
+    }
+}
+
+/// Reads the rows of an input sorted by its keys one group at a time,
+/// checking that the keys really are sorted along the way.
+struct SortedGroups<R> {
+    rdr: csv::Reader<R>,
+    sel: Selection,
+    casei: Case,
+    /// Which input this is, for error messages.
+    which: &'static str,
+    /// The first row of the next group, which has already been read.
+    peeked: Option<(Vec<ByteString>, csv::ByteRecord)>,
+    last_key: Option<Vec<ByteString>>,
+    recno: u64,
+}
+
+impl<R: io::Read> SortedGroups<R> {
+    fn new(
+        rdr: csv::Reader<R>,
+        sel: Selection,
+        casei: Case,
+        which: &'static str,
+    ) -> SortedGroups<R> {
+        SortedGroups {
+            rdr: rdr,
+            sel: sel,
+            casei: casei,
+            which: which,
+            peeked: None,
+            last_key: None,
+            recno: 0,
+        }
+    }
+
+    fn next_group(&mut self) -> CliResult<Option<Group>> {
+        let (key, first) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => match self.next_row()? {
+                None => return Ok(None),
+                Some(next) => next,
+            },
+        };
+        let mut rows = vec![first];
+        while let Some((next_key, row)) = self.next_row()? {
+            if next_key != key {
+                self.peeked = Some((next_key, row));
+                break;
+            }
+            rows.push(row);
+        }
+        Ok(Some(Group { key: key, rows: rows }))
+    }
+
+    fn next_row(
+        &mut self,
+    ) -> CliResult<Option<(Vec<ByteString>, csv::ByteRecord)>> {
+        let mut row = csv::ByteRecord::new();
+        if !self.rdr.read_byte_record(&mut row)? {
+            return Ok(None);
+        }
+        self.recno += 1;
+        let key = get_row_key(&self.sel, &row, self.casei, false);
+        if self.last_key.as_ref().map_or(false, |last| key < *last) {
+            return fail!(format!(
+                "The {} input isn't sorted by its join columns: record {} \
+                 comes after a record with a greater key.",
+                self.which, self.recno));
+        }
+        self.last_key = Some(key.clone());
+        Ok(Some((key, row)))
+    }
+}
+
 struct ValueIndex<R> {
     // This maps tuples of values to corresponding rows.
     values: HashMap<Vec<ByteString>, Vec<usize>>,
@@ -334,7 +1070,8 @@ impl<R: io::Read + io::Seek> ValueIndex<R> {
     fn new(
         mut rdr: csv::Reader<R>,
         sel: &Selection,
-        casei: bool,
+        casei: Case,
+        numeric: bool,
         nulls: bool,
     ) -> CliResult<ValueIndex<R>> {
         let mut val_idx = HashMap::with_capacity(10000);
@@ -367,7 +1104,7 @@ impl<R: io::Read + io::Seek> ValueIndex<R> {
 
             let fields: Vec<_> = sel
                 .select(&row)
-                .map(|v| transform(v, casei))
+                .map(|v| transform(v, casei, numeric))
                 .collect();
             if nulls || !fields.iter().any(|f| f.is_empty()) {
                 match val_idx.entry(fields) {
@@ -414,23 +1151,76 @@ impl<R> fmt::Debug for ValueIndex<R> {
 fn get_row_key(
     sel: &Selection,
     row: &csv::ByteRecord,
-    casei: bool,
+    casei: Case,
+    numeric: bool,
 ) -> Vec<ByteString> {
-    sel.select(row).map(|v| transform(&v, casei)).collect()
+    sel.select(row).map(|v| transform(&v, casei, numeric)).collect()
 }
 
-fn transform(bs: &[u8], casei: bool) -> ByteString {
+fn transform(bs: &[u8], casei: Case, numeric: bool) -> ByteString {
     match str::from_utf8(bs) {
         Err(_) => bs.to_vec(),
         Ok(s) => {
-            if !casei {
-                s.trim().as_bytes().to_vec()
-            } else {
-                let norm: String =
-                    s.trim().chars()
-                     .map(|c| c.to_lowercase().next().unwrap()).collect();
-                norm.into_bytes()
+            if numeric {
+                if let Some(n) = util::normalize_number(s.trim()) {
+                    return n.into_bytes();
+                }
             }
+            match casei {
+                Case::Sensitive => s.trim().as_bytes().to_vec(),
+                Case::Lower => {
+                    let norm: String =
+                        s.trim().chars()
+                         .map(|c| c.to_lowercase().next().unwrap())
+                         .collect();
+                    norm.into_bytes()
+                }
+                Case::Fold => fold_case(s.trim()).into_bytes(),
+            }
+        }
+    }
+}
+
+/// How keys are compared with respect to case.
+#[derive(Clone, Copy)]
+enum Case {
+    Sensitive,
+    /// Each character is replaced by the first character of its lowercase
+    /// form.
+    Lower,
+    /// Unicode case folding, see `fold_case`.
+    Fold,
+}
+
+/// Folds the case of `s`, so that strings that only differ by case are the
+/// same when folded.
+///
+/// This is the full lowercase mapping of each character, except for the
+/// characters whose case folding isn't their lowercase form. It doesn't do
+/// the Turkic special cases, so `I` folds to `i` (not `ı`), and `İ` folds
+/// to `i` followed by a combining dot above (U+0307).
+fn fold_case(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            'ſ' => folded.push('s'),
+            'ϐ' => folded.push('β'),
+            'ϑ' => folded.push('θ'),
+            'ϕ' => folded.push('φ'),
+            'ϖ' => folded.push('π'),
+            'ϰ' => folded.push('κ'),
+            'ϱ' => folded.push('ρ'),
+            'ϵ' => folded.push('ε'),
+            'ﬀ' => folded.push_str("ff"),
+            'ﬁ' => folded.push_str("fi"),
+            'ﬂ' => folded.push_str("fl"),
+            'ﬃ' => folded.push_str("ffi"),
+            'ﬄ' => folded.push_str("ffl"),
+            'ﬅ' | 'ﬆ' => folded.push_str("st"),
+            c => folded.extend(c.to_lowercase()),
         }
     }
+    folded
 }
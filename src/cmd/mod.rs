@@ -1,19 +1,32 @@
+pub mod apply;
+pub mod behead;
 pub mod cat;
 pub mod count;
+pub mod dedup;
+pub mod enumerate;
+pub mod fill;
 pub mod fixlengths;
 pub mod flatten;
 pub mod fmt;
 pub mod frequency;
+pub mod fromjson;
 pub mod headers;
 pub mod index;
 pub mod input;
 pub mod join;
 pub mod partition;
+pub mod pseudo;
+pub mod rename;
+pub mod replace;
+pub mod reverse;
 pub mod sample;
 pub mod search;
+pub mod searchset;
 pub mod select;
 pub mod slice;
 pub mod sort;
 pub mod split;
 pub mod stats;
 pub mod table;
+pub mod tojson;
+pub mod transpose;
@@ -1,5 +1,4 @@
-use std::collections::{HashMap, HashSet};
-use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::Path;
@@ -31,6 +30,16 @@ partition options:
     -p, --prefix-length <n>  Truncate the partition column after the
                            specified number of bytes when creating the
                            output file.
+    --max-open <n>         The maximum number of output files to keep open
+                           at the same time. When more partitions than this
+                           are seen, the least recently used file is closed
+                           and later reopened for appending if needed.
+                           [default: 256]
+    --limit <n>            The maximum number of partitions to create. If
+                           the partition column has more distinct values
+                           than this, the command fails. This guards
+                           against accidentally creating a huge number
+                           of files.
 
 Common options:
     -h, --help             Display this message
@@ -48,12 +57,17 @@ struct Args {
     arg_outdir: String,
     flag_filename: FilenameTemplate,
     flag_prefix_length: Option<usize>,
+    flag_max_open: usize,
+    flag_limit: Option<usize>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_max_open == 0 {
+        return fail!("--max-open must be greater than 0.");
+    }
     fs::create_dir_all(&args.arg_outdir)?;
 
     // It would be nice to support efficient parallel partitions, but doing
@@ -92,10 +106,19 @@ impl Args {
         let mut rdr = rconfig.reader()?;
         let headers = rdr.byte_headers()?.clone();
         let key_col = self.key_column(&rconfig, &headers)?;
-        let mut gen = WriterGenerator::new(self.flag_filename.clone());
+        let gen = WriterGenerator::new(self.flag_filename.clone());
 
-        let mut writers: HashMap<Vec<u8>, BoxedWriter> =
-            HashMap::new();
+        let mut writers = Writers {
+            outdir: Path::new(&self.arg_outdir),
+            headers: if rconfig.no_headers { None } else { Some(headers) },
+            gen: gen,
+            max_open: self.flag_max_open,
+            limit: self.flag_limit,
+            names: HashMap::new(),
+            open: HashMap::new(),
+            lru: BTreeMap::new(),
+            clock: 0,
+        };
         let mut row = csv::ByteRecord::new();
         while rdr.read_byte_record(&mut row)? {
             // Decide what file to put this in.
@@ -105,26 +128,94 @@ impl Args {
                 Some(len) if len < column.len() => &column[0..len],
                 _ => &column[..],
             };
-            let mut entry = writers.entry(key.to_vec());
-            let wtr = match entry {
-                Entry::Occupied(ref mut occupied) => occupied.get_mut(),
-                Entry::Vacant(vacant) => {
-                    // We have a new key, so make a new writer.
-                    let mut wtr = gen.writer(&*self.arg_outdir, key)?;
-                    if !rconfig.no_headers {
-                        wtr.write_record(&headers)?;
+            writers.write(key, &row)?;
+        }
+        writers.flush()
+    }
+}
+
+type BoxedWriter = csv::Writer<Box<io::Write+'static>>;
+
+/// The output files of a partition, of which at most `max_open` are open
+/// at any one time.
+struct Writers<'a> {
+    outdir: &'a Path,
+    headers: Option<csv::ByteRecord>,
+    gen: WriterGenerator,
+    max_open: usize,
+    limit: Option<usize>,
+    /// The unique filename value of every key seen so far.
+    names: HashMap<Vec<u8>, String>,
+    /// The open writers, along with when they were last used.
+    open: HashMap<Vec<u8>, (u64, BoxedWriter)>,
+    /// The keys of the open writers, ordered by when they were last used.
+    lru: BTreeMap<u64, Vec<u8>>,
+    clock: u64,
+}
+
+impl<'a> Writers<'a> {
+    /// Write `row` to the file for `key`, (re)opening it if necessary.
+    fn write(&mut self, key: &[u8], row: &csv::ByteRecord) -> CliResult<()> {
+        self.clock += 1;
+        if let Some(&mut (ref mut used, ref mut wtr)) = self.open.get_mut(key) {
+            let key = self.lru.remove(used).unwrap();
+            self.lru.insert(self.clock, key);
+            *used = self.clock;
+            wtr.write_byte_record(row)?;
+            return Ok(());
+        }
+
+        if self.open.len() >= self.max_open {
+            self.close_oldest()?;
+        }
+        let mut wtr = match self.names.get(key) {
+            // We've seen this key before, but closed its file.
+            Some(name) => self.gen.reopen(self.outdir, name)?,
+            None => {
+                if let Some(limit) = self.limit {
+                    if self.names.len() >= limit {
+                        return fail!(format!(
+                            "More than {} partitions would be created \
+                             (raise --limit to allow more).", limit));
                     }
-                    vacant.insert(wtr)
                 }
-            };
-            wtr.write_byte_record(&row)?;
+                // We have a new key, so make a new writer.
+                let name = self.gen.unique_value(key);
+                let mut wtr = self.gen.create(self.outdir, &name)?;
+                if let Some(ref headers) = self.headers {
+                    wtr.write_record(headers)?;
+                }
+                self.names.insert(key.to_vec(), name);
+                wtr
+            }
+        };
+        wtr.write_byte_record(row)?;
+        self.open.insert(key.to_vec(), (self.clock, wtr));
+        self.lru.insert(self.clock, key.to_vec());
+        Ok(())
+    }
+
+    /// Flush and close the least recently used writer.
+    fn close_oldest(&mut self) -> CliResult<()> {
+        let oldest = match self.lru.keys().next() {
+            None => return Ok(()),
+            Some(&oldest) => oldest,
+        };
+        let key = self.lru.remove(&oldest).unwrap();
+        let (_, mut wtr) = self.open.remove(&key).unwrap();
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Flush all of the open writers.
+    fn flush(&mut self) -> CliResult<()> {
+        for &mut (_, ref mut wtr) in self.open.values_mut() {
+            wtr.flush()?;
         }
         Ok(())
     }
 }
 
-type BoxedWriter = csv::Writer<Box<io::Write+'static>>;
-
 /// Generates unique filenames based on CSV values.
 struct WriterGenerator {
     template: FilenameTemplate,
@@ -143,12 +234,20 @@ impl WriterGenerator {
         }
     }
 
-    /// Create a CSV writer for `key`.  Does not add headers.
-    fn writer<P>(&mut self, path: P, key: &[u8]) -> io::Result<BoxedWriter>
+    /// Create a CSV writer for a value returned by `unique_value`.  Does
+    /// not add headers.
+    fn create<P>(&self, path: P, unique_value: &str) -> io::Result<BoxedWriter>
+        where P: AsRef<Path>
+    {
+        self.template.writer(path.as_ref(), unique_value)
+    }
+
+    /// Reopen the file previously created for `unique_value`, appending
+    /// to it.
+    fn reopen<P>(&self, path: P, unique_value: &str) -> io::Result<BoxedWriter>
         where P: AsRef<Path>
     {
-        let unique_value = self.unique_value(key);
-        self.template.writer(path.as_ref(), &unique_value)
+        self.template.append_writer(path.as_ref(), unique_value)
     }
 
     /// Generate a unique value for `key`, suitable for use in a
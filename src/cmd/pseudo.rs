@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use csv;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use CliResult;
+use config::{Config, Delimiter};
+use select::SelectColumns;
+use util;
+
+static USAGE: &'static str = "
+Pseudonymizes the values of the selected columns, e.g., to share data without
+revealing real identifiers.
+
+Each distinct value of a column is replaced with a token made of a prefix and
+a number, where the numbers are assigned from 0 in the order the values first
+appear. The same value always gets the same token, so that records can still
+be grouped or joined on the column. The prefix is the name of the column (or
+its index with --no-headers), unless --prefix is given.
+
+  Replace every user ID with 'user_id-0', 'user_id-1', etc.:
+  $ xsv pseudo user_id data.csv
+
+Each selected column has its own tokens, unless --shared is given. Then the
+same value gets the same token in all of the selected columns, which is
+useful when, e.g., both a 'sender' and a 'recipient' column hold user IDs. The
+prefix is then the name of the first selected column.
+
+With --hash, each value is instead replaced with its hex encoded HMAC-SHA256,
+using the salt given with --salt as the key. Unlike tokens, hashes don't
+depend on the order of the data, so they are the same across files and runs
+with the same salt. Anyone who knows the salt can recover a value by hashing
+guesses of it, so the salt should be kept secret.
+
+Empty fields are left empty.
+
+Usage:
+    xsv pseudo [options] [--] <selection> [<input>]
+    xsv pseudo --help
+
+pseudo options:
+    --shared               Give the same value the same token in all of the
+                           selected columns.
+    -p, --prefix <arg>     The prefix of the tokens.
+    --hash                 Replace values with a salted hash instead of a
+                           token. This requires --salt.
+    --salt <arg>           The salt (i.e., the secret key) to hash values
+                           with.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. (i.e., It will be pseudonymized like
+                           any other record.)
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    arg_selection: SelectColumns,
+    flag_shared: bool,
+    flag_prefix: Option<String>,
+    flag_hash: bool,
+    flag_salt: Option<String>,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_salt.is_some() && !args.flag_hash {
+        return fail!("--salt can only be used with --hash.");
+    }
+    if args.flag_hash && args.flag_salt.is_none() {
+        return fail!("--hash requires a --salt.");
+    }
+    if args.flag_hash && (args.flag_shared || args.flag_prefix.is_some()) {
+        return fail!("--hash cannot be used with --shared or --prefix.");
+    }
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers)
+        .select(args.arg_selection.clone());
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+
+    let headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+    rconfig.write_headers(&mut rdr, &mut wtr)?;
+
+    // The tokens of each selected column, which all share the first one's
+    // tokens with --shared.
+    let mut tokens: Vec<Tokens> = sel.iter().map(|&i| {
+        let prefix = match args.flag_prefix {
+            Some(ref prefix) => prefix.clone(),
+            None if rconfig.no_headers => (i + 1).to_string(),
+            None => String::from_utf8_lossy(&headers[i]).into_owned(),
+        };
+        Tokens::new(prefix)
+    }).collect();
+
+    let mut record = csv::ByteRecord::new();
+    let mut pseudo = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        let mut fields: Vec<Option<Vec<u8>>> = vec![None; record.len()];
+        for (j, &i) in sel.iter().enumerate() {
+            let field = &record[i];
+            if field.is_empty() {
+                continue;
+            }
+            fields[i] = Some(if let Some(ref salt) = args.flag_salt {
+                hex(&hmac_sha256(salt.as_bytes(), field)).into_bytes()
+            } else {
+                let j = if args.flag_shared { 0 } else { j };
+                tokens[j].get(field).into_bytes()
+            });
+        }
+        pseudo.clear();
+        for (field, new) in record.iter().zip(&fields) {
+            match *new {
+                None => pseudo.push_field(field),
+                Some(ref new) => pseudo.push_field(new),
+            }
+        }
+        wtr.write_byte_record(&pseudo)?;
+    }
+    Ok(wtr.flush()?)
+}
+
+/// Tokens assigns a numbered token to each distinct value, in the order they
+/// are first seen.
+struct Tokens {
+    prefix: String,
+    assigned: HashMap<Vec<u8>, usize>,
+}
+
+impl Tokens {
+    fn new(prefix: String) -> Tokens {
+        Tokens { prefix: prefix, assigned: HashMap::new() }
+    }
+
+    fn get(&mut self, value: &[u8]) -> String {
+        let next = self.assigned.len();
+        let n = *self.assigned.entry(value.to_vec()).or_insert(next);
+        format!("{}-{}", self.prefix, n)
+    }
+}
+
+/// Returns the HMAC-SHA256 of `data` with the key `key`.
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length, so this can't fail.
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().concat()
+}
@@ -0,0 +1,200 @@
+use std::io::Write;
+
+use csv;
+
+use CliResult;
+use config::{Config, Delimiter};
+use util::{self, Recorder};
+
+static USAGE: &'static str = "
+Renames the columns of CSV data.
+
+The new names are given as a comma separated list, in the same order as the
+columns. Names that contain a comma can be quoted, as in CSV data. There must
+be exactly one name for each column, unless --ignore-extra is set.
+
+  Rename the three columns of a file:
+  $ xsv rename id,name,'\"City, Country\"' data.csv
+
+Alternatively, --map renames only some columns, by giving pairs of an
+existing name and its new name, e.g., 'old:new,other:new_other' (or
+'old=new'). --map may also be given more than once. Every column with the
+old name is renamed, unless the name is followed by an index, as in
+'old[1]:new', which renames only the second column named 'old'.
+
+  $ xsv rename --map id:user_id data.csv
+  $ xsv rename --map id=user_id --map 'name[1]=alias' data.csv
+
+When --no-headers is set, the first row isn't replaced: the new names are
+written as a header row before it.
+
+Every other row is written exactly as it appears in the input, including its
+delimiters and quoting, so the new header row is written with the same
+delimiter as the input.
+
+Usage:
+    xsv rename [options] (--map <pairs>)... [<input>]
+    xsv rename [options] <new-names> [<input>]
+    xsv rename --help
+
+rename options:
+    -m, --map <pairs>      Rename only the columns named in these
+                           'old:new' pairs. May be given more than once.
+    --ignore-extra         Allow a different number of new names than
+                           columns. Extra names are ignored, and columns
+                           without a new name keep their name (or get an
+                           empty one with --no-headers).
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers, and the new names are added as a
+                           header row instead.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_new_names: Option<String>,
+    arg_input: Option<String>,
+    flag_map: Vec<String>,
+    flag_ignore_extra: bool,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+
+    let mut rdr = rconfig.from_reader(Recorder::new(rconfig.io_reader()?));
+    let headers = rdr.byte_headers()?.clone();
+    let start = if rconfig.no_headers {
+        0
+    } else {
+        rdr.position().byte() as usize
+    };
+    let renamed = match args.arg_new_names {
+        Some(_) if !args.flag_map.is_empty() => {
+            return fail!("New names and --map cannot both be given.");
+        }
+        Some(ref names) => args.rename_positional(&headers, names)?,
+        None => {
+            if rconfig.no_headers {
+                return fail!("--map cannot be used with --no-headers.");
+            }
+            let mut renamed = headers.clone();
+            for pairs in &args.flag_map {
+                renamed = rename_map(&headers, &renamed, pairs)?;
+            }
+            renamed
+        }
+    };
+
+    let mut wtr = Config::new(&args.flag_output).io_writer()?;
+    {
+        let mut hwtr = Config::new(&args.flag_output)
+            .delimiter(Some(Delimiter(rconfig.get_delimiter())))
+            .from_writer(&mut wtr);
+        hwtr.write_record(&renamed)?;
+        hwtr.flush()?;
+    }
+    rdr.into_inner().copy_from(start, &mut wtr)?;
+    Ok(wtr.flush()?)
+}
+
+impl Args {
+    fn rename_positional(&self, headers: &csv::ByteRecord, names: &str)
+                        -> CliResult<csv::ByteRecord> {
+        let names = parse_list(names)?;
+        if names.len() != headers.len() && !self.flag_ignore_extra {
+            return fail!(format!(
+                "{} new names were given, but there are {} columns. \
+                 (Use --ignore-extra to allow this.)",
+                names.len(), headers.len()));
+        }
+        let mut renamed = csv::ByteRecord::new();
+        for (i, header) in headers.iter().enumerate() {
+            match names.get(i) {
+                Some(name) => renamed.push_field(name.as_bytes()),
+                None if self.flag_no_headers => renamed.push_field(b""),
+                None => renamed.push_field(header),
+            }
+        }
+        Ok(renamed)
+    }
+}
+
+/// Renames the columns of `renamed` whose original names in `headers` are
+/// the old names in `pairs`.
+fn rename_map(
+    headers: &csv::ByteRecord,
+    renamed: &csv::ByteRecord,
+    pairs: &str,
+) -> CliResult<csv::ByteRecord> {
+    let mut renamed: Vec<Vec<u8>> =
+        renamed.iter().map(|h| h.to_vec()).collect();
+    for pair in parse_list(pairs)? {
+        let (old, new) = match pair.find(|c| c == ':' || c == '=') {
+            Some(i) => (&pair[..i], &pair[i+1..]),
+            None => {
+                return fail!(format!(
+                    "'{}' is not a pair like 'old:new'.", pair));
+            }
+        };
+        let (old, nth) = parse_indexed_name(old)?;
+        let mut found = 0;
+        for (i, header) in headers.iter().enumerate() {
+            if header != old.as_bytes() {
+                continue;
+            }
+            if nth.map_or(true, |nth| nth == found) {
+                renamed[i] = new.as_bytes().to_vec();
+            }
+            found += 1;
+        }
+        if found == 0 {
+            return fail!(format!("There is no column named '{}'.", old));
+        }
+        if let Some(nth) = nth {
+            if nth >= found {
+                return fail!(format!(
+                    "Index {} for the column named '{}' is out of bounds. \
+                     Must be >= 0 and <= {}.", nth, old, found - 1));
+            }
+        }
+    }
+    Ok(csv::ByteRecord::from(renamed))
+}
+
+/// Splits a name like `old[1]` into the name and the index of the column
+/// with that name.
+fn parse_indexed_name(name: &str) -> CliResult<(&str, Option<usize>)> {
+    if !name.ends_with(']') {
+        return Ok((name, None));
+    }
+    let start = match name.rfind('[') {
+        None => return Ok((name, None)),
+        Some(start) => start,
+    };
+    match name[start+1..name.len()-1].parse() {
+        Ok(nth) => Ok((&name[..start], Some(nth))),
+        Err(_) => fail!(format!(
+            "Could not parse the index in '{}'.", name)),
+    }
+}
+
+/// Parses a comma separated list, where items may be quoted like CSV fields.
+fn parse_list(list: &str) -> CliResult<Vec<String>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(list.as_bytes());
+    let mut record = csv::StringRecord::new();
+    rdr.read_record(&mut record)?;
+    Ok(record.iter().map(|s| s.to_owned()).collect())
+}
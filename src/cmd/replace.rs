@@ -0,0 +1,129 @@
+use csv;
+use regex;
+use regex::bytes::{NoExpand, RegexBuilder};
+
+use CliResult;
+use config::{Config, Delimiter};
+use select::SelectColumns;
+use util;
+
+static USAGE: &'static str = "
+Replaces every match of a regex in the fields of CSV data.
+
+The replacement may refer to the groups captured by the regex with '$N' or
+'$name', e.g., '$1' is replaced by the text matched by the first group and
+'$0' by the whole match. Use '$$' for a literal '$'.
+
+The columns to rewrite can be limited with '--select'. The headers are never
+rewritten. With '--first', only the first match in each field is replaced.
+
+To check a replacement before making it, '--dry-run' only prints how many
+fields would be changed (to stderr), without writing any CSV data.
+
+  Normalize phone numbers like '555.123.4567' to '(555) 123-4567':
+  $ xsv replace -s phone '^(\\d+)\\.(\\d+)\\.(\\d+)$' '($1) $2-$3' data.csv
+
+Usage:
+    xsv replace [options] <pattern> <replacement> [<input>]
+    xsv replace --help
+
+replace options:
+    -i, --ignore-case      Case insensitive matching. This is equivalent to
+                           prefixing the regex with '(?i)'.
+    -F, --literal          Treat the pattern as a fixed string instead of a
+                           regex. The replacement is then used as is, i.e.,
+                           '$' has no special meaning in it.
+    -s, --select <arg>     Select the columns to rewrite. See 'xsv select -h'
+                           for the full syntax.
+    -f, --first            Only replace the first match in each field.
+    --dry-run              Print the number of fields that would be changed
+                           to stderr instead of writing the output.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. (i.e., It is rewritten like any other
+                           row.)
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    arg_pattern: String,
+    arg_replacement: String,
+    flag_ignore_case: bool,
+    flag_literal: bool,
+    flag_first: bool,
+    flag_dry_run: bool,
+    flag_select: SelectColumns,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let pattern = if args.flag_literal {
+        regex::escape(&args.arg_pattern)
+    } else {
+        args.arg_pattern.clone()
+    };
+    let pattern = RegexBuilder::new(&pattern)
+        .case_insensitive(args.flag_ignore_case)
+        .build()?;
+    let replacement = args.arg_replacement.as_bytes();
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers)
+        .select(args.flag_select);
+
+    let mut rdr = rconfig.reader()?;
+    // Nothing is written with --dry-run, so don't touch the output file.
+    let output =
+        if args.flag_dry_run { None } else { args.flag_output.clone() };
+    let mut wtr = Config::new(&output).writer()?;
+
+    let headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+    let mut selected = vec![false; headers.len()];
+    for &i in sel.iter() {
+        selected[i] = true;
+    }
+
+    if !args.flag_dry_run {
+        rconfig.write_headers(&mut rdr, &mut wtr)?;
+    }
+    // A limit of 0 means replacing every match.
+    let limit = if args.flag_first { 1 } else { 0 };
+    let mut record = csv::ByteRecord::new();
+    let mut replaced = csv::ByteRecord::new();
+    let mut changed = 0u64;
+    while rdr.read_byte_record(&mut record)? {
+        replaced.clear();
+        for (i, field) in record.iter().enumerate() {
+            if !selected.get(i).map_or(false, |&s| s) {
+                replaced.push_field(field);
+                continue;
+            }
+            let new = if args.flag_literal {
+                pattern.replacen(field, limit, NoExpand(replacement))
+            } else {
+                pattern.replacen(field, limit, replacement)
+            };
+            if *new != *field {
+                changed += 1;
+            }
+            replaced.push_field(&new);
+        }
+        if !args.flag_dry_run {
+            wtr.write_byte_record(&replaced)?;
+        }
+    }
+    if args.flag_dry_run {
+        werr!("{} fields would be changed.", changed);
+    }
+    Ok(wtr.flush()?)
+}
@@ -0,0 +1,87 @@
+use std::fs;
+
+use csv;
+
+use CliResult;
+use config::{Config, Delimiter};
+use index::Indexed;
+use util;
+
+static USAGE: &'static str = "
+Reverses the order of the records in CSV data. The header row, if any, stays
+at the top.
+
+Without an index, all of the records are read into memory. If an index exists
+(see 'xsv index'), then the records are instead read one at a time by seeking
+backwards from the last record, which uses constant memory.
+
+Usage:
+    xsv reverse [options] [<input>]
+    xsv reverse --help
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. Namely, it will be reversed with the
+                           rest of the rows. Otherwise, the first row will
+                           always appear as the header row in the output.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    match args.rconfig().indexed()? {
+        None => args.no_index(),
+        Some(idxed) => args.with_index(idxed),
+    }
+}
+
+impl Args {
+    fn no_index(&self) -> CliResult<()> {
+        let mut rdr = self.rconfig().reader()?;
+        let mut wtr = self.wconfig().writer()?;
+        self.rconfig().write_headers(&mut rdr, &mut wtr)?;
+
+        let records = rdr.byte_records().collect::<Result<Vec<_>, _>>()?;
+        for r in records.iter().rev() {
+            wtr.write_byte_record(r)?;
+        }
+        Ok(wtr.flush()?)
+    }
+
+    fn with_index(
+        &self,
+        mut idx: Indexed<fs::File, fs::File>,
+    ) -> CliResult<()> {
+        let mut wtr = self.wconfig().writer()?;
+        self.rconfig().write_headers(&mut *idx, &mut wtr)?;
+
+        let mut record = csv::ByteRecord::new();
+        for i in (0..idx.count()).rev() {
+            idx.seek(i)?;
+            idx.read_byte_record(&mut record)?;
+            wtr.write_byte_record(&record)?;
+        }
+        Ok(wtr.flush()?)
+    }
+
+    fn rconfig(&self) -> Config {
+        Config::new(&self.arg_input)
+            .delimiter(self.flag_delimiter)
+            .no_headers(self.flag_no_headers)
+    }
+
+    fn wconfig(&self) -> Config {
+        Config::new(&self.flag_output)
+    }
+}
@@ -1,11 +1,13 @@
+use std::collections::HashMap;
 use std::io;
 
 use csv;
-use rand::Rng;
+use rand::{Rng, SeedableRng, StdRng};
 
 use CliResult;
 use config::{Config, Delimiter};
 use index::Indexed;
+use select::SelectColumns;
 use util;
 
 static USAGE: &'static str = "
@@ -23,10 +25,37 @@ once, which is necessary to provide a uniform random sample. If you wish to
 limit the number of records visited, use the 'xsv slice' command to pipe into
 'xsv sample'.
 
+Alternatively, --rate keeps each record independently with the given
+probability, e.g., '--rate 0.01' keeps about 1% of the records. The size of
+the sample then isn't exact, but the records are written as they are read,
+without keeping any of them in memory, and in their original order.
+
+With --stratify, the records are grouped by their value in the given column,
+and each group is sampled independently, so that every group contributes up
+to <sample-size> records (and a group with fewer records contributes all of
+them). This keeps one reservoir of up to <sample-size> records in memory for
+each distinct value of the column. The groups are written in the order in
+which their values first appear.
+
+The sample is random unless --seed is given, in which case running this
+command again with the same seed on the same input (and on the same
+platform) produces exactly the same sample, in the same order.
+
 Usage:
+    xsv sample [options] --rate <p> [<input>]
     xsv sample [options] <sample-size> [<input>]
     xsv sample --help
 
+sample options:
+    --seed <number>        Seed the random number generator, so that the
+                           sample is reproducible.
+    --rate <p>             Keep each record with probability <p>, which must
+                           be between 0 and 1, instead of sampling a fixed
+                           number of records.
+    --stratify <column>    Sample up to <sample-size> records from each
+                           group of records with the same value in
+                           <column>.
+
 Common options:
     -h, --help             Display this message
     -o, --output <file>    Write output to <file> instead of stdout.
@@ -41,8 +70,11 @@ Common options:
 #[derive(RustcDecodable)]
 struct Args {
     arg_input: Option<String>,
-    arg_sample_size: u64,
+    arg_sample_size: Option<u64>,
+    flag_rate: Option<f64>,
+    flag_stratify: Option<SelectColumns>,
     flag_output: Option<String>,
+    flag_seed: Option<usize>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
 }
@@ -52,24 +84,61 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
         .no_headers(args.flag_no_headers);
-    let sample_size = args.arg_sample_size;
+    let mut rng = match args.flag_seed {
+        None => StdRng::new()?,
+        Some(seed) => StdRng::from_seed(&[seed][..]),
+    };
 
     let mut wtr = Config::new(&args.flag_output).writer()?;
+    if args.flag_rate.is_some() && args.flag_stratify.is_some() {
+        return fail!("--rate and --stratify cannot be used together.");
+    }
+    if let Some(rate) = args.flag_rate {
+        if !(rate >= 0.0 && rate <= 1.0) {
+            return fail!("--rate must be between 0 and 1.");
+        }
+        let mut rdr = rconfig.reader()?;
+        rconfig.write_headers(&mut rdr, &mut wtr)?;
+        let mut record = csv::ByteRecord::new();
+        while rdr.read_byte_record(&mut record)? {
+            if rng.gen::<f64>() < rate {
+                wtr.write_byte_record(&record)?;
+            }
+        }
+        return Ok(wtr.flush()?);
+    }
+
+    let sample_size = args.arg_sample_size.unwrap();
+    if let Some(ref column) = args.flag_stratify {
+        let mut rdr = rconfig.reader()?;
+        let headers = rdr.byte_headers()?.clone();
+        let sel = column.selection(&headers, !rconfig.no_headers)?;
+        if sel.len() != 1 {
+            return fail!("--stratify must select exactly one column.");
+        }
+        rconfig.write_headers(&mut rdr, &mut wtr)?;
+        let sampled = sample_stratified(&mut rdr, sel[0], sample_size,
+                                        &mut rng)?;
+        for row in sampled.into_iter() {
+            wtr.write_byte_record(&row)?;
+        }
+        return Ok(wtr.flush()?);
+    }
     let sampled = match rconfig.indexed()? {
         Some(mut idx) => {
             if do_random_access(sample_size, idx.count()) {
                 rconfig.write_headers(&mut *idx, &mut wtr)?;
-                sample_random_access(&mut idx, sample_size)?
+                sample_random_access(&mut idx, sample_size, &mut rng)?
             } else {
                 let mut rdr = rconfig.reader()?;
                 rconfig.write_headers(&mut rdr, &mut wtr)?;
-                sample_reservoir(&mut rdr, sample_size)?
+                sample_reservoir(&mut rdr, sample_size, &mut rng)?
             }
         }
         _ => {
             let mut rdr = rconfig.reader()?;
             rconfig.write_headers(&mut rdr, &mut wtr)?;
-            sample_reservoir(&mut rdr, sample_size)?
+            sample_reservoir(&mut rdr, sample_size, &mut rng)?
         }
     };
     for row in sampled.into_iter() {
@@ -78,14 +147,14 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     Ok(wtr.flush()?)
 }
 
-fn sample_random_access<R, I>(
+fn sample_random_access<R, I, G>(
     idx: &mut Indexed<R, I>,
     sample_size: u64,
+    rng: &mut G,
 ) -> CliResult<Vec<csv::ByteRecord>>
-where R: io::Read + io::Seek, I: io::Read + io::Seek
+where R: io::Read + io::Seek, I: io::Read + io::Seek, G: Rng
 {
     let mut all_indices = (0..idx.count()).collect::<Vec<_>>();
-    let mut rng = ::rand::thread_rng();
     rng.shuffle(&mut *all_indices);
 
     let mut sampled = Vec::with_capacity(sample_size as usize);
@@ -96,9 +165,10 @@ where R: io::Read + io::Seek, I: io::Read + io::Seek
     Ok(sampled)
 }
 
-fn sample_reservoir<R: io::Read>(
+fn sample_reservoir<R: io::Read, G: Rng>(
     rdr: &mut csv::Reader<R>,
     sample_size: u64,
+    rng: &mut G,
 ) -> CliResult<Vec<csv::ByteRecord>> {
     // The following algorithm has been adapted from:
     // http://en.wikipedia.org/wiki/Reservoir_sampling
@@ -109,7 +179,6 @@ fn sample_reservoir<R: io::Read>(
     }
 
     // Now do the sampling.
-    let mut rng = ::rand::thread_rng();
     for (i, row) in records {
         let random = rng.gen_range(0, i+1);
         if random < sample_size as usize {
@@ -119,6 +188,44 @@ fn sample_reservoir<R: io::Read>(
     Ok(reservoir)
 }
 
+/// Samples up to `sample_size` records for each distinct value of the
+/// `column` field, with a reservoir per value.
+fn sample_stratified<R: io::Read, G: Rng>(
+    rdr: &mut csv::Reader<R>,
+    column: usize,
+    sample_size: u64,
+    rng: &mut G,
+) -> CliResult<Vec<csv::ByteRecord>> {
+    let sample_size = sample_size as usize;
+    // The values in the order they first appear, and for each one, the
+    // number of records seen so far and its reservoir.
+    let mut values: Vec<Vec<u8>> = vec![];
+    let mut groups: HashMap<Vec<u8>, (usize, Vec<csv::ByteRecord>)> =
+        HashMap::new();
+    for row in rdr.byte_records() {
+        let row = row?;
+        let value = row.get(column).unwrap_or(b"").to_vec();
+        if !groups.contains_key(&value) {
+            values.push(value.clone());
+        }
+        let group = groups.entry(value).or_insert_with(|| (0, vec![]));
+        group.0 += 1;
+        if group.1.len() < sample_size {
+            group.1.push(row);
+        } else {
+            let random = rng.gen_range(0, group.0);
+            if random < sample_size {
+                group.1[random] = row;
+            }
+        }
+    }
+    let mut sampled = vec![];
+    for value in values {
+        sampled.extend(groups.remove(&value).unwrap().1);
+    }
+    Ok(sampled)
+}
+
 fn do_random_access(sample_size: u64, total: u64) -> bool {
     sample_size <= (total / 10)
 }
@@ -1,9 +1,11 @@
 use csv;
-use regex::bytes::RegexBuilder;
+use memchr::memmem;
+use regex;
+use regex::bytes::{Regex, RegexBuilder};
 
 use CliResult;
 use config::{Config, Delimiter};
-use select::SelectColumns;
+use select::{SelectColumns, Selection};
 use util;
 
 static USAGE: &'static str = "
@@ -12,10 +14,39 @@ Filters CSV data by whether the given regex matches a row.
 The regex is applied to each field in each row, and if any field matches,
 then the row is written to the output. The columns to search can be limited
 with the '--select' flag (but the full row is still written to the output if
-there is a match).
+there is a match). With '--exact', a field only matches if the regex matches
+all of it, rather than only part of it.
+
+Instead of a regex, '--patterns' can give a file with one regex per line. A
+row matches if any of them matches. Empty lines and lines starting with '#'
+are ignored.
+
+Each '--where <column>:<regex>' clause only matches a row if its regex matches
+a field in the given column (or columns, using the same syntax as '--select').
+The column ends at the first ':' that isn't inside quotes. Clauses can be
+given more than once, and are combined with the regex (if any), so that a row
+only matches if all of them match. '--ignore-case', '--exact' and '--literal'
+apply to the regexes of clauses too.
+
+  Find the errors in EU regions:
+  $ xsv search --where status:^error$ --where region:^eu- data.csv
+
+Instead of the matching rows, '--count' prints only how many rows matched.
+With '--flag', every row is written, with a new column at the end that has
+the row number (starting at 1) of each matching row, or 0 if it didn't match.
+With '--invert-match' as well, the rows that didn't match are flagged instead.
+
+Fields are searched as bytes, so fields that aren't valid UTF-8 are searched
+too. By default, classes like '\\w' and '.' match Unicode characters, which
+can be turned off with '--no-unicode' so that they only match ASCII
+characters (and '.' matches any byte). Since fields may contain line breaks,
+'--multiline' makes '^' and '$' match at the start and end of each line of a
+field, and '--dot-matches-newline' lets '.' match line breaks.
 
 Usage:
-    xsv search [options] <regex> [<input>]
+    xsv search [options] [--where <clause>]... --patterns <file> [<input>]
+    xsv search [options] (--where <clause>)... [<input>]
+    xsv search [options] [--where <clause>]... <regex> [<input>]
     xsv search --help
 
 search options:
@@ -23,7 +54,35 @@ search options:
                            prefixing the regex with '(?i)'.
     -s, --select <arg>     Select the columns to search. See 'xsv select -h'
                            for the full syntax.
-    -v, --invert-match     Select only rows that did not match
+    -v, --invert-match     Select only rows that did not match, i.e., rows
+                           where none of the searched fields match.
+    -x, --exact            Only match whole fields. This is equivalent to
+                           wrapping the regex in '^(?:' and ')$' (even when
+                           '^' and '$' match at line breaks).
+    -F, --literal          Treat the regex as a fixed string, i.e., characters
+                           like '.' and '(' only match themselves. This is
+                           a plain substring search, so --ignore-case only
+                           ignores the case of ASCII letters.
+    --no-unicode           Make classes like '\\w' only match ASCII
+                           characters. This is equivalent to prefixing the
+                           regex with '(?-u)'.
+    --multiline            Make '^' and '$' match at line breaks in fields.
+                           This is equivalent to prefixing the regex with
+                           '(?m)'.
+    --dot-matches-newline  Make '.' match line breaks. This is equivalent to
+                           prefixing the regex with '(?s)'.
+    -c, --count            Print the number of matching rows instead of
+                           the rows.
+    -l, --limit <arg>      Stop searching after this many rows have matched.
+                           When 0, all rows are searched. With --flag, the
+                           rest of the rows are still written, but aren't
+                           flagged. [default: 0]
+    -f, --flag <column>    Write all rows, with a new column called <column>
+                           that flags the matching rows.
+    -p, --patterns <file>  Read the regexes from <file> instead of the
+                           command line.
+    -w, --where <clause>   Only match rows where the regex of <clause>
+                           matches in its column, e.g. 'region:^eu-'.
 
 Common options:
     -h, --help             Display this message
@@ -38,43 +97,236 @@ Common options:
 #[derive(RustcDecodable)]
 struct Args {
     arg_input: Option<String>,
-    arg_regex: String,
+    arg_regex: Option<String>,
+    flag_patterns: Option<String>,
+    flag_where: Vec<String>,
     flag_select: SelectColumns,
     flag_output: Option<String>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
     flag_invert_match: bool,
     flag_ignore_case: bool,
+    flag_exact: bool,
+    flag_literal: bool,
+    flag_no_unicode: bool,
+    flag_multiline: bool,
+    flag_dot_matches_newline: bool,
+    flag_count: bool,
+    flag_limit: u64,
+    flag_flag: Option<String>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
-    let pattern = RegexBuilder::new(&*args.arg_regex)
-        .case_insensitive(args.flag_ignore_case)
-        .build()?;
+    if args.flag_count && args.flag_flag.is_some() {
+        return fail!("--count and --flag cannot be used together.");
+    }
+    let pattern = match (&args.flag_patterns, &args.arg_regex) {
+        (&Some(ref path), _) => {
+            Some(args.matcher(&args.read_patterns(path)?)?)
+        }
+        (&None, &Some(ref regex)) => Some(args.matcher(&[regex.clone()])?),
+        (&None, &None) => None,
+    };
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
         .no_headers(args.flag_no_headers)
-        .select(args.flag_select);
+        .select(args.flag_select.clone());
 
     let mut rdr = rconfig.reader()?;
     let mut wtr = Config::new(&args.flag_output).writer()?;
 
     let headers = rdr.byte_headers()?.clone();
     let sel = rconfig.selection(&headers)?;
+    let clauses = args.flag_where
+        .iter()
+        .map(|clause| args.clause(clause, &headers))
+        .collect::<CliResult<Vec<_>>>()?;
 
-    if !rconfig.no_headers {
-        wtr.write_record(&headers)?;
+    if !rconfig.no_headers && !args.flag_count {
+        if let Some(ref column) = args.flag_flag {
+            let mut headers = headers.clone();
+            headers.push_field(column.as_bytes());
+            wtr.write_record(&headers)?;
+        } else {
+            wtr.write_record(&headers)?;
+        }
     }
     let mut record = csv::ByteRecord::new();
+    let (mut rowi, mut count) = (0u64, 0u64);
     while rdr.read_byte_record(&mut record)? {
-        let mut m = sel.select(&record).any(|f| pattern.is_match(f));
-        if args.flag_invert_match {
+        rowi += 1;
+        let searching = args.flag_limit == 0 || count < args.flag_limit;
+        let mut m = searching && pattern.as_ref().map_or(true, |pattern| {
+            sel.select(&record).any(|f| pattern.is_match(f))
+        }) && clauses.iter().all(|clause| clause.is_match(&record));
+        if args.flag_invert_match && searching {
             m = !m;
         }
         if m {
+            count += 1;
+        }
+        if args.flag_flag.is_some() {
+            let flag = if m { rowi } else { 0 };
+            record.push_field(flag.to_string().as_bytes());
+            wtr.write_byte_record(&record)?;
+        } else if m && !args.flag_count {
             wtr.write_byte_record(&record)?;
         }
+        if m && count == args.flag_limit && args.flag_flag.is_none() {
+            break;
+        }
+    }
+    if args.flag_count {
+        wtr.write_record(&[count.to_string()])?;
     }
     Ok(wtr.flush()?)
 }
+
+/// Clause is a parsed '--where' clause.
+struct Clause {
+    sel: Selection,
+    matcher: Matcher,
+}
+
+impl Clause {
+    fn is_match(&self, record: &csv::ByteRecord) -> bool {
+        self.sel.select(record).any(|f| self.matcher.is_match(f))
+    }
+}
+
+/// Matcher finds any of the patterns in a field, with a regex or, with
+/// --literal, with a substring search.
+enum Matcher {
+    Regex(Regex),
+    Literal(Literal),
+}
+
+/// Literal holds the fixed strings of --literal, which are lowercased when
+/// the case of ASCII letters is ignored.
+struct Literal {
+    finders: Vec<memmem::Finder<'static>>,
+    exact: bool,
+    ignore_case: bool,
+}
+
+impl Matcher {
+    fn is_match(&self, field: &[u8]) -> bool {
+        match *self {
+            Matcher::Regex(ref regex) => regex.is_match(field),
+            Matcher::Literal(ref lit) => lit.is_match(field),
+        }
+    }
+}
+
+impl Literal {
+    fn is_match(&self, field: &[u8]) -> bool {
+        let lowered;
+        let field = if self.ignore_case && !self.exact {
+            lowered = field.to_ascii_lowercase();
+            &lowered[..]
+        } else {
+            field
+        };
+        self.finders.iter().any(|finder| {
+            if !self.exact {
+                finder.find(field).is_some()
+            } else if self.ignore_case {
+                field.eq_ignore_ascii_case(finder.needle())
+            } else {
+                field == finder.needle()
+            }
+        })
+    }
+}
+
+impl Args {
+    /// Builds a matcher that matches if any of `patterns` does.
+    fn matcher(&self, patterns: &[String]) -> CliResult<Matcher> {
+        if !self.flag_literal {
+            let regexes: Vec<_> = patterns.iter()
+                .map(|p| format!("(?:{})", p))
+                .collect();
+            return Ok(Matcher::Regex(self.compile(&regexes.join("|"))?));
+        }
+        let finders = patterns.iter().map(|p| {
+            let needle = if self.flag_ignore_case {
+                p.as_bytes().to_ascii_lowercase()
+            } else {
+                p.as_bytes().to_vec()
+            };
+            memmem::Finder::new(&needle).into_owned()
+        }).collect();
+        Ok(Matcher::Literal(Literal {
+            finders: finders,
+            exact: self.flag_exact,
+            ignore_case: self.flag_ignore_case,
+        }))
+    }
+
+    /// Compiles `regex` with the options that apply to every regex.
+    fn compile(&self, regex: &str) -> CliResult<Regex> {
+        let regex = if self.flag_exact {
+            format!(r"\A(?:{})\z", regex)
+        } else {
+            regex.to_owned()
+        };
+        Ok(self.build(&regex)?)
+    }
+
+    fn build(&self, regex: &str) -> Result<Regex, regex::Error> {
+        RegexBuilder::new(regex)
+            .case_insensitive(self.flag_ignore_case)
+            .unicode(!self.flag_no_unicode)
+            .multi_line(self.flag_multiline)
+            .dot_matches_new_line(self.flag_dot_matches_newline)
+            .build()
+    }
+
+    /// Parses a '--where' clause, and finds its columns in `headers`.
+    fn clause(
+        &self,
+        clause: &str,
+        headers: &csv::ByteRecord,
+    ) -> CliResult<Clause> {
+        let mut quoted = false;
+        let split = clause.char_indices().find(|&(_, c)| {
+            if c == '"' {
+                quoted = !quoted;
+            }
+            c == ':' && !quoted
+        });
+        let (columns, regex) = match split {
+            None => {
+                return fail!(format!(
+                    "Could not find ':' in the clause '{}'.", clause));
+            }
+            Some((i, _)) => (&clause[..i], &clause[i + 1..]),
+        };
+        let sel = SelectColumns::parse(columns)?;
+        let sel = match sel.selection(headers, !self.flag_no_headers) {
+            Ok(sel) => sel,
+            Err(err) if self.flag_no_headers => return fail!(err),
+            Err(err) => {
+                let names: Vec<_> = headers
+                    .iter()
+                    .map(|name| String::from_utf8_lossy(name))
+                    .collect();
+                return fail!(format!("{} The available columns are: {}",
+                                     err, names.join(", ")));
+            }
+        };
+        let matcher = self.matcher(&[regex.to_owned()])?;
+        Ok(Clause { sel: sel, matcher: matcher })
+    }
+
+    /// Reads the regexes in `path`, and checks that they are valid.
+    fn read_patterns(&self, path: &str) -> CliResult<Vec<String>> {
+        util::read_patterns(path, |pattern| {
+            if self.flag_literal {
+                return Ok(());
+            }
+            self.build(pattern).map(|_| ()).map_err(|err| err.to_string())
+        })
+    }
+}
@@ -0,0 +1,126 @@
+use csv;
+use regex::bytes::{RegexBuilder, RegexSetBuilder};
+
+use CliResult;
+use config::{Config, Delimiter};
+use select::SelectColumns;
+use util;
+
+static USAGE: &'static str = "
+Filters CSV data by whether any of the regexes in a file matches a row.
+
+The file has one regex per line (empty lines and lines starting with '#' are
+ignored), and the regexes are numbered from 1 in the order they appear. Every regex is applied to each field
+in each row, and if any regex matches any field, then the row is written to
+the output. The columns to search can be limited with the '--select' flag
+(but the full row is still written to the output if there is a match).
+
+With '--flag', every row is written, with a new column at the end that lists
+the numbers of the regexes that matched the row, separated by ';', or 0 if
+none of them did.
+
+Usage:
+    xsv searchset [options] <patterns-file> [<input>]
+    xsv searchset --help
+
+searchset options:
+    -i, --ignore-case      Case insensitive search. This is equivalent to
+                           prefixing each regex with '(?i)'.
+    -s, --select <arg>     Select the columns to search. See 'xsv select -h'
+                           for the full syntax.
+    -v, --invert-match     Select only rows that did not match any regex.
+    -f, --flag <column>    Write all rows, with a new column called <column>
+                           that lists the regexes that matched each row.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. (i.e., They are not searched, analyzed,
+                           sliced, etc.)
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    arg_patterns_file: String,
+    flag_select: SelectColumns,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+    flag_invert_match: bool,
+    flag_ignore_case: bool,
+    flag_flag: Option<String>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_invert_match && args.flag_flag.is_some() {
+        return fail!("--invert-match and --flag cannot be used together.");
+    }
+    let patterns = util::read_patterns(&args.arg_patterns_file, |pattern| {
+        RegexBuilder::new(pattern)
+            .case_insensitive(args.flag_ignore_case)
+            .build()
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    })?;
+    let set = RegexSetBuilder::new(&patterns)
+        .case_insensitive(args.flag_ignore_case)
+        .build()?;
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers)
+        .select(args.flag_select);
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+
+    let mut headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+
+    if !rconfig.no_headers {
+        if let Some(ref column) = args.flag_flag {
+            headers.push_field(column.as_bytes());
+        }
+        wtr.write_record(&headers)?;
+    }
+    let mut record = csv::ByteRecord::new();
+    let mut matched = vec![false; set.len()];
+    while rdr.read_byte_record(&mut record)? {
+        if args.flag_flag.is_none() {
+            let mut m = sel.select(&record).any(|f| set.is_match(f));
+            if args.flag_invert_match {
+                m = !m;
+            }
+            if m {
+                wtr.write_byte_record(&record)?;
+            }
+            continue;
+        }
+
+        for m in &mut matched {
+            *m = false;
+        }
+        for field in sel.select(&record) {
+            for i in set.matches(field).iter() {
+                matched[i] = true;
+            }
+        }
+        let numbers: Vec<String> = matched
+            .iter()
+            .enumerate()
+            .filter(|&(_, &m)| m)
+            .map(|(i, _)| (i + 1).to_string())
+            .collect();
+        if numbers.is_empty() {
+            record.push_field(b"0");
+        } else {
+            record.push_field(numbers.join(";").as_bytes());
+        }
+        wtr.write_byte_record(&record)?;
+    }
+    Ok(wtr.flush()?)
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use csv;
 
 use CliResult;
@@ -11,7 +13,11 @@ Select columns from CSV data efficiently.
 This command lets you manipulate the columns in CSV data. You can re-order
 them, duplicate them or drop them. Columns can be referenced by index or by
 name if there is a header row (duplicate column names can be disambiguated with
-more indexing). Finally, column ranges can be specified.
+more indexing). Column ranges can be specified. Finally, columns can be
+selected by a regex that matches their names, written between slashes, which
+selects every matching column in order. (A '/' in the regex can be escaped
+with '\\/'.) A '*' selects every column that isn't selected elsewhere in
+the selection, in their original order, and can be used at most once.
 
   Select the first and fourth columns:
   $ xsv select 1,4
@@ -23,6 +29,21 @@ more indexing). Finally, column ranges can be specified.
   Ignore the first 2 columns (by range and by omission):
   $ xsv select 3-
   $ xsv select '!1-2'
+  $ xsv select --not 1-2
+
+  Select all of the columns except 'id' and 'ssn', in their original order:
+  $ xsv select '!id,ssn'
+
+  Index from the end, e.g., select the last column, drop the last column or
+  select the last 3 columns:
+  $ xsv select -- -1
+  $ xsv select 1--2
+  $ xsv select -- -3--1
+
+  (A range without a start, like '-Header2', selects all of the columns up to
+  'Header2'. With indices, use '1-2' instead, since '-2' is the second column
+  from the end. This is a breaking change: in earlier versions, '-2' was the
+  same as '1-2', in every command that takes a selection.)
 
   Select the third column named 'Foo':
   $ xsv select 'Foo[2]'
@@ -30,13 +51,49 @@ more indexing). Finally, column ranges can be specified.
   Re-order and duplicate columns arbitrarily:
   $ xsv select 3-1,Header3-Header1,Header1,Foo[2],Header1
 
+  Select 'id' and every column whose name is 'sales_' followed by a year:
+  $ xsv select 'id,/^sales_\\d{4}$/'
+
+  Move 'id' and 'created_at' to the front, keeping the other columns:
+  $ xsv select 'id,created_at,*'
+
   Quote column names that conflict with selector syntax:
   $ xsv select '\"Date - Opening\",\"Date - Actual Closing\"'
 
+When a column name appears more than once among the selected columns, e.g.,
+in exports that repeat a column, --dedup keeps only its first occurrence.
+Alternatively, --dedup-by-value only drops a later occurrence if every one of
+its fields is equal to the field of an earlier occurrence in the same record.
+(With --no-headers, any earlier column is an occurrence.) This requires
+reading all of the CSV data into memory.
+
+By default, every record must have the same number of fields. With --pad,
+records may have any number of fields: each record gets exactly one field for
+each selected column, which is empty if the record is too short, and extra
+fields are ignored. Columns selected by index may also be past the end of the
+header row (or the first record with --no-headers), in which case their names
+are empty, so that, e.g., '--pad 1-10' always writes 10 fields.
+
 Usage:
     xsv select [options] [--] <selection> [<input>]
     xsv select --help
 
+select options:
+    --not                  Select all of the columns except the given ones,
+                           like a selection that starts with '!'.
+    --allow-empty          Don't fail if a regex doesn't match any columns,
+                           or if no columns are selected.
+    --pad                  Allow records with a different number of fields,
+                           and write an empty field for each selected column
+                           that a record doesn't have.
+    -i, --ignore-case      Match column names case insensitively. (This
+                           doesn't apply to regexes, which can be made case
+                           insensitive with '(?i)'.)
+    --dedup                Drop selected columns whose name is the same as
+                           an earlier selected column.
+    --dedup-by-value       Drop selected columns whose name and values are
+                           the same as an earlier selected column.
+
 Common options:
     -h, --help             Display this message
     -o, --output <file>    Write output to <file> instead of stdout.
@@ -51,6 +108,12 @@ Common options:
 struct Args {
     arg_input: Option<String>,
     arg_selection: SelectColumns,
+    flag_not: bool,
+    flag_allow_empty: bool,
+    flag_ignore_case: bool,
+    flag_dedup: bool,
+    flag_dedup_by_value: bool,
+    flag_pad: bool,
     flag_output: Option<String>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
@@ -58,25 +121,80 @@ struct Args {
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_dedup && args.flag_dedup_by_value {
+        return fail!("--dedup and --dedup-by-value cannot be used together.");
+    }
+    if args.flag_dedup && args.flag_no_headers {
+        return fail!("--dedup cannot be used with --no-headers, since there \
+                      are no names to compare.");
+    }
 
+    let selection = args.arg_selection
+        .invert(args.flag_not)
+        .allow_empty(args.flag_allow_empty)
+        .ignore_case(args.flag_ignore_case);
+    let max_index = selection.max_index();
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
         .no_headers(args.flag_no_headers)
-        .select(args.arg_selection);
+        .flexible(args.flag_pad)
+        .select(selection);
 
     let mut rdr = rconfig.reader()?;
     let mut wtr = Config::new(&args.flag_output).writer()?;
 
-    let headers = rdr.byte_headers()?.clone();
-    let sel = rconfig.selection(&headers)?;
+    let mut headers = rdr.byte_headers()?.clone();
+    if args.flag_pad {
+        while headers.len() < max_index {
+            headers.push_field(b"");
+        }
+    }
+    let mut sel: Vec<usize> = rconfig.selection(&headers)?.to_vec();
+    if sel.is_empty() && !args.flag_allow_empty {
+        return fail!("The selection doesn't leave any columns. \
+                      (Use --allow-empty to allow this.)");
+    }
+    if args.flag_dedup {
+        let mut seen = HashSet::new();
+        sel.retain(|&i| seen.insert(&headers[i]));
+    }
+    let mut all = vec![];
+    if args.flag_dedup_by_value {
+        all = rdr.byte_records().collect::<Result<Vec<_>, _>>()?;
+        sel = dedup_by_value(&headers, &all, sel, rconfig.no_headers);
+    }
 
     if !rconfig.no_headers {
         wtr.write_record(sel.iter().map(|&i| &headers[i]))?;
     }
+    for record in &all {
+        wtr.write_record(sel.iter().map(|&i| record.get(i).unwrap_or(b"")))?;
+    }
     let mut record = csv::ByteRecord::new();
     while rdr.read_byte_record(&mut record)? {
-        wtr.write_record(sel.iter().map(|&i| &record[i]))?;
+        wtr.write_record(sel.iter().map(|&i| record.get(i).unwrap_or(b"")))?;
     }
     wtr.flush()?;
     Ok(())
 }
+
+/// Drops the columns in `sel` whose fields in all of `records` are equal to
+/// those of an earlier column in `sel` with the same name.
+fn dedup_by_value(
+    headers: &csv::ByteRecord,
+    records: &[csv::ByteRecord],
+    sel: Vec<usize>,
+    no_headers: bool,
+) -> Vec<usize> {
+    let mut kept = vec![];
+    for (j, &i) in sel.iter().enumerate() {
+        let dupe = sel[..j].iter().any(|&k| {
+            (no_headers || headers[k] == headers[i])
+            && records.iter().all(|r| r.get(k) == r.get(i))
+        });
+        if !dupe {
+            kept.push(i);
+        }
+    }
+    kept
+}
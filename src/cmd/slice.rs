@@ -1,6 +1,5 @@
 use std::fs;
 
-
 use CliResult;
 use config::{Config, Delimiter};
 use index::Indexed;
@@ -16,6 +15,11 @@ record in the CSV data.
 If the end of the range isn't specified, then the slice continues to the last
 record in the CSV data.
 
+A negative --start, --end or --index counts from the end of the CSV data
+instead, e.g., '--start -10' slices the last 10 records. This needs the number
+of records, which is read from the index when there is one. Otherwise, all of
+the records are read into memory first.
+
 This operation can be made much faster by creating an index with 'xsv index'
 first. Namely, a slice on an index requires parsing just the rows that are
 sliced. Without an index, all rows up to the first row in the slice must be
@@ -44,10 +48,10 @@ Common options:
 #[derive(RustcDecodable)]
 struct Args {
     arg_input: Option<String>,
-    flag_start: Option<usize>,
-    flag_end: Option<usize>,
+    flag_start: Option<i64>,
+    flag_end: Option<i64>,
     flag_len: Option<usize>,
-    flag_index: Option<usize>,
+    flag_index: Option<i64>,
     flag_output: Option<String>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
@@ -67,9 +71,17 @@ impl Args {
         let mut wtr = self.wconfig().writer()?;
         self.rconfig().write_headers(&mut rdr, &mut wtr)?;
 
-        let (start, end) = self.range()?;
-        for r in rdr.byte_records().skip(start).take(end - start) {
-            wtr.write_byte_record(&r?)?;
+        if !self.is_negative() {
+            let (start, end) = self.range(None)?;
+            for r in rdr.byte_records().skip(start).take(end - start) {
+                wtr.write_byte_record(&r?)?;
+            }
+            return Ok(wtr.flush()?);
+        }
+        let records = rdr.byte_records().collect::<Result<Vec<_>, _>>()?;
+        let (start, end) = self.range(Some(records.len() as u64))?;
+        for r in records.iter().skip(start).take(end - start) {
+            wtr.write_byte_record(r)?;
         }
         Ok(wtr.flush()?)
     }
@@ -81,7 +93,8 @@ impl Args {
         let mut wtr = self.wconfig().writer()?;
         self.rconfig().write_headers(&mut *idx, &mut wtr)?;
 
-        let (start, end) = self.range()?;
+        let count = idx.count();
+        let (start, end) = self.range(Some(count))?;
         if end - start == 0 {
             return Ok(());
         }
@@ -93,9 +106,37 @@ impl Args {
         Ok(())
     }
 
-    fn range(&self) -> Result<(usize, usize), String> {
+    fn is_negative(&self) -> bool {
+        [self.flag_start, self.flag_end, self.flag_index]
+            .iter()
+            .any(|i| i.map_or(false, |i| i < 0))
+    }
+
+    /// Returns the range to slice, where negative indices are resolved
+    /// with `count`, the number of records.
+    fn range(&self, count: Option<u64>) -> Result<(usize, usize), String> {
+        let resolve = |i: Option<i64>| -> Result<Option<i64>, String> {
+            match (i, count) {
+                (Some(i), Some(count)) if i < 0 => Ok(Some(count as i64 + i)),
+                (Some(i), None) if i < 0 => Err(format!(
+                    "Cannot resolve the negative index {} without knowing \
+                     the number of records.", i)),
+                (i, _) => Ok(i),
+            }
+        };
+        let clamp =
+            |i: Option<i64>| i.map(|i| if i < 0 { 0 } else { i as usize });
+        let index = resolve(self.flag_index)?;
+        if index.map_or(false, |i| i < 0) {
+            // The record is before the first one, so there's nothing to
+            // slice.
+            return Ok((0, 0));
+        }
         util::range(
-            self.flag_start, self.flag_end, self.flag_len, self.flag_index)
+            clamp(resolve(self.flag_start)?),
+            clamp(resolve(self.flag_end)?),
+            self.flag_len,
+            clamp(index))
     }
 
     fn rconfig(&self) -> Config {
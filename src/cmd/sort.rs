@@ -1,9 +1,19 @@
 use std::cmp;
+use std::collections::BinaryHeap;
+use std::env;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use csv;
+use rustc_serialize::{Decodable, Decoder};
 
 use CliResult;
 use config::{Config, Delimiter};
-use select::SelectColumns;
-use util;
+use select::{SelectColumns, Selection};
+use util::{self, ByteSize};
 use std::str::from_utf8;
 
 use self::Number::{Float, Int};
@@ -11,16 +21,61 @@ use self::Number::{Float, Int};
 static USAGE: &'static str = "
 Sorts CSV data lexicographically.
 
-Note that this requires reading all of the CSV data into memory.
+Records are compared by their selected columns, in order. By default, fields
+are compared byte by byte. With --numeric, fields are compared as numbers.
+With --natural, runs of digits within fields are compared as numbers, so
+that 'item2' sorts before 'item10'.
+
+To compare some columns differently than others, give one or more --key
+options instead of --select. Each key is a column name or index, optionally
+followed by ':' and a type: 'n' for numeric or 'v' for natural, and/or 'r'
+to reverse the order of that key. For example, '--key name:v --key 3:nr'
+sorts naturally by the 'name' column and then numerically by the third
+column, from the largest number to the smallest. A column name that contains
+':' followed by a type, like 'a:n', must itself be followed by ':', e.g.,
+'--key a:n:' or '--key a:n:r'.
+
+With --numeric, fields that aren't numbers sort after all numbers (even
+when reversed), and compare equal to each other.
+
+Records whose selected columns compare equal always appear in the same order
+as in the input, with or without --max-mem.
+With --unique, only the first of the records that compare equal is written,
+as if the sorted output was piped through 'xsv dedup --sorted' with the same
+columns.
+
+Note that by default this requires reading all of the CSV data into memory.
+When --max-mem is given, sorted runs of at most that many bytes of CSV data
+are instead written to temporary files, which are then merged together. This
+sorts data that doesn't fit in memory, at the cost of writing all of it to
+disk once.
 
 Usage:
-    xsv sort [options] [<input>]
+    xsv sort [options] [--key <spec>]... [<input>]
 
 sort options:
     -s, --select <arg>     Select a subset of columns to sort.
                            See 'xsv select --help' for the format details.
     -N, --numeric          Compare according to string numerical value
+    --natural              Compare according to natural order, where runs
+                           of digits are compared as numbers
+    -k, --key <spec>       Sort by the given column, compared according to
+                           the given type. May be given more than once.
+                           This cannot be combined with
+                           the --select, --numeric or --natural options.
     -R, --reverse          Reverse order
+    --stable               Keep records that compare equal in their input
+                           order. Sorting is always stable, so this does
+                           nothing; it's accepted for symmetry with
+                           'sort --stable' in GNU coreutils.
+    -u, --unique           Only write the first record (in input order) of
+                           each set of records that compare equal.
+    --max-mem <bytes>      The approximate amount of CSV data to hold in
+                           memory at once, e.g., 512M or 2G. Data beyond
+                           this limit is sorted on disk.
+    --tmp-dir <dir>        The directory to write temporary files to when
+                           sorting on disk. Defaults to the system's
+                           temporary directory, e.g., $TMPDIR on Unix.
 
 Common options:
     -h, --help             Display this message
@@ -38,7 +93,12 @@ struct Args {
     arg_input: Option<String>,
     flag_select: SelectColumns,
     flag_numeric: bool,
+    flag_natural: bool,
+    flag_key: Vec<SortKey>,
     flag_reverse: bool,
+    flag_unique: bool,
+    flag_max_mem: Option<ByteSize>,
+    flag_tmp_dir: Option<String>,
     flag_output: Option<String>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
@@ -46,54 +106,354 @@ struct Args {
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
-    let numeric = args.flag_numeric;
+    if args.flag_numeric && args.flag_natural {
+        return fail!("--numeric and --natural cannot be used together.");
+    }
+    if !args.flag_key.is_empty()
+            && (args.flag_numeric || args.flag_natural
+                || !args.flag_select.is_all()) {
+        return fail!("--key cannot be used with --select, --numeric \
+                      or --natural.");
+    }
     let reverse = args.flag_reverse;
+    let unique = args.flag_unique;
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
-        .no_headers(args.flag_no_headers)
-        .select(args.flag_select);
+        .no_headers(args.flag_no_headers);
 
     let mut rdr = rconfig.reader()?;
     let mut wtr = Config::new(&args.flag_output).writer()?;
 
     let headers = rdr.byte_headers()?.clone();
-    let sel = rconfig.selection(&headers)?;
-
-    let mut all = rdr.byte_records().collect::<Result<Vec<_>, _>>()?;
-    match (numeric, reverse) {
-        (false, false) =>
-            all.sort_by(|r1, r2| {
-                let a = sel.select(r1);
-                let b = sel.select(r2);
-                iter_cmp(a, b)
-            }),
-        (true, false) =>
-            all.sort_by(|r1, r2| {
-                let a = sel.select(r1);
-                let b = sel.select(r2);
-                iter_cmp_num(a, b)
-            }),
-        (false, true) =>
-            all.sort_by(|r1, r2| {
-                let a = sel.select(r1);
-                let b = sel.select(r2);
-                iter_cmp(b, a)
-            }),
-        (true, true) =>
-            all.sort_by(|r1, r2| {
-                let a = sel.select(r1);
-                let b = sel.select(r2);
-                iter_cmp_num(b, a)
-            }),
+    let keys = args.keys(&headers)?;
+    let compare = |r1: &csv::ByteRecord, r2: &csv::ByteRecord| {
+        for &(ref sel, kind, key_reverse) in &keys {
+            let (a, b) = (sel.select(r1), sel.select(r2));
+            let rev = reverse != key_reverse;
+            let ord = match kind {
+                KeyKind::Lexical => iter_cmp(a, b),
+                KeyKind::Numeric => iter_cmp_num(a, b, rev),
+                KeyKind::Natural => iter_cmp_natural(a, b),
+            };
+            let ord = if rev && kind != KeyKind::Numeric {
+                ord.reverse()
+            } else {
+                ord
+            };
+            if ord != cmp::Ordering::Equal {
+                return ord;
+            }
+        }
+        cmp::Ordering::Equal
+    };
+    let sort = |all: &mut Vec<csv::ByteRecord>| {
+        all.sort_by(|r1, r2| compare(r1, r2));
+        if unique {
+            all.dedup_by(|r2, r1| compare(r1, r2) == cmp::Ordering::Equal);
+        }
+    };
+
+    let max_mem = match args.flag_max_mem {
+        None => None,
+        Some(ByteSize(0)) => return fail!("--max-mem must be greater than 0."),
+        Some(ByteSize(n)) => Some(n),
+    };
+    let mut all = vec![];
+    let mut size = 0;
+    let mut runs: Option<Runs> = None;
+    for r in rdr.byte_records() {
+        let r = r?;
+        size += record_size(&r);
+        all.push(r);
+        if max_mem.map_or(false, |max| size >= max) {
+            if runs.is_none() {
+                runs = Some(Runs::new(&args.flag_tmp_dir)?);
+            }
+            sort(&mut all);
+            runs.as_mut().unwrap().write(&all)?;
+            all.clear();
+            size = 0;
+        }
     }
+    sort(&mut all);
 
     rconfig.write_headers(&mut rdr, &mut wtr)?;
-    for r in all.into_iter() {
-        wtr.write_byte_record(&r)?;
+    match runs {
+        None => {
+            for r in all.into_iter() {
+                wtr.write_byte_record(&r)?;
+            }
+        }
+        Some(mut runs) => {
+            if !all.is_empty() {
+                runs.write(&all)?;
+            }
+            drop(all);
+            runs.merge(&mut wtr, &compare, unique)?;
+        }
     }
     Ok(wtr.flush()?)
 }
 
+impl Args {
+    /// Resolves the columns to sort by, along with how to compare them.
+    fn keys(
+        &self,
+        headers: &csv::ByteRecord,
+    ) -> CliResult<Vec<(Selection, KeyKind, bool)>> {
+        if self.flag_key.is_empty() {
+            let kind =
+                if self.flag_numeric {
+                    KeyKind::Numeric
+                } else if self.flag_natural {
+                    KeyKind::Natural
+                } else {
+                    KeyKind::Lexical
+                };
+            let sel = self.flag_select.selection(headers, !self.flag_no_headers)?;
+            return Ok(vec![(sel, kind, false)]);
+        }
+        let mut keys = vec![];
+        for key in &self.flag_key {
+            let sel = key.sel.selection(headers, !self.flag_no_headers)?;
+            keys.push((sel, key.kind, key.reverse));
+        }
+        Ok(keys)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum KeyKind {
+    Lexical,
+    Numeric,
+    Natural,
+}
+
+/// A sort key given with --key, e.g., `name:v` or `revenue:nr`.
+#[derive(Clone, Debug)]
+struct SortKey {
+    sel: SelectColumns,
+    kind: KeyKind,
+    reverse: bool,
+}
+
+impl SortKey {
+    fn parse(s: &str) -> Result<SortKey, String> {
+        // Only a suffix that could be a type is one, so that, e.g.,
+        // 'time:utc' is a column name.
+        let (col, typ) = match s.rfind(':') {
+            Some(i) if s[i+1..].chars().all(|c| "nvr".contains(c)) => {
+                (&s[..i], &s[i+1..])
+            }
+            _ => (s, ""),
+        };
+        let reverse = typ.contains('r');
+        let kind = match &*typ.replacen('r', "", 1) {
+            "" => KeyKind::Lexical,
+            "n" => KeyKind::Numeric,
+            "v" => KeyKind::Natural,
+            _ => return Err(format!(
+                "Unknown type '{}' in sort key '{}'. The type must be \
+                 one of 'n' or 'v', optionally with 'r'.", typ, s)),
+        };
+        if col.is_empty() {
+            return Err(format!("Sort key '{}' has no column.", s));
+        }
+        Ok(SortKey {
+            sel: SelectColumns::parse(col)?,
+            kind: kind,
+            reverse: reverse,
+        })
+    }
+}
+
+impl Decodable for SortKey {
+    fn decode<D: Decoder>(d: &mut D) -> Result<SortKey, D::Error> {
+        SortKey::parse(&*d.read_str()?).map_err(|e| d.error(&e))
+    }
+}
+
+/// Approximates the number of bytes of memory used by a record.
+fn record_size(r: &csv::ByteRecord) -> u64 {
+    (r.as_slice().len() + r.len() * 8) as u64
+}
+
+/// The largest number of runs that are merged at once. With more runs than
+/// this, they are merged in several passes, so that no more than this many
+/// files are open at the same time.
+const MERGE_FAN_IN: usize = 32;
+
+/// Runs is a collection of sorted runs of records, each in its own file in
+/// a temporary directory.
+///
+/// The directory and all of the runs in it are removed when this is dropped,
+/// whether or not the sort succeeded.
+struct Runs {
+    dir: PathBuf,
+    paths: Vec<PathBuf>,
+    /// The number of files created so far, which names the next one.
+    created: usize,
+}
+
+impl Runs {
+    fn new(tmp_dir: &Option<String>) -> io::Result<Runs> {
+        let parent = match *tmp_dir {
+            None => env::temp_dir(),
+            Some(ref dir) => PathBuf::from(dir),
+        };
+        for i in 0.. {
+            let dir = parent.join(format!("xsv-sort-{}-{}", process::id(), i));
+            match fs::create_dir(&dir) {
+                Ok(()) => {
+                    return Ok(Runs { dir: dir, paths: vec![], created: 0 });
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err),
+            }
+        }
+        unreachable!()
+    }
+
+    /// Writes a sorted run of records to a new file.
+    fn write(&mut self, records: &[csv::ByteRecord]) -> CliResult<()> {
+        let path = self.new_path();
+        let mut wtr = csv::Writer::from_path(&path)?;
+        for r in records {
+            wtr.write_byte_record(r)?;
+        }
+        wtr.flush()?;
+        self.paths.push(path);
+        Ok(())
+    }
+
+    fn new_path(&mut self) -> PathBuf {
+        self.created += 1;
+        self.dir.join(format!("{}.csv", self.created - 1))
+    }
+
+    /// Merges all of the runs into `wtr`.
+    ///
+    /// When records compare equal, the record from the earliest run is
+    /// written first, so that the sort is stable. When `unique` is set, only
+    /// that record is written.
+    fn merge<W, F>(
+        &mut self,
+        wtr: &mut csv::Writer<W>,
+        compare: &F,
+        unique: bool,
+    ) -> CliResult<()>
+            where W: io::Write,
+                  F: Fn(&csv::ByteRecord, &csv::ByteRecord) -> cmp::Ordering {
+        // Merging consecutive runs keeps the earlier records of each group
+        // in an earlier run, so that the final merge is still stable.
+        while self.paths.len() > MERGE_FAN_IN {
+            let paths = mem::replace(&mut self.paths, vec![]);
+            for group in paths.chunks(MERGE_FAN_IN) {
+                if group.len() == 1 {
+                    self.paths.push(group[0].clone());
+                    continue;
+                }
+                let path = self.new_path();
+                let mut run = csv::Writer::from_path(&path)?;
+                merge_runs(group, &mut run, compare, unique)?;
+                run.flush()?;
+                for p in group {
+                    fs::remove_file(p)?;
+                }
+                self.paths.push(path);
+            }
+        }
+        merge_runs(&self.paths, wtr, compare, unique)
+    }
+}
+
+/// Merges the sorted runs in `paths` into `wtr`, as described by
+/// `Runs::merge`.
+fn merge_runs<W, F>(
+    paths: &[PathBuf],
+    wtr: &mut csv::Writer<W>,
+    compare: &F,
+    unique: bool,
+) -> CliResult<()>
+        where W: io::Write,
+              F: Fn(&csv::ByteRecord, &csv::ByteRecord) -> cmp::Ordering {
+    let mut rdrs = vec![];
+    let mut heads = BinaryHeap::new();
+    for (i, path) in paths.iter().enumerate() {
+        let mut rdr = run_reader(path)?;
+        let mut record = csv::ByteRecord::new();
+        if rdr.read_byte_record(&mut record)? {
+            heads.push(Head { record: record, run: i, compare: compare });
+        }
+        rdrs.push(rdr);
+    }
+    let mut last: Option<csv::ByteRecord> = None;
+    while let Some(mut head) = heads.pop() {
+        let dupe = last.as_ref().map_or(false, |last| {
+            compare(last, &head.record) == cmp::Ordering::Equal
+        });
+        if !dupe {
+            wtr.write_byte_record(&head.record)?;
+            if unique {
+                last = Some(head.record.clone());
+            }
+        }
+        if rdrs[head.run].read_byte_record(&mut head.record)? {
+            heads.push(head);
+        }
+    }
+    Ok(())
+}
+
+/// Head is the next record of a run that is being merged.
+///
+/// Heads are ordered so that a `BinaryHeap`, which is a max-heap, gives the
+/// smallest record first, and the one from the earliest run among records
+/// that compare equal.
+struct Head<'a, F: 'a> {
+    record: csv::ByteRecord,
+    run: usize,
+    compare: &'a F,
+}
+
+impl<'a, F> Ord for Head<'a, F>
+        where F: Fn(&csv::ByteRecord, &csv::ByteRecord) -> cmp::Ordering {
+    fn cmp(&self, other: &Head<'a, F>) -> cmp::Ordering {
+        (self.compare)(&other.record, &self.record)
+            .then(other.run.cmp(&self.run))
+    }
+}
+
+impl<'a, F> PartialOrd for Head<'a, F>
+        where F: Fn(&csv::ByteRecord, &csv::ByteRecord) -> cmp::Ordering {
+    fn partial_cmp(&self, other: &Head<'a, F>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, F> PartialEq for Head<'a, F>
+        where F: Fn(&csv::ByteRecord, &csv::ByteRecord) -> cmp::Ordering {
+    fn eq(&self, other: &Head<'a, F>) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl<'a, F> Eq for Head<'a, F>
+        where F: Fn(&csv::ByteRecord, &csv::ByteRecord) -> cmp::Ordering {}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn run_reader(path: &Path) -> csv::Result<csv::Reader<fs::File>> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+}
+
 /// Order `a` and `b` lexicographically using `Ord`
 pub fn iter_cmp<A, L, R>(mut a: L, mut b: R) -> cmp::Ordering
         where A: Ord, L: Iterator<Item=A>, R: Iterator<Item=A> {
@@ -110,15 +470,39 @@ pub fn iter_cmp<A, L, R>(mut a: L, mut b: R) -> cmp::Ordering
     }
 }
 
-/// Try parsing `a` and `b` as numbers when ordering
-pub fn iter_cmp_num<'a, L, R>(mut a: L, mut b: R) -> cmp::Ordering
+/// Try parsing `a` and `b` as numbers when ordering, in reverse order if
+/// `reverse` is set. Fields that aren't numbers always sort after numbers.
+pub fn iter_cmp_num<'a, L, R>(mut a: L, mut b: R, reverse: bool)
+        -> cmp::Ordering
         where L: Iterator<Item=&'a [u8]>, R: Iterator<Item=&'a [u8]> {
+    let ord = |ord: cmp::Ordering| if reverse { ord.reverse() } else { ord };
     loop {
-        match (next_num(&mut a), next_num(&mut b)) {
+        match (a.next(), b.next()) {
+            (None, None) => return cmp::Ordering::Equal,
+            (None, _   ) => return ord(cmp::Ordering::Less),
+            (_   , None) => return ord(cmp::Ordering::Greater),
+            (Some(x), Some(y)) => match (parse_num(x), parse_num(y)) {
+                (None, None) => (),
+                (None, _   ) => return cmp::Ordering::Greater,
+                (_   , None) => return cmp::Ordering::Less,
+                (Some(x), Some(y)) => match compare_num(x, y) {
+                    cmp::Ordering::Equal => (),
+                    non_eq => return ord(non_eq),
+                },
+            },
+        }
+    }
+}
+
+/// Order `a` and `b` naturally, field by field
+pub fn iter_cmp_natural<'a, L, R>(mut a: L, mut b: R) -> cmp::Ordering
+        where L: Iterator<Item=&'a [u8]>, R: Iterator<Item=&'a [u8]> {
+    loop {
+        match (a.next(), b.next()) {
             (None, None) => return cmp::Ordering::Equal,
             (None, _   ) => return cmp::Ordering::Less,
             (_   , None) => return cmp::Ordering::Greater,
-            (Some(x), Some(y)) => match compare_num(x, y) {
+            (Some(x), Some(y)) => match natural_cmp(x, y) {
                 cmp::Ordering::Equal => (),
                 non_eq => return non_eq,
             },
@@ -126,6 +510,45 @@ pub fn iter_cmp_num<'a, L, R>(mut a: L, mut b: R) -> cmp::Ordering
     }
 }
 
+/// Order `a` and `b` so that runs of ASCII digits are compared by their
+/// numeric value, and everything else is compared byte by byte.
+///
+/// Strings that are only equal when ignoring leading zeros (e.g., `a01` and
+/// `a1`) are ordered byte by byte.
+pub fn natural_cmp(a: &[u8], b: &[u8]) -> cmp::Ordering {
+    fn is_digit(b: u8) -> bool { b'0' <= b && b <= b'9' }
+    fn digits(s: &[u8], mut i: usize) -> usize {
+        while i < s.len() && is_digit(s[i]) { i += 1; }
+        i
+    }
+    fn trim_zeros(s: &[u8]) -> &[u8] {
+        let start = s.iter().position(|&b| b != b'0').unwrap_or(s.len());
+        &s[start..]
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if is_digit(a[i]) && is_digit(b[j]) {
+            let (end_i, end_j) = (digits(a, i), digits(b, j));
+            let (n1, n2) = (trim_zeros(&a[i..end_i]), trim_zeros(&b[j..end_j]));
+            match n1.len().cmp(&n2.len()).then(n1.cmp(n2)) {
+                cmp::Ordering::Equal => (),
+                non_eq => return non_eq,
+            }
+            i = end_i;
+            j = end_j;
+        } else {
+            match a[i].cmp(&b[j]) {
+                cmp::Ordering::Equal => (),
+                non_eq => return non_eq,
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j)).then(a.cmp(b))
+}
+
 #[derive(Clone, Copy, PartialEq)]
 enum Number {
     Int(i64),
@@ -147,13 +570,14 @@ fn compare_float(f1: f64, f2: f64) -> cmp::Ordering {
 
 
 
-fn next_num<'a, X>(xs: &mut X) -> Option<Number>
-        where X: Iterator<Item=&'a [u8]> {
-    xs.next()
-        .and_then(|bytes| from_utf8(bytes).ok())
-        .and_then(|s| {
-            if let Ok(i) = s.parse::<i64>() { Some(Number::Int(i)) }
-            else if let Ok(f) = s.parse::<f64>() { Some(Number::Float(f)) }
-            else { None }
-        })
+/// Parses `bytes` as a number, or returns `None` if it isn't one (including
+/// a literal NaN, which can't be ordered).
+fn parse_num(bytes: &[u8]) -> Option<Number> {
+    from_utf8(bytes).ok().and_then(|s| {
+        if let Ok(i) = s.parse::<i64>() { Some(Number::Int(i)) }
+        else if let Ok(f) = s.parse::<f64>() {
+            if f.is_nan() { None } else { Some(Number::Float(f)) }
+        }
+        else { None }
+    })
 }
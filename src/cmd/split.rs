@@ -1,21 +1,36 @@
+use std::cell::{Ref, RefCell};
+use std::cmp;
 use std::fs;
-use std::io;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use chan;
 use csv;
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use threadpool::ThreadPool;
 
 use CliResult;
 use config::{Config, Delimiter};
-use index::Indexed;
-use util::{self, FilenameTemplate};
+use util::{self, ByteSize, FilenameTemplate, Progress};
 
 static USAGE: &'static str = "
 Splits the given CSV data into chunks.
 
 The files are written to the directory given with the name '{start}.csv',
 where {start} is the index of the first record of the chunk (starting at 0).
+The names of the files can be changed with the --filename flag.
+
+Chunks are either a fixed number of records (--size) or as many records as
+will fit into a fixed number of bytes (--max-size). A single record that is
+larger than --max-size on its own is written to its own chunk. When using
+a maximum size, the CSV data is always read sequentially, even if an index
+exists (in which case a note says so on stderr).
+
+Alternatively, --chunks splits the CSV data into a fixed number of chunks
+of nearly equal size. Without an index, this reads the CSV data twice (once
+to count the records), so it cannot be used with <stdin>.
 
 Usage:
     xsv split [options] <outdir> [<input>]
@@ -23,19 +38,57 @@ Usage:
 
 split options:
     -s, --size <arg>       The number of records to write into each chunk.
-                           [default: 500]
+                           (default: 500)
+    --max-size <arg>       The maximum number of bytes to write into each
+                           chunk, including the header row. Accepts suffixes
+                           such as '500k' or '10MiB' (K, M and G are powers
+                           of 1024; KB, MB and GB are powers of 1000).
+                           This cannot be used with --size.
+    --size-bytes <arg>     An alias for --max-size.
+    -c, --chunks <n>       The number of chunks to split the data into.
+                           Exactly <n> files are written, where earlier
+                           chunks may have one more record than later ones.
+                           <n> may not exceed the number of records.
+                           This cannot be used with --size or --max-size.
     -j, --jobs <arg>       The number of spliting jobs to run in parallel.
                            This only works when the given CSV data has
                            an index already created. Note that a file handle
-                           is opened for each job.
+                           is opened for each job, and that no more jobs
+                           than chunks are run.
                            When set to '0', the number of jobs is set to the
                            number of CPUs detected.
                            [default: 0]
     --filename <filename>  A filename template to use when constructing
                            the names of the output files.  The string '{}'
-                           will be replaced by a value based on the value
-                           of the field, but sanitized for shell safety.
-                           [default: {}.csv]
+                           will be replaced by the index of the first
+                           record of the chunk. The template may contain
+                           directories, e.g., '{}/part.csv', which are
+                           created inside <outdir> as needed.
+                           (default: {}.csv, or {}.tsv when the input
+                           is tab delimited)
+    --pad <n>              Left-pad the index of the first record of each
+                           chunk with zeros to <n> digits, so that the
+                           output files sort in record order. It is an
+                           error if an index needs more than <n> digits.
+                           This is checked before writing anything when
+                           the number of records is known in advance, i.e.,
+                           with an index (but without a maximum size) or
+                           with --chunks. Otherwise, the chunks written
+                           before the error are left in <outdir>.
+                           When set to '0', no padding is done.
+                           [default: 0]
+    --gzip                 Compress each chunk with gzip, adding '.gz' to
+                           the names of the output files. Chunks are also
+                           compressed whenever the --filename template ends
+                           with '.gz'.
+    --compress-level <n>   The gzip compression level, from 0 (no
+                           compression) to 9 (best compression).
+                           [default: 6]
+    --manifest <file>      Write a CSV file listing the path, the index of
+                           the first record and the number of records of
+                           every chunk, in order.
+    --progress             Show a progress bar on stderr while splitting,
+                           if stderr is a terminal.
 
 Common options:
     -h, --help             Display this message
@@ -50,77 +103,298 @@ Common options:
 struct Args {
     arg_input: Option<String>,
     arg_outdir: String,
-    flag_size: usize,
+    flag_size: Option<usize>,
+    flag_max_size: Option<ByteSize>,
+    flag_size_bytes: Option<ByteSize>,
+    flag_chunks: Option<usize>,
     flag_jobs: usize,
-    flag_filename: FilenameTemplate,
+    flag_filename: Option<FilenameTemplate>,
+    flag_pad: usize,
+    flag_gzip: bool,
+    flag_compress_level: u32,
+    flag_manifest: Option<String>,
+    flag_progress: bool,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
-    let args: Args = util::get_args(USAGE, argv)?;
-    if args.flag_size == 0 {
+    let mut args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_size_bytes.is_some() {
+        if args.flag_max_size.is_some() {
+            return fail!("--size-bytes is an alias for --max-size, so \
+                          only one of them may be used.");
+        }
+        args.flag_max_size = args.flag_size_bytes.take();
+    }
+    let nmodes = [
+        args.flag_size.is_some(),
+        args.flag_max_size.is_some(),
+        args.flag_chunks.is_some(),
+    ].iter().filter(|&&given| given).count();
+    if nmodes > 1 {
+        return fail!("Only one of --size, --max-size or --chunks \
+                      may be used.");
+    }
+    if args.flag_size == Some(0) {
         return fail!("--size must be greater than 0.");
     }
+    if args.flag_max_size.map_or(false, |n| n.as_u64() == 0) {
+        return fail!("--max-size must be greater than 0.");
+    }
+    if args.flag_chunks == Some(0) {
+        return fail!("--chunks must be greater than 0.");
+    }
+    if args.flag_compress_level > 9 {
+        return fail!("--compress-level must be between 0 and 9.");
+    }
     fs::create_dir_all(&args.arg_outdir)?;
 
-    match args.rconfig().indexed()? {
-        Some(idx) => args.parallel_split(idx),
-        None => args.sequential_split(),
+    let chunks = match (args.flag_max_size, args.rconfig().indexed()?) {
+        (Some(max_size), idx) => {
+            if idx.is_some() {
+                werr!("Note: --max-size reads the CSV data sequentially, \
+                       so the index is not used.");
+            }
+            args.sized_split(max_size.as_u64())?
+        }
+        (None, Some(idx)) => {
+            let count = idx.count() as usize;
+            let chunks = match args.flag_chunks {
+                Some(nchunks) => even_chunks(count, nchunks)?,
+                None => {
+                    let chunk_size = args.chunk_size();
+                    let nchunks = util::num_of_chunks(count, chunk_size);
+                    (0..nchunks).map(|i| {
+                        let start = i * chunk_size;
+                        (start, cmp::min(chunk_size, count - start))
+                    }).collect()
+                }
+            };
+            args.parallel_split(&chunks)?;
+            chunks
+        }
+        (None, None) => match args.flag_chunks {
+            Some(nchunks) => args.counted_split(nchunks)?,
+            None => args.sequential_split()?,
+        },
+    };
+    if let Some(ref manifest) = args.flag_manifest {
+        args.write_manifest(manifest, &chunks)?;
     }
+    Ok(())
+}
+
+/// Divide `nitems` into exactly `nchunks` contiguous `(start, len)` ranges
+/// whose lengths differ by at most one, with the longer ranges first.
+fn even_chunks(
+    nitems: usize,
+    nchunks: usize,
+) -> CliResult<Vec<(usize, usize)>> {
+    // Empty chunks would all start at the same record, and therefore get
+    // the same filename.
+    if nchunks > cmp::max(nitems, 1) {
+        return fail!(format!(
+            "Cannot split {} records into {} chunks.", nitems, nchunks));
+    }
+    let (base, extra) = (nitems / nchunks, nitems % nchunks);
+    let mut start = 0;
+    Ok((0..nchunks).map(|i| {
+        let len = if i < extra { base + 1 } else { base };
+        let chunk = (start, len);
+        start += len;
+        chunk
+    }).collect())
 }
 
 impl Args {
-    fn sequential_split(&self) -> CliResult<()> {
+    // Each of the splits returns the `(start, len)` of every chunk it wrote,
+    // in order.
+
+    fn sequential_split(&self) -> CliResult<Vec<(usize, usize)>> {
         let rconfig = self.rconfig();
-        let mut rdr = rconfig.reader()?;
+        let progress = self.progress(None);
+        let mut rdr = rconfig.from_reader(
+            progress.reader(rconfig.io_reader()?));
         let headers = rdr.byte_headers()?.clone();
 
         let mut wtr = self.new_writer(&headers, 0)?;
-        let mut i = 0;
+        let (mut i, mut start) = (0, 0);
+        let mut chunks = vec![];
         let mut row = csv::ByteRecord::new();
         while rdr.read_byte_record(&mut row)? {
-            if i > 0 && i % self.flag_size == 0 {
-                wtr.flush()?;
+            if i > 0 && i % self.chunk_size() == 0 {
+                finish_writer(wtr)?;
+                chunks.push((start, i - start));
                 wtr = self.new_writer(&headers, i)?;
+                start = i;
             }
             wtr.write_byte_record(&row)?;
             i += 1;
         }
-        wtr.flush()?;
-        Ok(())
+        finish_writer(wtr)?;
+        chunks.push((start, i - start));
+        progress.finish();
+        Ok(chunks)
     }
 
-    fn parallel_split(
-        &self,
-        idx: Indexed<fs::File, fs::File>,
-    ) -> CliResult<()> {
-        let nchunks = util::num_of_chunks(
-            idx.count() as usize, self.flag_size);
-        let pool = ThreadPool::new(self.njobs());
+    /// A sequential split where each chunk is limited to `max_size` bytes.
+    ///
+    /// Every record is serialized before it is written so that we know
+    /// whether it still fits into the current chunk.
+    fn sized_split(&self, max_size: u64) -> CliResult<Vec<(usize, usize)>> {
+        let rconfig = self.rconfig();
+        let progress = self.progress(None);
+        let mut rdr = rconfig.from_reader(
+            progress.reader(rconfig.io_reader()?));
+        let mut ser = self.serializer();
+        let mut header = vec![];
+        if !rconfig.no_headers {
+            header.extend_from_slice(&ser.serialize(rdr.byte_headers()?)?);
+        }
+
+        let mut wtr = self.new_sized_writer(&header, 0)?;
+        let mut chunk_size = header.len() as u64;
+        let mut chunk_len = 0;
+        let mut i = 0;
+        let mut chunks = vec![];
+        let mut row = csv::ByteRecord::new();
+        while rdr.read_byte_record(&mut row)? {
+            let buf = ser.serialize(&row)?;
+            let nbytes = buf.len() as u64;
+            // A record that doesn't fit into a chunk on its own still gets
+            // written, so never roll over on an empty chunk.
+            if chunk_len > 0 && chunk_size + nbytes > max_size {
+                finish_sized_writer(wtr)?;
+                chunks.push((i - chunk_len, chunk_len));
+                wtr = self.new_sized_writer(&header, i)?;
+                chunk_size = header.len() as u64;
+                chunk_len = 0;
+            }
+            wtr.write_all(&buf)?;
+            chunk_size += nbytes;
+            chunk_len += 1;
+            i += 1;
+        }
+        finish_sized_writer(wtr)?;
+        chunks.push((i - chunk_len, chunk_len));
+        progress.finish();
+        Ok(chunks)
+    }
+
+    /// A sequential split into exactly `nchunks` chunks, which first
+    /// counts the records.
+    fn counted_split(&self, nchunks: usize) -> CliResult<Vec<(usize, usize)>> {
+        let rconfig = self.rconfig();
+        if rconfig.is_std() {
+            return fail!("--chunks cannot be used with <stdin> unless \
+                          the CSV data is indexed.");
+        }
+        // The data is read twice.
+        let total = rconfig.input_len().map(|len| 2 * len);
+        let progress = Progress::new(self.flag_progress, total, "bytes");
+        let mut count = 0;
+        let mut rdr = rconfig.from_reader(
+            progress.reader(rconfig.io_reader()?));
+        let mut row = csv::ByteRecord::new();
+        while rdr.read_byte_record(&mut row)? {
+            count += 1;
+        }
+
+        let mut rdr = rconfig.from_reader(
+            progress.reader(rconfig.io_reader()?));
+        let headers = rdr.byte_headers()?.clone();
+        let chunks = even_chunks(count, nchunks)?;
+        if let Some(&(start, _)) = chunks.last() {
+            // Like the parallel split, fail on --pad before writing anything.
+            self.chunk_name(start)?;
+        }
+        for &(start, len) in &chunks {
+            let mut wtr = self.new_writer(&headers, start)?;
+            for _ in 0..len {
+                rdr.read_byte_record(&mut row)?;
+                wtr.write_byte_record(&row)?;
+            }
+            finish_writer(wtr)?;
+        }
+        progress.finish();
+        Ok(chunks)
+    }
+
+    /// Write each `(start, len)` chunk of an indexed CSV file in parallel.
+    fn parallel_split(&self, chunks: &[(usize, usize)]) -> CliResult<()> {
+        if let Some(&(start, _)) = chunks.last() {
+            // Make sure every chunk can be named before any jobs start, so
+            // that they can't fail on --pad.
+            self.chunk_name(start)?;
+        }
+        // There's no point in having more jobs than chunks, since each job
+        // opens its own file handle.
+        let njobs = cmp::max(1, cmp::min(self.njobs(), chunks.len()));
+        let pool = ThreadPool::new(njobs);
         let wg = chan::WaitGroup::new();
-        for i in 0..nchunks {
+        let total = chunks.iter().map(|&(_, len)| len as u64).sum();
+        let progress = self.progress(Some(total));
+        for &(start, len) in chunks {
             wg.add(1);
             let args = self.clone();
             let wg = wg.clone();
+            let progress = progress.clone();
             pool.execute(move || {
                 let conf = args.rconfig();
                 let mut idx = conf.indexed().unwrap().unwrap();
                 let headers = idx.byte_headers().unwrap().clone();
-                let mut wtr = args
-                    .new_writer(&headers, i * args.flag_size)
-                    .unwrap();
+                let mut wtr = args.new_writer(&headers, start).unwrap();
 
-                idx.seek((i * args.flag_size) as u64).unwrap();
-                for row in idx.byte_records().take(args.flag_size) {
+                // Empty chunks start past the last record.
+                if len > 0 {
+                    idx.seek(start as u64).unwrap();
+                }
+                for (i, row) in idx.byte_records().take(len).enumerate() {
                     let row = row.unwrap();
                     wtr.write_byte_record(&row).unwrap();
+                    if (i + 1) % PROGRESS_BATCH == 0 {
+                        progress.add(PROGRESS_BATCH as u64);
+                    }
                 }
-                wtr.flush().unwrap();
+                finish_writer(wtr).unwrap();
+                progress.add((len % PROGRESS_BATCH) as u64);
                 wg.done();
             });
         }
         wg.wait();
+        progress.finish();
+        Ok(())
+    }
+
+    /// Write a CSV file listing the path, first record and number of
+    /// records of every chunk.
+    ///
+    /// The manifest is written to a temporary file first and then renamed,
+    /// so that it's never left half written.
+    fn write_manifest(
+        &self,
+        manifest: &str,
+        chunks: &[(usize, usize)],
+    ) -> CliResult<()> {
+        let mut tmp = PathBuf::from(manifest).into_os_string();
+        tmp.push(".tmp");
+        {
+            // The manifest is formatted for its final path (e.g., with tabs
+            // for '.tsv'), not for the temporary one.
+            let mut wtr = Config::new(&Some(manifest.to_owned()))
+                .from_writer(fs::File::create(&tmp)?);
+            wtr.write_record(&["path", "start", "count"])?;
+            for &(start, len) in chunks {
+                let path = self.chunk_path(start)?;
+                let path = path.display().to_string();
+                wtr.write_record(&[
+                    path, start.to_string(), len.to_string(),
+                ])?;
+            }
+            wtr.flush()?;
+        }
+        fs::rename(&tmp, manifest)?;
         Ok(())
     }
 
@@ -128,28 +402,205 @@ impl Args {
         &self,
         headers: &csv::ByteRecord,
         start: usize,
-    ) -> CliResult<csv::Writer<Box<io::Write+'static>>> {
-        let dir = Path::new(&self.arg_outdir);
-        let path = dir.join(self.flag_filename.filename(&format!("{}", start)));
-        let spath = Some(path.display().to_string());
-        let mut wtr = Config::new(&spath).writer()?;
+    ) -> CliResult<csv::Writer<Chunk>> {
+        let path = self.chunk_path(start)?;
+        let mut wtr = self.wconfig(&path).from_writer(self.open_chunk(&path)?);
         if !self.rconfig().no_headers {
             wtr.write_record(headers)?;
         }
         Ok(wtr)
     }
 
+    /// Like `new_writer`, but returns a raw writer for records that have
+    /// already been serialized with a `Serializer`.
+    fn new_sized_writer(
+        &self,
+        header: &[u8],
+        start: usize,
+    ) -> CliResult<io::BufWriter<Chunk>> {
+        let path = self.chunk_path(start)?;
+        let mut wtr = io::BufWriter::new(self.open_chunk(&path)?);
+        wtr.write_all(header)?;
+        Ok(wtr)
+    }
+
+    /// Create the output file for a chunk, compressing it if its name ends
+    /// with `.gz`.
+    fn open_chunk(&self, path: &Path) -> CliResult<Chunk> {
+        let file: Box<io::Write+'static> = Box::new(fs::File::create(path)?);
+        if is_gzip(path) {
+            let level = Compression::new(self.flag_compress_level);
+            Ok(Chunk::Gzip(GzEncoder::new(file, level)))
+        } else {
+            Ok(Chunk::Plain(file))
+        }
+    }
+
+    /// The configuration for writing CSV data to the chunk at `path`.
+    ///
+    /// This ignores a `.gz` extension, so that e.g. `0.tsv.gz` is still
+    /// tab delimited.
+    fn wconfig(&self, path: &Path) -> Config {
+        let path = if is_gzip(path) {
+            path.with_extension("")
+        } else {
+            path.to_path_buf()
+        };
+        Config::new(&Some(path.display().to_string()))
+    }
+
+    /// A `Serializer` that formats records exactly as a chunk writer would.
+    fn serializer(&self) -> Serializer {
+        // Only the file extension matters here, which is the same for
+        // every chunk.
+        let path = PathBuf::from(self.filename_template().filename("0"));
+        let buf = Rc::new(RefCell::new(vec![]));
+        Serializer {
+            wtr: self.wconfig(&path).from_writer(SharedBuf(buf.clone())),
+            buf: buf,
+        }
+    }
+
+    /// The path of the chunk starting at record `start`. Both the
+    /// sequential and parallel splits name their chunks with this.
+    fn chunk_path(&self, start: usize) -> CliResult<PathBuf> {
+        let name = self.chunk_name(start)?;
+        let template = self.filename_template();
+        let path = template.create_path(&self.arg_outdir, &name)?;
+        if self.flag_gzip && !is_gzip(&path) {
+            let mut path = path.into_os_string();
+            path.push(".gz");
+            return Ok(PathBuf::from(path));
+        }
+        Ok(path)
+    }
+
+    /// The value substituted into the filename template for the chunk
+    /// starting at record `start`, padded according to --pad.
+    fn chunk_name(&self, start: usize) -> CliResult<String> {
+        let name = format!("{:0width$}", start, width = self.flag_pad);
+        if self.flag_pad > 0 && name.len() > self.flag_pad {
+            return fail!(format!(
+                "The chunk starting at record {} needs more than {} digits. \
+                 Please increase --pad.", start, self.flag_pad));
+        }
+        Ok(name)
+    }
+
+    /// The template given by --filename, or a default whose extension
+    /// matches the delimiter of the input.
+    fn filename_template(&self) -> FilenameTemplate {
+        match self.flag_filename {
+            Some(ref template) => template.clone(),
+            None if self.rconfig().get_delimiter() == b'\t' => {
+                "{}.tsv".parse().unwrap()
+            }
+            None => "{}.csv".parse().unwrap(),
+        }
+    }
+
     fn rconfig(&self) -> Config {
         Config::new(&self.arg_input)
             .delimiter(self.flag_delimiter)
             .no_headers(self.flag_no_headers)
     }
 
+    fn progress(&self, count: Option<u64>) -> Progress {
+        Progress::for_input(self.flag_progress, &self.rconfig(), count)
+    }
+
+    fn chunk_size(&self) -> usize {
+        self.flag_size.unwrap_or(500)
+    }
+
     fn njobs(&self) -> usize {
-        if self.flag_jobs == 0 {
-            util::num_cpus()
-        } else {
-            self.flag_jobs
+        util::njobs(self.flag_jobs)
+    }
+}
+
+/// The number of records that each job writes between updates of the
+/// progress bar.
+const PROGRESS_BATCH: usize = 1000;
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == "gz")
+}
+
+/// The output file of a single chunk, which may be gzip compressed.
+enum Chunk {
+    Plain(Box<io::Write+'static>),
+    Gzip(GzEncoder<Box<io::Write+'static>>),
+}
+
+impl Chunk {
+    /// Flush the chunk, writing the end of the gzip stream if necessary.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Chunk::Plain(mut wtr) => wtr.flush(),
+            Chunk::Gzip(wtr) => wtr.finish().and_then(|mut wtr| wtr.flush()),
+        }
+    }
+}
+
+impl io::Write for Chunk {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Chunk::Plain(ref mut wtr) => wtr.write(buf),
+            Chunk::Gzip(ref mut wtr) => wtr.write(buf),
         }
     }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Chunk::Plain(ref mut wtr) => wtr.flush(),
+            Chunk::Gzip(ref mut wtr) => wtr.flush(),
+        }
+    }
+}
+
+/// Serializer writes one record at a time into a buffer, so that its size is
+/// known before it's written to a chunk. The same CSV writer and buffer are
+/// used for every record.
+struct Serializer {
+    wtr: csv::Writer<SharedBuf>,
+    buf: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Serializer {
+    /// Returns the bytes of `record`, which stay valid until the next call.
+    fn serialize(&mut self, record: &csv::ByteRecord)
+                -> CliResult<Ref<'_, Vec<u8>>> {
+        self.buf.borrow_mut().clear();
+        self.wtr.write_byte_record(record)?;
+        self.wtr.flush()?;
+        Ok(self.buf.borrow())
+    }
+}
+
+/// A buffer that a CSV writer writes to, while the `Serializer` that owns the
+/// writer can still read it.
+struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn finish_writer(wtr: csv::Writer<Chunk>) -> io::Result<()> {
+    match wtr.into_inner() {
+        Ok(chunk) => chunk.finish(),
+        Err(err) => Err(io::Error::new(err.error().kind(), err.to_string())),
+    }
+}
+
+fn finish_sized_writer(wtr: io::BufWriter<Chunk>) -> io::Result<()> {
+    match wtr.into_inner() {
+        Ok(chunk) => chunk.finish(),
+        Err(err) => Err(io::Error::new(err.error().kind(), err.to_string())),
+    }
 }
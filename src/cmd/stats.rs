@@ -1,39 +1,69 @@
 use std::borrow::ToOwned;
+use std::cmp::{self, Reverse};
+use std::collections::{BinaryHeap, HashMap};
 use std::default::Default;
+use std::env;
 use std::fmt;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::iter::{FromIterator, repeat};
+use std::mem;
+use std::path::PathBuf;
+use std::process;
 use std::str::{self, FromStr};
+use std::sync::Arc;
 
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use chan;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use chrono::format::{Item, StrftimeItems};
 use csv;
-use stats::{Commute, OnlineStats, MinMax, Unsorted, merge_all};
+use rand;
+use rustc_serialize::{Decodable, Decoder};
+use rustc_serialize::json;
+use stats::{Commute, OnlineStats, MinMax, merge_all};
 use threadpool::ThreadPool;
 
 use CliResult;
 use config::{Config, Delimiter};
 use index::Indexed;
 use select::{SelectColumns, Selection};
-use util;
+use util::{self, ByteSize, Progress};
 
-use self::FieldType::{TUnknown, TNull, TUnicode, TFloat, TInteger};
+use self::FieldType::{
+    TUnknown, TNull, TUnicode, TFloat, TInteger, TDate, TDateTime,
+};
 
 static USAGE: &'static str = "
 Computes basic statistics on CSV data.
 
-Basic statistics includes mean, median, mode, standard deviation, sum, max and
-min values. Note that some statistics are expensive to compute, so they must
-be enabled explicitly. By default, the following statistics are reported for
-*every* column in the CSV data: mean, max, min and standard deviation. The
-default set of statistics corresponds to statistics that can be computed
-efficiently on a stream of data (i.e., constant memory).
+Basic statistics includes mean, median, quartiles, mode, standard deviation,
+sum, max and min values. Note that some statistics are expensive to compute,
+so they must be enabled explicitly. By default, the following statistics are
+reported for *every* column in the CSV data: mean, max, min, standard
+deviation, variance, the minimum and maximum length (in bytes) of its fields
+and the number of NULLs. The default set of statistics corresponds to
+statistics that can be computed efficiently on a stream of data (i.e.,
+constant memory).
+
+Empty fields are NULL, as are fields given by --null-value (and fields that
+only contain whitespace, with --whitespace). NULLs do not affect the type of
+a column, so a column of integers with some 'N/A' fields is still an
+'Integer' column when given '--null-value N/A'. The sum of an 'Integer'
+column is exact, or 'OVERFLOW' in the unlikely case that it doesn't fit in
+128 bits.
+
+Fields are also checked for whether they are dates when --infer-dates is
+given. A column whose fields are all dates has the type 'Date', or 'DateTime'
+if any of them include a time, and its min and max are the earliest and
+latest dates. Like numbers, a column with a mix of dates and other values has
+the type 'Unicode'.
 
 Computing statistics on a large file can be made much faster if you create
 an index for it first with 'xsv index'.
 
 Usage:
-    xsv stats [options] [<input>]
+    xsv stats [options] [--null-value <arg>]... [<input>]
 
 stats options:
     -s, --select <arg>     Select a subset of columns to compute stats for.
@@ -41,14 +71,62 @@ stats options:
                            This is provided here because piping 'xsv select'
                            into 'xsv stats' will disable the use of indexing.
     --everything           Show all statistics available.
-    --mode                 Show the mode.
+    --mode                 Show the mode, i.e., the most frequent value.
+                           Ties are broken by picking the smallest value.
+                           This requires storing every distinct value of
+                           each column in memory.
+    --cardinality          Show the cardinality, i.e., the exact number of
+                           distinct values.
+                           This requires storing every distinct value of
+                           each column in memory.
+    --median               Show the median.
                            This requires storing all CSV data in memory.
-    --cardinality          Show the cardinality.
+    --quartiles            Show the first and third quartiles (q1 and q3).
                            This requires storing all CSV data in memory.
-    --median               Show the median.
+    --percentiles <list>   Show the given percentiles, as a comma separated
+                           list of numbers between 0 and 100 (e.g., 5,95,99.9).
+                           Each percentile gets its own column, named 'p'
+                           followed by the percentile (e.g., p95). Percentiles
+                           are computed by linear interpolation between the
+                           two closest ranks.
+                           This requires storing all CSV data in memory.
+    --histogram <nbins>    Show a histogram of each numeric column, as the
+                           counts of its numbers in each of <nbins> bins of
+                           equal width between its min and max, separated
+                           by '|' (e.g., 1|4|10|3|0).
                            This requires storing all CSV data in memory.
+    --memory-limit <size>  The approximate amount of memory to use for the
+                           values that the mode, cardinality, median,
+                           quartiles, percentiles and histogram require,
+                           e.g., 512M or 2G. Values beyond this limit are
+                           sorted and written to temporary files (in the
+                           directory given by TMPDIR), which are merged to
+                           compute these statistics. The limit is shared by
+                           the selected columns, but applies to each job and
+                           to each group with --group-by.
+    --infer-dates          Infer Date and DateTime types.
+    --dates-whitelist <arg>
+                           Only infer dates in the given columns. This
+                           implies --infer-dates. Checking for dates is slow,
+                           so this helps with wide CSV data.
+                           See 'xsv select --help' for the format details.
+    --date-formats <arg>   A list of strftime formats, separated by ';',
+                           that dates are recognized by. By default, ISO 8601
+                           dates (e.g., 2017-01-31) and date times
+                           (e.g., 2017-01-31T08:30:00+01:00) are recognized,
+                           as are 'YYYY-MM-DD HH:MM:SS' date times.
+    --sample               Compute the sample standard deviation and variance
+                           (i.e., dividing by n-1) instead of the population
+                           standard deviation and variance (dividing by n).
+                           These are empty for columns with fewer than two
+                           numbers.
     --nulls                Include NULLs in the population size for computing
                            mean and standard deviation.
+    --null-value <arg>     Treat fields with this value as NULL, in addition
+                           to empty fields. May be given more than once.
+    --ignore-case          Match the values given by --null-value
+                           case insensitively.
+    --whitespace           Treat fields that only contain whitespace as NULL.
     -j, --jobs <arg>       The number of jobs to run in parallel.
                            This works better when the given CSV data has
                            an index already created. Note that a file handle
@@ -56,6 +134,19 @@ stats options:
                            When set to '0', the number of jobs is set to the
                            number of CPUs detected.
                            [default: 0]
+    --progress             Show a progress bar on stderr while reading the
+                           CSV data, if stderr is a terminal.
+    -g, --group-by <arg>   Compute statistics separately for each distinct
+                           value of the given columns. Each row of output
+                           starts with the values of these columns.
+                           See 'xsv select --help' for the format details.
+                           This requires memory proportional to the number
+                           of groups, and does not use an index.
+    --limit-groups <n>     Fail if there are more than this many groups.
+    --json                 Write the statistics of each column as a JSON
+                           object on its own line, instead of as CSV.
+                           Statistics that are numbers are written as JSON
+                           numbers, and missing statistics are null.
 
 Common options:
     -h, --help             Display this message
@@ -75,8 +166,23 @@ struct Args {
     flag_mode: bool,
     flag_cardinality: bool,
     flag_median: bool,
+    flag_quartiles: bool,
+    flag_percentiles: Option<Percentiles>,
+    flag_histogram: Option<usize>,
+    flag_memory_limit: Option<ByteSize>,
+    flag_infer_dates: bool,
+    flag_dates_whitelist: Option<SelectColumns>,
+    flag_date_formats: Option<DateFormats>,
+    flag_sample: bool,
     flag_nulls: bool,
+    flag_null_value: Vec<String>,
+    flag_ignore_case: bool,
+    flag_whitespace: bool,
     flag_jobs: usize,
+    flag_progress: bool,
+    flag_json: bool,
+    flag_group_by: Option<SelectColumns>,
+    flag_limit_groups: Option<usize>,
     flag_output: Option<String>,
     flag_no_headers: bool,
     flag_delimiter: Option<Delimiter>,
@@ -84,41 +190,187 @@ struct Args {
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_histogram == Some(0) {
+        return fail!("The number of histogram bins must be at least 1.");
+    }
+    if let Some(ByteSize(0)) = args.flag_memory_limit {
+        return fail!("--memory-limit must be greater than 0.");
+    }
 
-    let mut wtr = Config::new(&args.flag_output).writer()?;
-    let (headers, stats) = match args.rconfig().indexed()? {
-        None => args.sequential_stats(),
-        Some(idx) => {
-            if args.flag_jobs == 1 {
-                args.sequential_stats()
-            } else {
-                args.parallel_stats(idx)
+    let (key_names, headers, groups) = if args.flag_group_by.is_some() {
+        args.grouped_stats()?
+    } else {
+        let (headers, stats) = match args.rconfig().indexed()? {
+            None => args.sequential_stats(),
+            Some(idx) => {
+                if args.flag_jobs == 1 {
+                    args.sequential_stats()
+                } else {
+                    args.parallel_stats(idx)
+                }
             }
-        }
-    }?;
-    let stats = args.stats_to_records(stats);
+        }?;
+        (vec![], headers, vec![(vec![], stats)])
+    };
 
-    wtr.write_record(&args.stat_headers())?;
-    let fields = headers.iter().zip(stats.into_iter());
-    for (i, (header, stat)) in fields.enumerate() {
-        let header =
-            if args.flag_no_headers {
-                i.to_string().into_bytes()
-            } else {
-                header.to_vec()
-            };
-        let stat = stat.iter().map(|f| f.as_bytes());
-        wtr.write_record(vec![&*header].into_iter().chain(stat))?;
+    // Convert the stats of every group at once, so that they're all
+    // converted in parallel.
+    let (mut keys, mut all) = (vec![], vec![]);
+    for (key, stats) in groups {
+        keys.push(key);
+        all.extend(stats);
+    }
+    let records = args.stats_to_records(all)?;
+    let groups: Vec<_> = keys.into_iter()
+        .zip(records.chunks(cmp::max(1, headers.len())))
+        .collect();
+
+    if args.flag_json {
+        return args.write_json(&key_names, &headers, &groups);
+    }
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+    let stat_headers = args.stat_headers();
+    wtr.write_record(key_names.iter().map(|k| &**k)
+                     .chain(stat_headers.iter().map(|h| h.as_bytes())))?;
+    for &(ref key, stats) in &groups {
+        let fields = headers.iter().zip(stats.iter());
+        for (i, (header, stat)) in fields.enumerate() {
+            let header = args.field_name(i, header);
+            let row = key.iter().map(|k| &**k)
+                .chain(Some(&*header))
+                .chain(stat.iter().map(|f| f.as_bytes()));
+            wtr.write_record(row)?;
+        }
     }
     wtr.flush()?;
     Ok(())
 }
 
+type ByteString = Vec<u8>;
+
 impl Args {
+    fn field_name(&self, i: usize, header: &[u8]) -> Vec<u8> {
+        if self.flag_no_headers {
+            i.to_string().into_bytes()
+        } else {
+            header.to_vec()
+        }
+    }
+
+    /// Writes one JSON object per line for each column (of each group).
+    fn write_json(
+        &self,
+        key_names: &[ByteString],
+        headers: &csv::ByteRecord,
+        groups: &[(Vec<ByteString>, &[csv::StringRecord])],
+    ) -> CliResult<()> {
+        let mut wtr = Config::new(&self.flag_output).io_writer()?;
+        for &(ref key, stats) in groups {
+            self.write_json_group(&mut wtr, key_names, key, headers, stats)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    fn write_json_group<W: io::Write>(
+        &self,
+        wtr: &mut W,
+        key_names: &[ByteString],
+        key: &[ByteString],
+        headers: &csv::ByteRecord,
+        stats: &[csv::StringRecord],
+    ) -> CliResult<()> {
+        let names = self.stat_headers();
+        let fields = headers.iter().zip(stats.iter());
+        for (i, (header, stat)) in fields.enumerate() {
+            let header = self.field_name(i, header);
+            let mut pairs: Vec<String> = key_names.iter().zip(key.iter())
+                .map(|(name, value)| {
+                    format!("{}:{}", json_string(name), json_string(value))
+                })
+                .collect();
+            pairs.push(format!("\"field\":{}", json_string(&header)));
+            let typ = stat.get(0).unwrap_or("");
+            for (name, value) in names.iter().skip(1).zip(stat.iter()) {
+                let is_string = match name {
+                    "type" | "mode" | "histogram" => true,
+                    "min" | "max" => typ != "Integer" && typ != "Float",
+                    _ => false,
+                };
+                let value =
+                    if value.is_empty() {
+                        "null".to_owned()
+                    } else if is_string {
+                        json_string(value.as_bytes())
+                    } else {
+                        match value.parse::<f64>() {
+                            Ok(n) if n.is_finite() => value.to_owned(),
+                            _ => "null".to_owned(),
+                        }
+                    };
+                let name = json_string(name.as_bytes());
+                pairs.push(format!("{}:{}", name, value));
+            }
+            writeln!(wtr, "{{{}}}", pairs.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Computes stats for each distinct value of the --group-by columns,
+    /// in the order that the groups first appear.
+    fn grouped_stats(&self) -> CliResult<(
+        Vec<ByteString>,
+        csv::ByteRecord,
+        Vec<(Vec<ByteString>, Vec<Stats>)>,
+    )> {
+        let rconfig = self.rconfig();
+        let progress = self.progress(None);
+        let mut rdr = rconfig.from_reader(
+            progress.reader(rconfig.io_reader()?));
+        let (headers, sel, template) = self.sel_headers(&mut rdr)?;
+        let all_headers = rdr.byte_headers()?.clone();
+        let group_sel = self.flag_group_by.as_ref().unwrap()
+            .selection(&all_headers, !self.flag_no_headers)?;
+        let key_names = group_sel.iter().map(|&i| {
+            self.field_name(i, &all_headers[i])
+        }).collect();
+
+        let nulls = self.null_values();
+        let mut index: HashMap<Vec<ByteString>, usize> = HashMap::new();
+        let mut groups: Vec<(Vec<ByteString>, Vec<Stats>)> = vec![];
+        let mut record = csv::ByteRecord::new();
+        while rdr.read_byte_record(&mut record)? {
+            let key: Vec<ByteString> =
+                group_sel.select(&record).map(|f| f.to_vec()).collect();
+            let i = match index.get(&key) {
+                Some(&i) => i,
+                None => {
+                    if let Some(limit) = self.flag_limit_groups {
+                        if groups.len() >= limit {
+                            return fail!(format!(
+                                "The number of groups exceeds the limit \
+                                 of {} set by --limit-groups.", limit));
+                        }
+                    }
+                    index.insert(key.clone(), groups.len());
+                    groups.push((key, template.clone()));
+                    groups.len() - 1
+                }
+            };
+            add_record(&nulls, &sel, &mut groups[i].1, &record)?;
+        }
+        progress.finish();
+        Ok((key_names, headers, groups))
+    }
+
     fn sequential_stats(&self) -> CliResult<(csv::ByteRecord, Vec<Stats>)> {
-        let mut rdr = self.rconfig().reader()?;
-        let (headers, sel) = self.sel_headers(&mut rdr)?;
-        let stats = self.compute(&sel, rdr.byte_records())?;
+        let rconfig = self.rconfig();
+        let progress = self.progress(None);
+        let mut rdr = rconfig.from_reader(
+            progress.reader(rconfig.io_reader()?));
+        let (headers, sel, stats) = self.sel_headers(&mut rdr)?;
+        let stats = self.compute(&sel, stats, rdr.byte_records(), None)?;
+        progress.finish();
         Ok((headers, stats))
     }
 
@@ -133,27 +385,39 @@ impl Args {
         }
 
         let mut rdr = self.rconfig().reader()?;
-        let (headers, sel) = self.sel_headers(&mut rdr)?;
+        let (headers, sel, stats) = self.sel_headers(&mut rdr)?;
 
         let chunk_size = util::chunk_size(idx.count() as usize, self.njobs());
         let nchunks = util::num_of_chunks(idx.count() as usize, chunk_size);
 
         let pool = ThreadPool::new(self.njobs());
+        let progress = self.progress(Some(idx.count()));
         let (send, recv) = chan::sync(0);
         for i in 0..nchunks {
             let (send, args, sel) = (send.clone(), self.clone(), sel.clone());
+            let (stats, progress) = (stats.clone(), progress.clone());
             pool.execute(move || {
                 let mut idx = args.rconfig().indexed().unwrap().unwrap();
                 idx.seek((i * chunk_size) as u64).unwrap();
                 let it = idx.byte_records().take(chunk_size);
-                send.send(args.compute(&sel, it).unwrap());
+                let stats = args.compute(&sel, stats, it, Some(&progress));
+                send.send((i, stats.unwrap()));
             });
         }
         drop(send);
-        Ok((headers, merge_all(recv.iter()).unwrap_or_else(Vec::new)))
+        // Merge the chunks in order, so that the (floating point) results
+        // don't depend on which chunk happened to finish first.
+        let mut chunks: Vec<_> = recv.iter().collect();
+        progress.finish();
+        chunks.sort_by_key(|&(i, _)| i);
+        let chunks = chunks.into_iter().map(|(_, stats)| stats);
+        Ok((headers, merge_all(chunks).unwrap_or_else(Vec::new)))
     }
 
-    fn stats_to_records(&self, stats: Vec<Stats>) -> Vec<csv::StringRecord> {
+    fn stats_to_records(
+        &self,
+        stats: Vec<Stats>,
+    ) -> CliResult<Vec<csv::StringRecord>> {
         let mut records: Vec<_> = repeat(csv::StringRecord::new())
             .take(stats.len())
             .collect();
@@ -162,33 +426,69 @@ impl Args {
         for mut stat in stats.into_iter() {
             let (send, recv) = chan::sync(0);
             results.push(recv);
-            pool.execute(move || { send.send(stat.to_record()); });
+            pool.execute(move || {
+                let record = stat.to_record();
+                // Remove any spills before the record is sent, since the
+                // process may exit as soon as every record is written.
+                drop(stat);
+                send.send(record);
+            });
         }
         for (i, recv) in results.into_iter().enumerate() {
-            records[i] = recv.recv().unwrap();
+            records[i] = recv.recv().unwrap()?;
         }
-        records
+        Ok(records)
     }
 
-    fn compute<I>(&self, sel: &Selection, it: I) -> CliResult<Vec<Stats>>
+    /// Adds the records of `it` to `stats`. If `progress` is given, then
+    /// the records are counted on it; otherwise, the bytes read are counted
+    /// by the reader.
+    fn compute<I>(
+        &self,
+        sel: &Selection,
+        mut stats: Vec<Stats>,
+        it: I,
+        progress: Option<&Progress>,
+    ) -> CliResult<Vec<Stats>>
             where I: Iterator<Item=csv::Result<csv::ByteRecord>> {
-        let mut stats = self.new_stats(sel.len());
+        const BATCH: u64 = 1000;
+        let nulls = self.null_values();
+        let mut count = 0;
         for row in it {
-            let row = row?;
-            for (i, field) in sel.select(&row).enumerate() {
-                stats[i].add(field);
+            add_record(&nulls, sel, &mut stats, &row?)?;
+            count += 1;
+            if count == BATCH {
+                if let Some(progress) = progress {
+                    progress.add(count);
+                }
+                count = 0;
             }
         }
+        if let Some(progress) = progress {
+            progress.add(count);
+        }
         Ok(stats)
     }
 
+    fn progress(&self, count: Option<u64>) -> Progress {
+        Progress::for_input(self.flag_progress, &self.rconfig(), count)
+    }
+
+    fn null_values(&self) -> util::NullValues {
+        let (values, ignore_case) =
+            (&self.flag_null_value, self.flag_ignore_case);
+        util::NullValues::new(values, ignore_case)
+            .whitespace(self.flag_whitespace)
+    }
+
     fn sel_headers<R: io::Read>(
         &self,
         rdr: &mut csv::Reader<R>,
-    ) -> CliResult<(csv::ByteRecord, Selection)> {
+    ) -> CliResult<(csv::ByteRecord, Selection, Vec<Stats>)> {
         let headers = rdr.byte_headers()?.clone();
         let sel = self.rconfig().selection(&headers)?;
-        Ok((csv::ByteRecord::from_iter(sel.select(&headers)), sel))
+        let stats = self.new_stats(&headers, &sel)?;
+        Ok((csv::ByteRecord::from_iter(sel.select(&headers)), sel, stats))
     }
 
     fn rconfig(&self) -> Config {
@@ -199,43 +499,88 @@ impl Args {
     }
 
     fn njobs(&self) -> usize {
-        if self.flag_jobs == 0 { util::num_cpus() } else { self.flag_jobs }
+        util::njobs(self.flag_jobs)
     }
 
-    fn new_stats(&self, record_len: usize) -> Vec<Stats> {
-        repeat(Stats::new(WhichStats {
+    fn new_stats(
+        &self,
+        headers: &csv::ByteRecord,
+        sel: &Selection,
+    ) -> CliResult<Vec<Stats>> {
+        let formats = self.flag_date_formats.clone().unwrap_or_default();
+        let whitelist = match self.flag_dates_whitelist {
+            None => None,
+            Some(ref cols) => {
+                let wsel = cols.selection(headers, !self.flag_no_headers)?;
+                Some(wsel.to_vec())
+            }
+        };
+        let infer_dates = |i: usize| {
+            match whitelist {
+                None => self.flag_infer_dates,
+                Some(ref cols) => cols.contains(&i),
+            }
+        };
+        // The memory limit is shared equally by every column.
+        let memory_limit = self.flag_memory_limit.map(|size| {
+            let ncols = cmp::max(1, sel.len()) as u64;
+            (size.as_u64() / ncols) as usize
+        });
+        Ok(sel.iter().map(|&i| Stats::new(WhichStats {
             include_nulls: self.flag_nulls,
+            sample: self.flag_sample,
             sum: true,
             range: true,
             dist: true,
             cardinality: self.flag_cardinality || self.flag_everything,
             median: self.flag_median || self.flag_everything,
+            quartiles: self.flag_quartiles || self.flag_everything,
+            percentiles: self.percentiles().to_vec(),
+            histogram: self.flag_histogram,
+            memory_limit: memory_limit,
             mode: self.flag_mode || self.flag_everything,
-        })).take(record_len).collect()
+            dates: if infer_dates(i) { Some(formats.clone()) } else { None },
+        })).collect())
+    }
+
+    fn percentiles(&self) -> &[f64] {
+        self.flag_percentiles.as_ref().map_or(&[], |p| &*p.0)
     }
 
     fn stat_headers(&self) -> csv::StringRecord {
         let mut fields = vec![
             "field", "type", "sum", "min", "max", "min_length", "max_length",
-            "mean", "stddev",
+            "mean", "stddev", "variance", "nullcount",
         ];
         let all = self.flag_everything;
         if self.flag_median || all { fields.push("median"); }
+        if self.flag_quartiles || all { fields.push("q1"); fields.push("q3"); }
+        let percentiles: Vec<String> = self.percentiles().iter()
+            .map(|p| format!("p{}", p))
+            .collect();
+        fields.extend(percentiles.iter().map(|p| &**p));
         if self.flag_mode || all { fields.push("mode"); }
         if self.flag_cardinality || all { fields.push("cardinality"); }
+        if self.flag_histogram.is_some() { fields.push("histogram"); }
         csv::StringRecord::from(fields)
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 struct WhichStats {
     include_nulls: bool,
+    sample: bool,
     sum: bool,
     range: bool,
     dist: bool,
     cardinality: bool,
     median: bool,
+    quartiles: bool,
+    percentiles: Vec<f64>,
+    histogram: Option<usize>,
+    memory_limit: Option<usize>,
     mode: bool,
+    dates: Option<DateFormats>,
 }
 
 impl Commute for WhichStats {
@@ -250,39 +595,69 @@ struct Stats {
     sum: Option<TypedSum>,
     minmax: Option<TypedMinMax>,
     online: Option<OnlineStats>,
-    mode: Option<Unsorted<Vec<u8>>>,
-    median: Option<Unsorted<f64>>,
+    nullcount: u64,
+    freqs: Option<Frequencies>,
+    quantiles: Option<Quantiles>,
     which: WhichStats,
 }
 
 impl Stats {
     fn new(which: WhichStats) -> Stats {
-        let (mut sum, mut minmax, mut online, mut mode, mut median) =
+        let (mut sum, mut minmax, mut online, mut freqs, mut quantiles) =
             (None, None, None, None, None);
         if which.sum { sum = Some(Default::default()); }
         if which.range { minmax = Some(Default::default()); }
         if which.dist { online = Some(Default::default()); }
-        if which.mode || which.cardinality { mode = Some(Default::default()); }
-        if which.median { median = Some(Default::default()); }
+        if which.mode || which.cardinality { freqs = Some(()); }
+        if which.median || which.quartiles || !which.percentiles.is_empty()
+                || which.histogram.is_some() {
+            quantiles = Some(());
+        }
+        // Split the memory limit between the values that are retained.
+        let limit = which.memory_limit.map(|limit| {
+            if freqs.is_some() && quantiles.is_some() {
+                limit / 2
+            } else {
+                limit
+            }
+        });
+        let freqs = freqs.map(|()| Frequencies::with_limit(limit));
+        let quantiles = quantiles.map(|()| Quantiles::with_limit(limit));
         Stats {
             typ: Default::default(),
             sum: sum,
             minmax: minmax,
             online: online,
-            mode: mode,
-            median: median,
+            nullcount: 0,
+            freqs: freqs,
+            quantiles: quantiles,
             which: which,
         }
     }
 
-    fn add(&mut self, sample: &[u8]) {
-        let sample_type = FieldType::from_sample(sample);
+    /// Adds a field, which is treated as empty if it's NULL. Its length is
+    /// always that of the raw field.
+    fn add(&mut self, field: &[u8], is_null: bool) -> io::Result<()> {
+        let sample = if is_null { &b""[..] } else { field };
+        let (sample_type, date) =
+            FieldType::from_sample(sample, self.which.dates.as_ref());
         self.typ.merge(sample_type);
+        if sample_type.is_null() {
+            self.nullcount += 1;
+        }
 
         let t = self.typ;
         self.sum.as_mut().map(|v| v.add(t, sample));
-        self.minmax.as_mut().map(|v| v.add(t, sample));
-        self.mode.as_mut().map(|v| v.add(sample.to_vec()));
+        self.minmax.as_mut().map(|v| {
+            v.add_len(field.len());
+            v.add(t, sample);
+        });
+        if let Some(date) = date {
+            self.minmax.as_mut().map(|v| v.add_date(date, sample));
+        }
+        if let Some(ref mut v) = self.freqs {
+            v.add(sample)?;
+        }
         match self.typ {
             TUnknown => {}
             TNull => {
@@ -290,7 +665,7 @@ impl Stats {
                     self.online.as_mut().map(|v| { v.add_null(); });
                 }
             }
-            TUnicode => {}
+            TUnicode | TDate | TDateTime => {}
             TFloat | TInteger => {
                 if sample_type.is_null() {
                     if self.which.include_nulls {
@@ -298,14 +673,30 @@ impl Stats {
                     }
                 } else {
                     let n = from_bytes::<f64>(sample).unwrap();
-                    self.median.as_mut().map(|v| { v.add(n); });
+                    if let Some(ref mut v) = self.quantiles {
+                        v.add(n)?;
+                    }
                     self.online.as_mut().map(|v| { v.add(n); });
                 }
             }
         }
+        Ok(())
     }
 
-    fn to_record(&mut self) -> csv::StringRecord {
+    /// Returns the population variance, or the sample variance with
+    /// --sample, which isn't defined for fewer than two numbers.
+    fn variance(&self, v: &OnlineStats) -> Option<f64> {
+        let n = v.len() as f64;
+        if !self.which.sample {
+            Some(v.variance())
+        } else if n < 2.0 {
+            None
+        } else {
+            Some(v.variance() * n / (n - 1.0))
+        }
+    }
+
+    fn to_record(&mut self) -> CliResult<csv::StringRecord> {
         let typ = self.typ;
         let mut pieces = vec![];
         let empty = || "".to_owned();
@@ -325,25 +716,71 @@ impl Stats {
         }
 
         if !self.typ.is_number() {
-            pieces.push(empty()); pieces.push(empty());
+            pieces.push(empty()); pieces.push(empty()); pieces.push(empty());
         } else {
             match self.online {
                 Some(ref v) => {
                     pieces.push(v.mean().to_string());
-                    pieces.push(v.stddev().to_string());
+                    match self.variance(v) {
+                        Some(var) => {
+                            pieces.push(var.sqrt().to_string());
+                            pieces.push(var.to_string());
+                        }
+                        None => { pieces.push(empty()); pieces.push(empty()); }
+                    }
+                }
+                None => {
+                    pieces.push(empty()); pieces.push(empty());
+                    pieces.push(empty());
                 }
-                None => { pieces.push(empty()); pieces.push(empty()); }
             }
         }
-        match self.median.as_mut().and_then(|v| v.median()) {
-            None => {
-                if self.which.median {
-                    pieces.push(empty());
+        pieces.push(self.nullcount.to_string());
+        // Like the mean, quantiles are only reported for numeric columns.
+        // The numbers at all of the ranks that are needed are found at once,
+        // so that spilled numbers are only merged once.
+        let (mut quantiles, at) = match self.quantiles {
+            Some(ref mut v) if self.typ.is_number() => {
+                let mut ranks = vec![];
+                if self.which.median || self.which.quartiles {
+                    ranks.extend(v.quartile_ranks());
                 }
+                for &p in &self.which.percentiles {
+                    ranks.extend(v.percentile_ranks(p));
+                }
+                if self.which.histogram.is_some() {
+                    ranks.extend(v.histogram_ranks());
+                }
+                let at = v.at_ranks(&ranks)?;
+                (Some(v), at)
+            }
+            _ => (None, HashMap::new()),
+        };
+        let quartiles = match quantiles {
+            Some(ref v) if self.which.median || self.which.quartiles => {
+                v.quartiles(&at)
+            }
+            _ => None,
+        };
+        if self.which.median {
+            match quartiles {
+                Some((_, q2, _)) => { pieces.push(q2.to_string()); }
+                None => { pieces.push(empty()); }
             }
-            Some(v) => { pieces.push(v.to_string()); }
         }
-        match self.mode.as_mut() {
+        if self.which.quartiles {
+            match quartiles {
+                Some((q1, _, q3)) => {
+                    pieces.push(q1.to_string()); pieces.push(q3.to_string());
+                }
+                None => { pieces.push(empty()); pieces.push(empty()); }
+            }
+        }
+        for &p in &self.which.percentiles {
+            let v = quantiles.as_ref().and_then(|v| v.percentile(p, &at));
+            pieces.push(v.map_or_else(&empty, |v| v.to_string()));
+        }
+        match self.freqs.as_mut() {
             None => {
                 if self.which.mode {
                     pieces.push(empty());
@@ -352,20 +789,31 @@ impl Stats {
                     pieces.push(empty());
                 }
             }
-            Some(ref mut v) => {
+            Some(v) => {
                 if self.which.mode {
-                    let lossy = |s: Vec<u8>| -> String {
-                        String::from_utf8_lossy(&*s).into_owned()
+                    let lossy = |s: &[u8]| -> String {
+                        String::from_utf8_lossy(s).into_owned()
                     };
                     pieces.push(
-                        v.mode().map_or("N/A".to_owned(), lossy));
+                        v.mode()?.map_or("N/A".to_owned(), |v| lossy(&v)));
                 }
                 if self.which.cardinality {
-                    pieces.push(v.cardinality().to_string());
+                    pieces.push(v.cardinality()?.to_string());
                 }
             }
         }
-        csv::StringRecord::from(pieces)
+        if let Some(nbins) = self.which.histogram {
+            let bins = match quantiles {
+                Some(ref mut v) => v.histogram(nbins, &at)?,
+                None => None,
+            };
+            pieces.push(bins.map_or_else(&empty, |bins| {
+                let bins: Vec<_> =
+                    bins.iter().map(|n| n.to_string()).collect();
+                bins.join("|")
+            }));
+        }
+        Ok(csv::StringRecord::from(pieces))
     }
 }
 
@@ -374,9 +822,15 @@ impl Commute for Stats {
         self.typ.merge(other.typ);
         self.sum.merge(other.sum);
         self.minmax.merge(other.minmax);
-        self.online.merge(other.online);
-        self.mode.merge(other.mode);
-        self.median.merge(other.median);
+        match (self.online.as_mut(), other.online) {
+            // Merging two empty accumulators would divide by zero.
+            (Some(v1), Some(v2)) if v1.len() == 0 => *v1 = v2,
+            (Some(v1), Some(v2)) if v2.len() > 0 => v1.merge(v2),
+            _ => {}
+        }
+        self.nullcount += other.nullcount;
+        self.freqs.merge(other.freqs);
+        self.quantiles.merge(other.quantiles);
         self.which.merge(other.which);
     }
 }
@@ -388,20 +842,30 @@ enum FieldType {
     TUnicode,
     TFloat,
     TInteger,
+    TDate,
+    TDateTime,
 }
 
 impl FieldType {
-    fn from_sample(sample: &[u8]) -> FieldType {
+    /// Infers the type of `sample`. If `dates` is given and `sample` is a
+    /// date, then the date is returned too.
+    fn from_sample(
+        sample: &[u8],
+        dates: Option<&DateFormats>,
+    ) -> (FieldType, Option<NaiveDateTime>) {
         if sample.is_empty() {
-            return TNull;
+            return (TNull, None);
         }
         let string = match str::from_utf8(sample) {
-            Err(_) => return TUnknown,
+            Err(_) => return (TUnknown, None),
             Ok(s) => s,
         };
-        if let Ok(_) = string.parse::<i64>() { return TInteger; }
-        if let Ok(_) = string.parse::<f64>() { return TFloat; }
-        TUnicode
+        if let Ok(_) = string.parse::<i64>() { return (TInteger, None); }
+        if let Ok(_) = string.parse::<f64>() { return (TFloat, None); }
+        if let Some((typ, date)) = dates.and_then(|d| d.parse(string)) {
+            return (typ, Some(date));
+        }
+        (TUnicode, None)
     }
 
     fn is_number(&self) -> bool {
@@ -428,6 +892,13 @@ impl Commute for FieldType {
             // Numbers can degrade to Unicode strings.
             (TUnicode, TFloat) | (TFloat, TUnicode) => TUnicode,
             (TUnicode, TInteger) | (TInteger, TUnicode) => TUnicode,
+            (TDate, TDate) => TDate,
+            (TDateTime, TDateTime) => TDateTime,
+            // Dates can degrade to date times.
+            (TDate, TDateTime) | (TDateTime, TDate) => TDateTime,
+            // Dates mixed with anything else degrade to Unicode strings.
+            (TDate, _) | (_, TDate) => TUnicode,
+            (TDateTime, _) | (_, TDateTime) => TUnicode,
         };
     }
 }
@@ -447,16 +918,21 @@ impl fmt::Display for FieldType {
             TUnicode => write!(f, "Unicode"),
             TFloat => write!(f, "Float"),
             TInteger => write!(f, "Integer"),
+            TDate => write!(f, "Date"),
+            TDateTime => write!(f, "DateTime"),
         }
     }
 }
 
 /// TypedSum keeps a rolling sum of the data seen.
 ///
-/// It sums integers until it sees a float, at which point it sums floats.
+/// It sums integers exactly until it sees a float, at which point it sums
+/// floats. If the sum of integers doesn't fit in an `i128`, then it is
+/// reported as `OVERFLOW` instead of a wrong number.
 #[derive(Clone, Default)]
 struct TypedSum {
-    integer: i64,
+    integer: i128,
+    overflow: bool,
     float: Option<f64>,
 }
 
@@ -481,16 +957,25 @@ impl TypedSum {
                 if let Some(ref mut float) = self.float {
                     *float += from_bytes::<f64>(sample).unwrap();
                 } else {
-                    self.integer += from_bytes::<i64>(sample).unwrap();
+                    let n = from_bytes::<i64>(sample).unwrap();
+                    self.add_integer(n as i128);
                 }
             }
             _ => {}
         }
     }
 
+    fn add_integer(&mut self, n: i128) {
+        match self.integer.checked_add(n) {
+            Some(sum) => self.integer = sum,
+            None => self.overflow = true,
+        }
+    }
+
     fn show(&self, typ: FieldType) -> Option<String> {
         match typ {
-            TNull | TUnicode | TUnknown  => None,
+            TNull | TUnicode | TUnknown | TDate | TDateTime => None,
+            TInteger if self.overflow => Some("OVERFLOW".to_owned()),
             TInteger => Some(self.integer.to_string()),
             TFloat => Some(self.float.unwrap_or(0.0).to_string()),
         }
@@ -503,7 +988,10 @@ impl Commute for TypedSum {
             (Some(f1), Some(f2)) => self.float = Some(f1 + f2),
             (Some(f1), None) => self.float = Some(f1 + (other.integer as f64)),
             (None, Some(f2)) => self.float = Some((self.integer as f64) + f2),
-            (None, None) => self.integer += other.integer,
+            (None, None) => {
+                self.overflow |= other.overflow;
+                self.add_integer(other.integer);
+            }
         }
     }
 }
@@ -516,17 +1004,21 @@ struct TypedMinMax {
     str_len: MinMax<usize>,
     integers: MinMax<i64>,
     floats: MinMax<f64>,
+    dates: MinMax<(NaiveDateTime, Vec<u8>)>,
 }
 
 impl TypedMinMax {
+    fn add_len(&mut self, len: usize) {
+        self.str_len.add(len);
+    }
+
     fn add(&mut self, typ: FieldType, sample: &[u8]) {
-        self.str_len.add(sample.len());
         if sample.is_empty() {
             return;
         }
         self.strings.add(sample.to_vec());
         match typ {
-            TUnicode | TUnknown | TNull => {}
+            TUnicode | TUnknown | TNull | TDate | TDateTime => {}
             TFloat => {
                 let n = str::from_utf8(&*sample)
                             .ok()
@@ -546,6 +1038,11 @@ impl TypedMinMax {
         }
     }
 
+    /// Adds a date, which is shown as `sample`.
+    fn add_date(&mut self, date: NaiveDateTime, sample: &[u8]) {
+        self.dates.add((date, sample.to_vec()));
+    }
+
     fn len_range(&self) -> Option<(String, String)> {
         match (self.str_len.min(), self.str_len.max()) {
             (Some(min), Some(max)) => Some((min.to_string(), max.to_string())),
@@ -582,6 +1079,16 @@ impl TypedMinMax {
                     _ => None
                 }
             }
+            TDate | TDateTime => {
+                match (self.dates.min(), self.dates.max()) {
+                    (Some(min), Some(max)) => {
+                        let min = String::from_utf8_lossy(&*min.1).to_string();
+                        let max = String::from_utf8_lossy(&*max.1).to_string();
+                        Some((min, max))
+                    }
+                    _ => None
+                }
+            }
         }
     }
 }
@@ -593,6 +1100,7 @@ impl Default for TypedMinMax {
             str_len: Default::default(),
             integers: Default::default(),
             floats: Default::default(),
+            dates: Default::default(),
         }
     }
 }
@@ -603,7 +1111,587 @@ impl Commute for TypedMinMax {
         self.str_len.merge(other.str_len);
         self.integers.merge(other.integers);
         self.floats.merge(other.floats);
+        self.dates.merge(other.dates);
+    }
+}
+
+/// DateFormats are the strftime formats that dates are recognized by.
+#[derive(Clone, Debug, PartialEq)]
+struct DateFormats(Vec<String>);
+
+impl DateFormats {
+    /// Parses `s` with the first format that matches it. Dates that have a
+    /// UTC offset are converted to UTC, and dates without a time are at
+    /// midnight.
+    fn parse(&self, s: &str) -> Option<(FieldType, NaiveDateTime)> {
+        for fmt in &self.0 {
+            if let Ok(dt) = DateTime::parse_from_str(s, fmt) {
+                return Some((TDateTime, dt.naive_utc()));
+            }
+            if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) {
+                return Some((TDateTime, dt));
+            }
+            if let Ok(d) = NaiveDate::parse_from_str(s, fmt) {
+                return Some((TDate, d.and_hms_opt(0, 0, 0).unwrap()));
+            }
+        }
+        None
+    }
+}
+
+impl Default for DateFormats {
+    fn default() -> DateFormats {
+        DateFormats(vec![
+            "%Y-%m-%d".to_owned(),
+            "%Y-%m-%dT%H:%M:%S%.f%:z".to_owned(),
+            "%Y-%m-%dT%H:%M:%S%.fZ".to_owned(),
+            "%Y-%m-%dT%H:%M:%S%.f".to_owned(),
+            "%Y-%m-%d %H:%M:%S%.f".to_owned(),
+        ])
+    }
+}
+
+impl Decodable for DateFormats {
+    fn decode<D: Decoder>(d: &mut D) -> Result<DateFormats, D::Error> {
+        let raw = d.read_str()?;
+        let mut formats = vec![];
+        for fmt in raw.split(';') {
+            if fmt.is_empty()
+                    || StrftimeItems::new(fmt).any(|it| it == Item::Error) {
+                let msg = format!("Invalid date format '{}'.", fmt);
+                return Err(d.error(&*msg));
+            }
+            formats.push(fmt.to_owned());
+        }
+        Ok(DateFormats(formats))
+    }
+}
+
+/// Frequencies counts the occurrences of every distinct value in a column.
+///
+/// Merging the counts of two chunks of data sums the counts of values that
+/// appear in both, so each distinct value is only counted once towards the
+/// cardinality.
+///
+/// When the counts take up more than `limit` bytes, they are written to a
+/// spill sorted by value, and the spills are merged to compute the mode and
+/// cardinality.
+#[derive(Clone, Default)]
+struct Frequencies {
+    counts: HashMap<Vec<u8>, u64>,
+    size: usize,
+    limit: Option<usize>,
+    spills: Vec<Arc<Spill>>,
+}
+
+/// The approximate number of bytes used by each count, in addition to the
+/// bytes of its value.
+const COUNT_OVERHEAD: usize = 48;
+
+impl Frequencies {
+    fn with_limit(limit: Option<usize>) -> Frequencies {
+        Frequencies { limit: limit, ..Default::default() }
+    }
+
+    fn add(&mut self, sample: &[u8]) -> io::Result<()> {
+        if let Some(count) = self.counts.get_mut(sample) {
+            *count += 1;
+            return Ok(());
+        }
+        self.counts.insert(sample.to_vec(), 1);
+        self.size += sample.len() + COUNT_OVERHEAD;
+        if self.limit.map_or(false, |limit| self.size >= limit) {
+            self.spill()?;
+        }
+        Ok(())
     }
+
+    /// Writes the counts in memory to a new spill, sorted by value.
+    fn spill(&mut self) -> io::Result<()> {
+        let mut counts: Vec<_> = self.counts.drain().collect();
+        counts.sort();
+        let (spill, mut wtr) = Spill::create()?;
+        for (v, count) in counts {
+            write_count(&mut wtr, &v, count)?;
+        }
+        wtr.flush()?;
+        self.spills.push(Arc::new(spill));
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Calls `f` with every distinct value and its count.
+    ///
+    /// The values are in no particular order.
+    fn each_count<F>(&mut self, mut f: F) -> io::Result<()>
+            where F: FnMut(&[u8], u64) {
+        if self.spills.is_empty() {
+            for (v, &count) in &self.counts {
+                f(v, count);
+            }
+            return Ok(());
+        }
+        if !self.counts.is_empty() {
+            self.spill()?;
+        }
+        while self.spills.len() > SPILL_FAN_IN {
+            let spills = mem::replace(&mut self.spills, vec![]);
+            for group in spills.chunks(SPILL_FAN_IN) {
+                let (spill, mut wtr) = Spill::create()?;
+                merge_counts(group, |v, n| write_count(&mut wtr, v, n))?;
+                wtr.flush()?;
+                self.spills.push(Arc::new(spill));
+            }
+        }
+        merge_counts(&self.spills, |v, count| Ok(f(v, count)))
+    }
+
+    /// Returns the most frequent value. Ties are broken by picking the
+    /// lexicographically smallest value.
+    fn mode(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut mode: Option<(Vec<u8>, u64)> = None;
+        self.each_count(|v, count| {
+            let better = match mode {
+                None => true,
+                Some((ref mv, mc)) => count > mc || (count == mc && v < &**mv),
+            };
+            if better {
+                mode = Some((v.to_vec(), count));
+            }
+        })?;
+        Ok(mode.map(|(v, _)| v))
+    }
+
+    fn cardinality(&mut self) -> io::Result<usize> {
+        if self.spills.is_empty() {
+            return Ok(self.counts.len());
+        }
+        let mut cardinality = 0;
+        self.each_count(|_, _| cardinality += 1)?;
+        Ok(cardinality)
+    }
+}
+
+impl Commute for Frequencies {
+    fn merge(&mut self, other: Frequencies) {
+        for (v, count) in other.counts {
+            *self.counts.entry(v).or_insert(0) += count;
+        }
+        self.size += other.size;
+        self.spills.extend(other.spills);
+    }
+}
+
+/// Calls `f` with every distinct value in the sorted `spills` of
+/// `Frequencies` and its total count, in ascending order of value.
+fn merge_counts<F>(spills: &[Arc<Spill>], mut f: F) -> io::Result<()>
+        where F: FnMut(&[u8], u64) -> io::Result<()> {
+    let mut rdrs = vec![];
+    let mut counts = vec![];
+    let mut heads = BinaryHeap::new();
+    for (i, spill) in spills.iter().enumerate() {
+        let mut rdr = spill.open()?;
+        counts.push(0);
+        if let Some((v, count)) = read_count(&mut rdr)? {
+            counts[i] = count;
+            heads.push(Reverse((v, i)));
+        }
+        rdrs.push(rdr);
+    }
+    while let Some(Reverse((v, i))) = heads.pop() {
+        let mut count = counts[i];
+        let mut next = vec![i];
+        // Each spill has at most one count for each value.
+        while heads.peek().map_or(false, |&Reverse((ref w, _))| *w == v) {
+            let Reverse((_, j)) = heads.pop().unwrap();
+            count += counts[j];
+            next.push(j);
+        }
+        f(&v, count)?;
+        for j in next {
+            if let Some((w, count)) = read_count(&mut rdrs[j])? {
+                counts[j] = count;
+                heads.push(Reverse((w, j)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a value and its count to a spill of `Frequencies`.
+fn write_count<W: io::Write>(wtr: &mut W, v: &[u8], count: u64)
+                            -> io::Result<()> {
+    wtr.write_u64::<LittleEndian>(v.len() as u64)?;
+    wtr.write_all(v)?;
+    wtr.write_u64::<LittleEndian>(count)
+}
+
+/// Reads the next value and its count from a spill of `Frequencies`.
+fn read_count<R: io::Read>(rdr: &mut R) -> io::Result<Option<(Vec<u8>, u64)>> {
+    let len = match rdr.read_u64::<LittleEndian>() {
+        Ok(len) => len as usize,
+        Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            return Ok(None);
+        }
+        Err(err) => return Err(err),
+    };
+    let mut v = vec![0; len];
+    rdr.read_exact(&mut v)?;
+    let count = rdr.read_u64::<LittleEndian>()?;
+    Ok(Some((v, count)))
+}
+
+/// A list of percentiles given on the command line, each between 0 and 100.
+#[derive(Clone, Debug)]
+struct Percentiles(Vec<f64>);
+
+impl Decodable for Percentiles {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Percentiles, D::Error> {
+        let raw = d.read_str()?;
+        let mut percentiles = vec![];
+        for p in raw.split(',') {
+            match p.trim().parse::<f64>() {
+                Ok(n) if n >= 0.0 && n <= 100.0 => percentiles.push(n),
+                _ => {
+                    let msg = format!("Invalid percentile '{}'. Percentiles \
+                                       must be numbers between 0 and 100.",
+                                      p);
+                    return Err(d.error(&*msg));
+                }
+            }
+        }
+        Ok(Percentiles(percentiles))
+    }
+}
+
+/// Quantiles retains every number seen so that the median and quartiles can
+/// be computed once all of the data has been read.
+///
+/// The quartiles are the medians of the lower and upper halves of the data.
+/// When there is an odd number of values, the median itself is excluded from
+/// both halves.
+///
+/// When the numbers take up more than `limit` bytes, they are written to a
+/// sorted spill, and the spills are merged to find the numbers at each rank.
+#[derive(Clone, Default)]
+struct Quantiles {
+    data: Vec<f64>,
+    sorted: bool,
+    len: usize,
+    limit: Option<usize>,
+    spills: Vec<Arc<Spill>>,
+}
+
+impl Quantiles {
+    fn with_limit(limit: Option<usize>) -> Quantiles {
+        Quantiles { limit: limit, ..Default::default() }
+    }
+
+    fn add(&mut self, n: f64) -> io::Result<()> {
+        self.data.push(n);
+        self.sorted = false;
+        self.len += 1;
+        if self.limit.map_or(false, |limit| self.data.len() * 8 >= limit) {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn sort(&mut self) {
+        if !self.sorted {
+            self.data.sort_by(|a, b| {
+                a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal)
+            });
+            self.sorted = true;
+        }
+    }
+
+    /// Writes the numbers in memory to a new sorted spill.
+    fn spill(&mut self) -> io::Result<()> {
+        self.sort();
+        let (spill, mut wtr) = Spill::create()?;
+        for &n in &self.data {
+            wtr.write_f64::<LittleEndian>(n)?;
+        }
+        wtr.flush()?;
+        self.spills.push(Arc::new(spill));
+        self.data.clear();
+        Ok(())
+    }
+
+    /// Calls `f` with every number in ascending order.
+    fn each_sorted<F: FnMut(f64)>(&mut self, mut f: F) -> io::Result<()> {
+        self.sort();
+        if self.spills.is_empty() {
+            for &n in &self.data {
+                f(n);
+            }
+            return Ok(());
+        }
+        if !self.data.is_empty() {
+            self.spill()?;
+        }
+        while self.spills.len() > SPILL_FAN_IN {
+            let spills = mem::replace(&mut self.spills, vec![]);
+            for group in spills.chunks(SPILL_FAN_IN) {
+                let (spill, mut wtr) = Spill::create()?;
+                merge_numbers(group, |n| wtr.write_f64::<LittleEndian>(n))?;
+                wtr.flush()?;
+                self.spills.push(Arc::new(spill));
+            }
+        }
+        merge_numbers(&self.spills, |n| Ok(f(n)))
+    }
+
+    /// Returns the numbers at the given ranks, i.e., indices into all of
+    /// the numbers in ascending order, reading all of the numbers at most
+    /// once.
+    fn at_ranks(&mut self, ranks: &[usize])
+               -> io::Result<HashMap<usize, f64>> {
+        if self.spills.is_empty() {
+            self.sort();
+            return Ok(ranks.iter().map(|&r| (r, self.data[r])).collect());
+        }
+        let mut values = HashMap::new();
+        if ranks.is_empty() {
+            return Ok(values);
+        }
+        let mut wanted = ranks.to_vec();
+        wanted.sort();
+        wanted.dedup();
+        let (mut rank, mut next) = (0, 0);
+        self.each_sorted(|n| {
+            if next < wanted.len() && wanted[next] == rank {
+                values.insert(rank, n);
+                next += 1;
+            }
+            rank += 1;
+        })?;
+        Ok(values)
+    }
+
+    /// Returns the ranks that `quartiles` needs.
+    fn quartile_ranks(&self) -> Vec<usize> {
+        let len = self.len;
+        match len {
+            0 => vec![],
+            1 => vec![0],
+            _ => {
+                let half = len / 2;
+                let mut ranks = median_ranks(0, half);
+                ranks.extend(median_ranks(0, len));
+                ranks.extend(median_ranks(len - half, half));
+                ranks
+            }
+        }
+    }
+
+    /// Returns the quartiles, given the numbers at `quartile_ranks`.
+    fn quartiles(&self, at: &HashMap<usize, f64>)
+                -> Option<(f64, f64, f64)> {
+        let len = self.len;
+        if len == 0 {
+            return None;
+        }
+        if len == 1 {
+            let n = at[&0];
+            return Some((n, n, n));
+        }
+        let median = |start, len| {
+            let ranks = median_ranks(start, len);
+            ranks.iter().map(|r| at[r]).sum::<f64>() / (ranks.len() as f64)
+        };
+        let half = len / 2;
+        Some((median(0, half), median(0, len), median(len - half, half)))
+    }
+
+    /// Returns the rank of the `p`th percentile, which is between two
+    /// ranks unless it's a whole number.
+    fn percentile_rank(&self, p: f64) -> f64 {
+        (p / 100.0) * ((self.len - 1) as f64)
+    }
+
+    /// Returns the ranks that `percentile(p)` needs.
+    fn percentile_ranks(&self, p: f64) -> Vec<usize> {
+        if self.len == 0 {
+            return vec![];
+        }
+        let rank = self.percentile_rank(p);
+        vec![rank.floor() as usize, rank.ceil() as usize]
+    }
+
+    /// Returns the `p`th percentile, where `0 <= p <= 100`, given the
+    /// numbers at `percentile_ranks(p)`.
+    ///
+    /// This interpolates linearly between the closest ranks, such that the
+    /// 0th and 100th percentiles are the minimum and maximum values.
+    fn percentile(&self, p: f64, at: &HashMap<usize, f64>) -> Option<f64> {
+        if self.len == 0 {
+            return None;
+        }
+        let rank = self.percentile_rank(p);
+        let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+        let weight = rank - (lo as f64);
+        Some(at[&lo] + (at[&hi] - at[&lo]) * weight)
+    }
+
+    /// Returns the ranks that `histogram` needs, i.e., of the minimum and
+    /// maximum values.
+    fn histogram_ranks(&self) -> Vec<usize> {
+        if self.len == 0 { vec![] } else { vec![0, self.len - 1] }
+    }
+
+    /// Returns the number of values in each of `nbins` bins of equal width
+    /// between the minimum and maximum values, given the numbers at
+    /// `histogram_ranks`.
+    ///
+    /// If the minimum and maximum are the same, then every value is in the
+    /// first bin.
+    fn histogram(&mut self, nbins: usize, at: &HashMap<usize, f64>)
+                -> io::Result<Option<Vec<u64>>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        let (min, max) = (at[&0], at[&(self.len - 1)]);
+        let width = (max - min) / (nbins as f64);
+        let mut bins = vec![0; nbins];
+        self.each_sorted(|n| {
+            let bin = if width > 0.0 {
+                // The maximum belongs to the last bin.
+                cmp::min(((n - min) / width) as usize, nbins - 1)
+            } else {
+                0
+            };
+            bins[bin] += 1;
+        })?;
+        Ok(Some(bins))
+    }
+}
+
+impl Commute for Quantiles {
+    fn merge(&mut self, other: Quantiles) {
+        self.data.extend(other.data);
+        self.sorted = false;
+        self.len += other.len;
+        self.spills.extend(other.spills);
+    }
+}
+
+/// Returns the ranks of the numbers whose mean is the median of the `len`
+/// numbers starting at rank `start`.
+fn median_ranks(start: usize, len: usize) -> Vec<usize> {
+    let mid = start + len / 2;
+    if len % 2 == 0 { vec![mid - 1, mid] } else { vec![mid] }
+}
+
+/// Calls `f` with every number in the sorted `spills` of `Quantiles`, in
+/// ascending order.
+fn merge_numbers<F>(spills: &[Arc<Spill>], mut f: F) -> io::Result<()>
+        where F: FnMut(f64) -> io::Result<()> {
+    let mut rdrs = vec![];
+    let mut heads = BinaryHeap::new();
+    for (i, spill) in spills.iter().enumerate() {
+        let mut rdr = spill.open()?;
+        if let Some(n) = read_number(&mut rdr)? {
+            heads.push(Reverse((Number(n), i)));
+        }
+        rdrs.push(rdr);
+    }
+    while let Some(Reverse((Number(n), i))) = heads.pop() {
+        f(n)?;
+        if let Some(n) = read_number(&mut rdrs[i])? {
+            heads.push(Reverse((Number(n), i)));
+        }
+    }
+    Ok(())
+}
+
+/// A number that can be ordered, in the same order used to sort the numbers
+/// of `Quantiles`.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+struct Number(f64);
+
+impl Eq for Number {}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Number) -> cmp::Ordering {
+        self.partial_cmp(other).unwrap_or(cmp::Ordering::Equal)
+    }
+}
+
+/// Reads the next number from a spill of `Quantiles`.
+fn read_number<R: io::Read>(rdr: &mut R) -> io::Result<Option<f64>> {
+    match rdr.read_f64::<LittleEndian>() {
+        Ok(n) => Ok(Some(n)),
+        Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// The largest number of spills that are merged at once. With more spills,
+/// they are first merged into fewer, bigger spills, so that no more than
+/// this many files are open at the same time.
+const SPILL_FAN_IN: usize = 32;
+
+/// A spill is a temporary file that holds a sorted run of the values that
+/// `Frequencies` or `Quantiles` retain, once they exceed --memory-limit.
+///
+/// The file is removed when the last reference to it is dropped, whether or
+/// not the stats were computed successfully.
+struct Spill {
+    path: PathBuf,
+}
+
+impl Spill {
+    fn create() -> io::Result<(Spill, io::BufWriter<fs::File>)> {
+        loop {
+            let name = format!("xsv-stats-{}-{:016x}",
+                               process::id(), rand::random::<u64>());
+            let path = env::temp_dir().join(name);
+            let file = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path);
+            match file {
+                Ok(file) => {
+                    let spill = Spill { path: path };
+                    return Ok((spill, io::BufWriter::new(file)));
+                }
+                Err(err) => {
+                    if err.kind() != io::ErrorKind::AlreadyExists {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+    }
+
+    fn open(&self) -> io::Result<io::BufReader<fs::File>> {
+        Ok(io::BufReader::new(fs::File::open(&self.path)?))
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Adds the selected fields of `record` to `stats`.
+fn add_record(
+    nulls: &util::NullValues,
+    sel: &Selection,
+    stats: &mut [Stats],
+    record: &csv::ByteRecord,
+) -> io::Result<()> {
+    for (i, field) in sel.select(record).enumerate() {
+        stats[i].add(field, nulls.is_null(field))?;
+    }
+    Ok(())
+}
+
+fn json_string(s: &[u8]) -> String {
+    json::encode(&String::from_utf8_lossy(s).into_owned()).unwrap()
 }
 
 fn from_bytes<T: FromStr>(bytes: &[u8]) -> Option<T> {
@@ -1,7 +1,9 @@
 use std::borrow::Cow;
+use std::cmp;
 
 use csv;
 use tabwriter::TabWriter;
+use unicode_width::UnicodeWidthChar;
 
 use CliResult;
 use config::{Config, Delimiter};
@@ -10,7 +12,13 @@ use util;
 static USAGE: &'static str = "
 Outputs CSV data as a table with columns in alignment.
 
-This will not work well if the CSV data contains large fields.
+This will not work well if the CSV data contains large fields, unless they're
+truncated with --condense or --max-width.
+
+Columns where every field after the first row is a number (or empty) are
+aligned to the right. Widths are measured in the number of columns each
+character takes up on a terminal, so wide characters (e.g., in Chinese) and
+combining characters are aligned correctly.
 
 Note that formatting a table requires buffering all CSV data into memory.
 Therefore, you should use the 'sample' or 'slice' command to trim down large
@@ -24,10 +32,14 @@ table options:
                            [default: 2]
     -p, --pad <arg>        The minimum number of spaces between each column.
                            [default: 2]
-    -c, --condense <arg>  Limits the length of each field to the value
-                           specified. If the field is UTF-8 encoded, then
-                           <arg> refers to the number of code points.
-                           Otherwise, it refers to the number of bytes.
+    -c, --condense <arg>   Limits the length of each field to the value
+                           specified, followed by '...'. If the field is
+                           UTF-8 encoded, then <arg> refers to the number of
+                           code points. Otherwise, it refers to the number
+                           of bytes.
+    -m, --max-width <arg>  Limits the width of each line of the table by
+                           truncating the widest columns, whose fields then
+                           end with '...'.
 
 Common options:
     -h, --help             Display this message
@@ -44,6 +56,7 @@ struct Args {
     flag_output: Option<String>,
     flag_delimiter: Option<Delimiter>,
     flag_condense: Option<usize>,
+    flag_max_width: Option<usize>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
@@ -60,12 +73,95 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let mut wtr = wconfig.from_writer(tw);
     let mut rdr = rconfig.reader()?;
 
+    // The whole table is buffered anyway, so read it first to find the
+    // width of each column and which columns are numeric.
+    let mut rows: Vec<Vec<Vec<u8>>> = vec![];
     let mut record = csv::ByteRecord::new();
     while rdr.read_byte_record(&mut record)? {
-        wtr.write_record(record.iter().map(|f| {
-            util::condense(Cow::Borrowed(f), args.flag_condense)
+        rows.push(record.iter().map(|f| {
+            util::condense(Cow::Borrowed(f), args.flag_condense).into_owned()
+        }).collect());
+    }
+    let ncols = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut widths = vec![args.flag_width; ncols];
+    let mut numeric = vec![Some(false); ncols];
+    for (i, row) in rows.iter().enumerate() {
+        for (j, field) in row.iter().enumerate() {
+            widths[j] = cmp::max(widths[j], display_width(field));
+            if i > 0 && !field.is_empty() && numeric[j].is_some() {
+                numeric[j] = if is_number(field) { Some(true) } else { None };
+            }
+        }
+    }
+    if let Some(max_width) = args.flag_max_width {
+        shrink(&mut widths, max_width, args.flag_pad,
+               cmp::max(args.flag_width, 4));
+    }
+
+    for row in rows {
+        wtr.write_record(row.into_iter().enumerate().map(|(j, field)| {
+            let field = if display_width(&field) > widths[j] {
+                truncate(&field, widths[j])
+            } else {
+                field
+            };
+            if numeric[j] == Some(true) {
+                let pad = widths[j] - display_width(&field);
+                let mut padded = vec![b' '; pad];
+                padded.extend(field);
+                padded
+            } else {
+                field
+            }
         }))?;
     }
     wtr.flush()?;
     Ok(())
 }
+
+/// Returns the number of columns `field` takes up on a terminal.
+fn display_width(field: &[u8]) -> usize {
+    String::from_utf8_lossy(field)
+        .chars()
+        .map(|c| UnicodeWidthChar::width(c).unwrap_or(0))
+        .sum()
+}
+
+fn is_number(field: &[u8]) -> bool {
+    String::from_utf8_lossy(field).trim().parse::<f64>().is_ok()
+}
+
+/// Narrows the widest columns, but not below `min_width`, until the table
+/// fits in `max_width`.
+fn shrink(widths: &mut [usize], max_width: usize, pad: usize,
+          min_width: usize) {
+    if widths.is_empty() {
+        return;
+    }
+    let mut total: usize =
+        widths.iter().sum::<usize>() + pad * (widths.len() - 1);
+    while total > max_width {
+        let widest = (0..widths.len()).max_by_key(|&j| widths[j]).unwrap();
+        if widths[widest] <= min_width {
+            break;
+        }
+        widths[widest] -= 1;
+        total -= 1;
+    }
+}
+
+/// Cuts `field` down to `width` columns, ending with '...'.
+fn truncate(field: &[u8], width: usize) -> Vec<u8> {
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in String::from_utf8_lossy(field).chars() {
+        let w = UnicodeWidthChar::width(c).unwrap_or(0);
+        if used + w + 3 > width {
+            break;
+        }
+        truncated.push(c);
+        used += w;
+    }
+    truncated.push_str("...");
+    truncated.into_bytes()
+}
@@ -0,0 +1,139 @@
+use std::io::Write;
+
+use csv;
+use rustc_serialize::json;
+
+use CliResult;
+use config::{Config, Delimiter};
+use util;
+
+static USAGE: &'static str = "
+Converts CSV data to JSON.
+
+Each record is written as a JSON object whose keys are the header names, on a
+line of its own. (This is known as newline delimited JSON, or NDJSON.) The
+objects can be written as a single JSON array instead with --array.
+
+Every value is written as a JSON string, unless --numbers is set. Fields that
+aren't valid UTF-8 have their invalid bytes replaced.
+
+  $ xsv tojson data.csv
+  {\"name\":\"Boston\",\"population\":\"667137\"}
+  {\"name\":\"Buffalo\",\"population\":\"258071\"}
+
+Usage:
+    xsv tojson [options] [<input>]
+    xsv tojson --help
+
+tojson options:
+    -a, --array            Write a single JSON array of objects.
+    --numbers              Write the values that are valid JSON numbers as
+                           numbers instead of strings.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers, and the keys are 'field0', 'field1',
+                           etc.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    flag_array: bool,
+    flag_numbers: bool,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(&args.arg_input)
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(&args.flag_output).io_writer()?;
+
+    let headers = rdr.byte_headers()?.clone();
+    let keys: Vec<String> = headers.iter().enumerate().map(|(i, h)| {
+        if rconfig.no_headers {
+            json_string(format!("field{}", i).as_bytes())
+        } else {
+            json_string(h)
+        }
+    }).collect();
+
+    let mut record = csv::ByteRecord::new();
+    let mut first = true;
+    while rdr.read_byte_record(&mut record)? {
+        if args.flag_array {
+            wtr.write_all(if first { b"[" } else { b",\n" })?;
+        }
+        first = false;
+        wtr.write_all(b"{")?;
+        for (i, (key, field)) in keys.iter().zip(&record).enumerate() {
+            if i > 0 {
+                wtr.write_all(b",")?;
+            }
+            wtr.write_all(key.as_bytes())?;
+            wtr.write_all(b":")?;
+            if args.flag_numbers && is_json_number(field) {
+                wtr.write_all(field)?;
+            } else {
+                wtr.write_all(json_string(field).as_bytes())?;
+            }
+        }
+        wtr.write_all(b"}")?;
+        if !args.flag_array {
+            wtr.write_all(b"\n")?;
+        }
+    }
+    if args.flag_array {
+        wtr.write_all(if first { b"[]\n" } else { b"]\n" })?;
+    }
+    Ok(wtr.flush()?)
+}
+
+fn json_string(s: &[u8]) -> String {
+    json::encode(&String::from_utf8_lossy(s).into_owned()).unwrap()
+}
+
+/// Returns true if `s` follows the JSON grammar for numbers, e.g., `-1.5e3`.
+/// (Unlike Rust, JSON doesn't allow `+1`, `.5`, `01` or `NaN`.)
+fn is_json_number(s: &[u8]) -> bool {
+    fn digits(s: &[u8]) -> usize {
+        s.iter().take_while(|b| b.is_ascii_digit()).count()
+    }
+
+    let mut i = 0;
+    if s.get(i) == Some(&b'-') {
+        i += 1;
+    }
+    match digits(&s[i..]) {
+        0 => return false,
+        n if n > 1 && s[i] == b'0' => return false,
+        n => i += n,
+    }
+    if s.get(i) == Some(&b'.') {
+        match digits(&s[i+1..]) {
+            0 => return false,
+            n => i += 1 + n,
+        }
+    }
+    if s.get(i) == Some(&b'e') || s.get(i) == Some(&b'E') {
+        i += 1;
+        if s.get(i) == Some(&b'+') || s.get(i) == Some(&b'-') {
+            i += 1;
+        }
+        match digits(&s[i..]) {
+            0 => return false,
+            n => i += n,
+        }
+    }
+    i == s.len()
+}
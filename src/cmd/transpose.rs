@@ -0,0 +1,104 @@
+use std::cmp;
+
+use csv;
+
+use CliResult;
+use config::{Config, Delimiter};
+use util;
+
+static USAGE: &'static str = "
+Transposes CSV data, so that rows become columns and columns become rows.
+The header row, if any, is treated like any other row and becomes the first
+column of the output.
+
+Records that are shorter than the longest record in the data are padded with
+empty fields.
+
+By default, all of the CSV data is read into memory. If the data is too big
+for that, use --multipass, which reads the input once for every column in
+the data instead. This requires the input to be a file and not stdin.
+
+Usage:
+    xsv transpose [options] [<input>]
+    xsv transpose --help
+
+transpose options:
+    -m, --multipass        Process the transpose by making multiple passes
+                           over the data, using constant memory per pass.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(RustcDecodable)]
+struct Args {
+    arg_input: Option<String>,
+    flag_multipass: bool,
+    flag_output: Option<String>,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_multipass {
+        args.multipass_transpose()
+    } else {
+        args.in_memory_transpose()
+    }
+}
+
+impl Args {
+    fn in_memory_transpose(&self) -> CliResult<()> {
+        let mut rdr = self.rconfig().reader()?;
+        let mut wtr = self.wconfig().writer()?;
+        let records = rdr.byte_records().collect::<Result<Vec<_>, _>>()?;
+        let ncols = records.iter().map(|r| r.len()).max().unwrap_or(0);
+        for i in 0..ncols {
+            let mut record = csv::ByteRecord::new();
+            for row in &records {
+                record.push_field(row.get(i).unwrap_or(b""));
+            }
+            wtr.write_byte_record(&record)?;
+        }
+        Ok(wtr.flush()?)
+    }
+
+    fn multipass_transpose(&self) -> CliResult<()> {
+        let rconfig = self.rconfig();
+        if rconfig.is_std() {
+            return fail!("<stdin> cannot be used with --multipass. \
+                          Please specify a file path.");
+        }
+        let mut wtr = self.wconfig().writer()?;
+
+        let mut ncols = 0;
+        let mut record = csv::ByteRecord::new();
+        let mut rdr = rconfig.reader_file()?;
+        while rdr.read_byte_record(&mut record)? {
+            ncols = cmp::max(ncols, record.len());
+        }
+        for i in 0..ncols {
+            let mut out = csv::ByteRecord::new();
+            let mut rdr = rconfig.reader_file()?;
+            while rdr.read_byte_record(&mut record)? {
+                out.push_field(record.get(i).unwrap_or(b""));
+            }
+            wtr.write_byte_record(&out)?;
+        }
+        Ok(wtr.flush()?)
+    }
+
+    fn rconfig(&self) -> Config {
+        Config::new(&self.arg_input)
+            .delimiter(self.flag_delimiter)
+            .no_headers(true)
+            .flexible(true)
+    }
+
+    fn wconfig(&self) -> Config {
+        Config::new(&self.flag_output)
+    }
+}
@@ -160,11 +160,28 @@ impl Config {
         self
     }
 
+    /// Sets the path of the index, instead of `<input>.idx`.
+    pub fn idx_path(mut self, path: Option<PathBuf>) -> Config {
+        self.idx_path = path;
+        self
+    }
+
     pub fn select(mut self, sel_cols: SelectColumns) -> Config {
         self.select_columns = Some(sel_cols);
         self
     }
 
+    pub fn get_delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    /// The size of the input in bytes, if it's a file.
+    pub fn input_len(&self) -> Option<u64> {
+        self.path.as_ref()
+            .and_then(|p| fs::metadata(p).ok())
+            .map(|md| md.len())
+    }
+
     pub fn is_std(&self) -> bool {
         self.path.is_none()
     }
@@ -285,6 +302,8 @@ impl Config {
             .has_headers(!self.no_headers)
             .quote(self.quote)
             .quoting(self.quoting)
+            .double_quote(self.double_quote)
+            .escape(if self.double_quote { None } else { Some(self.escape) })
             .from_reader(rdr)
     }
 
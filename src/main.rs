@@ -1,16 +1,23 @@
 extern crate byteorder;
 extern crate chan;
+extern crate chrono;
 extern crate csv;
 extern crate csv_index;
 extern crate docopt;
 extern crate filetime;
+extern crate flate2;
+extern crate hmac;
+extern crate libc;
+extern crate memchr;
 extern crate num_cpus;
 extern crate rand;
 extern crate regex;
 extern crate rustc_serialize;
+extern crate sha2;
 extern crate stats;
 extern crate tabwriter;
 extern crate threadpool;
+extern crate unicode_width;
 
 use std::borrow::ToOwned;
 use std::env;
@@ -41,25 +48,39 @@ macro_rules! fail {
 macro_rules! command_list {
     () => (
 "
+    apply       Apply operations to fields
+    behead      Drop the header row
     cat         Concatenate by row or column
     count       Count records
+    dedup       Remove duplicate rows
+    enumerate   Add a column with a row number
+    fill        Fill empty values
     fixlengths  Makes all records have same length
     flatten     Show one field per line
     fmt         Format CSV output (change field delimiter)
     frequency   Show frequency tables
+    fromjson    Convert JSON to CSV data
     headers     Show header names
     help        Show this usage message.
     index       Create CSV index for faster access
     input       Read CSV data with special quoting rules
     join        Join CSV files
+    partition   Partition CSV data based on a column value
+    pseudo      Pseudonymize the values of columns
+    rename      Rename the columns of CSV data
+    replace     Replace regex matches in CSV data
+    reverse     Reverse the order of records
     sample      Randomly sample CSV data
     search      Search CSV data with regexes
+    searchset   Search CSV data with a file of regexes
     select      Select columns from CSV
     slice       Slice records from CSV
     sort        Sort CSV data
     split       Split CSV data into many files
     stats       Compute basic statistics
     table       Align CSV data into columns
+    tojson      Convert CSV data to JSON
+    transpose   Transpose rows and columns of CSV data
 "
     )
 }
@@ -135,26 +156,39 @@ Please choose one of the following commands:",
 
 #[derive(Debug, RustcDecodable)]
 enum Command {
+    Apply,
+    Behead,
     Cat,
     Count,
+    Dedup,
+    Enumerate,
+    Fill,
     FixLengths,
     Flatten,
     Fmt,
     Frequency,
+    FromJson,
     Headers,
     Help,
     Index,
     Input,
     Join,
     Partition,
+    Pseudo,
+    Rename,
+    Replace,
+    Reverse,
     Sample,
     Search,
+    Searchset,
     Select,
     Slice,
     Sort,
     Split,
     Stats,
     Table,
+    ToJson,
+    Transpose,
 }
 
 impl Command {
@@ -163,26 +197,39 @@ impl Command {
         let argv: Vec<_> = argv.iter().map(|s| &**s).collect();
         let argv = &*argv;
         match self {
+            Command::Apply => cmd::apply::run(argv),
+            Command::Behead => cmd::behead::run(argv),
             Command::Cat => cmd::cat::run(argv),
             Command::Count => cmd::count::run(argv),
+            Command::Dedup => cmd::dedup::run(argv),
+            Command::Enumerate => cmd::enumerate::run(argv),
+            Command::Fill => cmd::fill::run(argv),
             Command::FixLengths => cmd::fixlengths::run(argv),
             Command::Flatten => cmd::flatten::run(argv),
             Command::Fmt => cmd::fmt::run(argv),
             Command::Frequency => cmd::frequency::run(argv),
+            Command::FromJson => cmd::fromjson::run(argv),
             Command::Headers => cmd::headers::run(argv),
             Command::Help => { wout!("{}", USAGE); Ok(()) }
             Command::Index => cmd::index::run(argv),
             Command::Input => cmd::input::run(argv),
             Command::Join => cmd::join::run(argv),
             Command::Partition => cmd::partition::run(argv),
+            Command::Pseudo => cmd::pseudo::run(argv),
+            Command::Rename => cmd::rename::run(argv),
+            Command::Replace => cmd::replace::run(argv),
+            Command::Reverse => cmd::reverse::run(argv),
             Command::Sample => cmd::sample::run(argv),
             Command::Search => cmd::search::run(argv),
+            Command::Searchset => cmd::searchset::run(argv),
             Command::Select => cmd::select::run(argv),
             Command::Slice => cmd::slice::run(argv),
             Command::Sort => cmd::sort::run(argv),
             Command::Split => cmd::split::run(argv),
             Command::Stats => cmd::stats::run(argv),
             Command::Table => cmd::table::run(argv),
+            Command::ToJson => cmd::tojson::run(argv),
+            Command::Transpose => cmd::transpose::run(argv),
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::cmp::Ordering;
+use std::cmp::{self, Ordering};
 use std::collections::HashSet;
 use std::fmt;
 use std::iter::{self, repeat};
@@ -7,16 +7,19 @@ use std::slice;
 use std::str::FromStr;
 
 use csv;
+use regex::bytes::Regex;
 use rustc_serialize::{Decodable, Decoder};
 
 #[derive(Clone)]
 pub struct SelectColumns {
     selectors: Vec<Selector>,
     invert: bool,
+    allow_empty: bool,
+    ignore_case: bool,
 }
 
 impl SelectColumns {
-    fn parse(mut s: &str) -> Result<SelectColumns, String> {
+    pub fn parse(mut s: &str) -> Result<SelectColumns, String> {
         let invert =
             if !s.is_empty() && s.as_bytes()[0] == b'!' {
                 s = &s[1..];
@@ -27,9 +30,54 @@ impl SelectColumns {
         Ok(SelectColumns {
             selectors: SelectorParser::new(s).parse()?,
             invert: invert,
+            allow_empty: false,
+            ignore_case: false,
         })
     }
 
+    /// Whether a regex that doesn't match any columns is allowed. By
+    /// default, it is an error.
+    pub fn allow_empty(mut self, yes: bool) -> SelectColumns {
+        self.allow_empty = yes;
+        self
+    }
+
+    /// Whether to select the columns that aren't selected, as with a
+    /// leading `!`.
+    pub fn invert(mut self, yes: bool) -> SelectColumns {
+        if yes {
+            self.invert = !self.invert;
+        }
+        self
+    }
+
+    /// Whether names match headers case insensitively.
+    pub fn ignore_case(mut self, yes: bool) -> SelectColumns {
+        self.ignore_case = yes;
+        self
+    }
+
+    /// Returns true if no columns were given, i.e., all columns are selected.
+    pub fn is_all(&self) -> bool {
+        self.selectors.is_empty() && !self.invert
+    }
+
+    /// Returns the largest (1-based) index given by number, or 0 if there is
+    /// none.
+    pub fn max_index(&self) -> usize {
+        let index = |sel: &OneSelector| match *sel {
+            OneSelector::Index(i) => i,
+            _ => 0,
+        };
+        self.selectors.iter().map(|sel| match *sel {
+            Selector::One(ref sel) => index(sel),
+            Selector::Range(ref sel1, ref sel2) => {
+                cmp::max(index(sel1), index(sel2))
+            }
+            Selector::Regex(_) | Selector::Rest => 0,
+        }).max().unwrap_or(0)
+    }
+
     pub fn selection(
         &self,
         first_record: &csv::ByteRecord,
@@ -45,9 +93,25 @@ impl SelectColumns {
         }
 
         let mut map = vec![];
+        let mut rest = None;
         for sel in &self.selectors {
-            let idxs = sel.indices(first_record, use_names);
-            map.extend(idxs?.into_iter());
+            if let Selector::Rest = *sel {
+                rest = Some(map.len());
+                continue;
+            }
+            let idxs = sel.indices(first_record, use_names, self.ignore_case)?;
+            if idxs.is_empty() && !self.allow_empty {
+                return Err(format!("Selector {:?} does not match any \
+                                    columns in the given CSV data.", sel));
+            }
+            map.extend(idxs.into_iter());
+        }
+        if let Some(pos) = rest {
+            let named: HashSet<_> = map.iter().cloned().collect();
+            let others: Vec<usize> = (0..first_record.len())
+                .filter(|i| !named.contains(i))
+                .collect();
+            map.splice(pos..pos, others);
         }
         if self.invert {
             let set: HashSet<_> = map.into_iter().collect();
@@ -99,8 +163,45 @@ impl SelectorParser {
             if self.cur().is_none() {
                 break;
             }
+            if self.cur() == Some('*') {
+                self.bump();
+                if !self.is_end_of_selector() {
+                    return Err(format!(
+                        "Expected end of field after '*' but got '{}' \
+                         instead.", self.cur().unwrap()));
+                }
+                if sels.iter().any(|sel| match *sel {
+                    Selector::Rest => true,
+                    _ => false,
+                }) {
+                    return Err("'*' can only be used once in a selection."
+                               .to_owned());
+                }
+                sels.push(Selector::Rest);
+                self.bump();
+                continue;
+            }
+            if self.cur() == Some('/') {
+                let re = self.parse_regex()?;
+                if !self.is_end_of_selector() {
+                    return Err(format!(
+                        "Expected end of field after regex but got '{}' \
+                         instead. (Regexes cannot be used in ranges.)",
+                        self.cur().unwrap()));
+                }
+                sels.push(Selector::Regex(re));
+                self.bump();
+                continue;
+            }
+            // A leading '-' followed by digits is a negative index, like
+            // '-3' or '-3--1'. Otherwise, it's the start of a range with no
+            // start, like '-h3'.
             let f1: OneSelector =
-                if self.cur() == Some('-') {
+                if let Some(i) = self.parse_from_end(Some('-')) {
+                    OneSelector::FromEnd(i)
+                } else if let Some(i) = self.parse_from_end(None) {
+                    OneSelector::FromEnd(i)
+                } else if self.cur() == Some('-') {
                     OneSelector::Start
                 } else {
                     self.parse_one()?
@@ -110,6 +211,8 @@ impl SelectorParser {
                     self.bump();
                     Some(if self.is_end_of_selector() {
                         OneSelector::End
+                    } else if let Some(i) = self.parse_from_end(None) {
+                        OneSelector::FromEnd(i)
                     } else {
                         self.parse_one()?
                     })
@@ -149,6 +252,29 @@ impl SelectorParser {
         })
     }
 
+    /// Parses a negative index like `-2` if there is one, followed by
+    /// `next` (or by the end of the selector when `next` is `None`).
+    fn parse_from_end(&mut self, next: Option<char>) -> Option<usize> {
+        if self.cur() != Some('-') {
+            return None;
+        }
+        let digits: String = self.chars[self.pos+1..]
+            .iter()
+            .take_while(|c| c.is_digit(10))
+            .cloned()
+            .collect();
+        let end = self.pos + 1 + digits.len();
+        let followed = match next {
+            None => self.chars.get(end).map_or(true, |&c| c == ','),
+            Some(next) => self.chars.get(end) == Some(&next),
+        };
+        if digits.is_empty() || !followed {
+            return None;
+        }
+        self.pos = end;
+        digits.parse().ok()
+    }
+
     fn parse_name(&mut self) -> Result<String, String> {
         let mut name = String::new();
         loop {
@@ -184,6 +310,35 @@ impl SelectorParser {
         Ok(name)
     }
 
+    fn parse_regex(&mut self) -> Result<Regex, String> {
+        assert_eq!(self.cur().unwrap(), '/');
+        self.bump();
+
+        let mut re = String::new();
+        loop {
+            match self.cur() {
+                None => {
+                    return Err("Unclosed regex, missing closing /."
+                               .to_owned());
+                }
+                Some('/') => { self.bump(); break; }
+                Some('\\') => {
+                    self.bump();
+                    match self.cur() {
+                        Some('/') => re.push('/'),
+                        Some(c) => { re.push('\\'); re.push(c); }
+                        None => re.push('\\'),
+                    }
+                    self.bump();
+                }
+                Some(c) => { re.push(c); self.bump(); }
+            }
+        }
+        Regex::new(&re).map_err(|err| {
+            format!("Could not compile regex '{}': {}", re, err)
+        })
+    }
+
     fn parse_index(&mut self) -> Result<usize, String> {
         assert_eq!(self.cur().unwrap(), '[');
         self.bump();
@@ -225,6 +380,9 @@ impl SelectorParser {
 enum Selector {
     One(OneSelector),
     Range(OneSelector, OneSelector),
+    Regex(Regex),
+    /// Every column that isn't selected by the other selectors.
+    Rest,
 }
 
 #[derive(Clone)]
@@ -232,6 +390,8 @@ enum OneSelector {
     Start,
     End,
     Index(usize),
+    /// An index from the end, where 1 is the last column.
+    FromEnd(usize),
     IndexedName(String, usize),
 }
 
@@ -240,14 +400,15 @@ impl Selector {
         &self,
         first_record: &csv::ByteRecord,
         use_names: bool,
+        ignore_case: bool,
     ) -> Result<Vec<usize>, String> {
         match *self {
             Selector::One(ref sel) => {
-                sel.index(first_record, use_names).map(|i| vec![i])
+                sel.index(first_record, use_names, ignore_case).map(|i| vec![i])
             }
             Selector::Range(ref sel1, ref sel2) => {
-                let i1 = sel1.index(first_record, use_names)?;
-                let i2 = sel2.index(first_record, use_names)?;
+                let i1 = sel1.index(first_record, use_names, ignore_case)?;
+                let i2 = sel2.index(first_record, use_names, ignore_case)?;
                 Ok(match i1.cmp(&i2) {
                     Ordering::Equal => vec!(i1),
                     Ordering::Less => (i1..(i2 + 1)).collect(),
@@ -262,6 +423,18 @@ impl Selector {
                     }
                 })
             }
+            Selector::Regex(ref re) => {
+                if !use_names {
+                    return Err(format!("Cannot use regexes ('/{}/') in \
+                                        selection with --no-headers set.",
+                                       re));
+                }
+                Ok(first_record.iter().enumerate()
+                               .filter(|&(_, field)| re.is_match(field))
+                               .map(|(i, _)| i)
+                               .collect())
+            }
+            Selector::Rest => unreachable!(),
         }
     }
 }
@@ -271,6 +444,7 @@ impl OneSelector {
         &self,
         first_record: &csv::ByteRecord,
         use_names: bool,
+        ignore_case: bool,
     ) -> Result<usize, String> {
         match *self {
             OneSelector::Start => Ok(0),
@@ -291,14 +465,32 @@ impl OneSelector {
                     Ok(i-1)
                 }
             }
+            OneSelector::FromEnd(i) => {
+                if i < 1 || i > first_record.len() {
+                    Err(format!("Selector index -{} is out of bounds. \
+                                 Negative indices must be >= -{} and \
+                                 <= -1.", i, first_record.len()))
+                } else {
+                    Ok(first_record.len() - i)
+                }
+            }
             OneSelector::IndexedName(ref s, sidx) => {
                 if !use_names {
                     return Err(format!("Cannot use names ('{}') in selection \
                                         with --no-headers set.", s));
                 }
+                let name =
+                    if ignore_case { s.to_lowercase() } else { s.clone() };
                 let mut num_found = 0;
                 for (i, field) in first_record.iter().enumerate() {
-                    if field == s.as_bytes() {
+                    let found =
+                        if ignore_case {
+                            String::from_utf8_lossy(field).to_lowercase()
+                            == name
+                        } else {
+                            field == name.as_bytes()
+                        };
+                    if found {
                         if num_found == sidx {
                             return Ok(i);
                         }
@@ -325,6 +517,8 @@ impl fmt::Debug for Selector {
             Selector::One(ref sel) => sel.fmt(f),
             Selector::Range(ref s, ref e) =>
                 write!(f, "Range({:?}, {:?})", s, e),
+            Selector::Regex(ref re) => write!(f, "/{}/", re),
+            Selector::Rest => write!(f, "*"),
         }
     }
 }
@@ -335,6 +529,7 @@ impl fmt::Debug for OneSelector {
             OneSelector::Start => write!(f, "Start"),
             OneSelector::End => write!(f, "End"),
             OneSelector::Index(idx) => write!(f, "Index({})", idx),
+            OneSelector::FromEnd(idx) => write!(f, "Index(-{})", idx),
             OneSelector::IndexedName(ref s, idx) =>
                 write!(f, "IndexedName({}[{}])", s, idx),
         }
@@ -1,8 +1,9 @@
 use std::borrow::Cow;
 use std::fs;
-use std::io;
+use std::io::{self, BufRead, Read, Write};
 use std::path::{Path, PathBuf};
-use std::str;
+use std::str::{self, FromStr};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time;
 
@@ -18,6 +19,11 @@ pub fn num_cpus() -> usize {
     num_cpus::get()
 }
 
+/// Resolve a `--jobs` flag, where `0` means the number of CPUs detected.
+pub fn njobs(flag_jobs: usize) -> usize {
+    if flag_jobs == 0 { num_cpus() } else { flag_jobs }
+}
+
 pub fn version() -> String {
     let (maj, min, pat) = (
         option_env!("CARGO_PKG_VERSION_MAJOR"),
@@ -113,6 +119,51 @@ pub fn condense<'a>(val: Cow<'a, [u8]>, n: Option<usize>) -> Cow<'a, [u8]> {
     }
 }
 
+/// Returns a canonical form of `s` if it's a number, so that numbers with the
+/// same value have the same form, e.g., `+007`, `7.0` and `7e0` are all `7`,
+/// `-0` is `0` and `1e999` is `inf`. NaN isn't a number here, since it isn't
+/// equal to anything.
+pub fn normalize_number(s: &str) -> Option<String> {
+    if let Ok(n) = s.parse::<i64>() {
+        return Some(n.to_string());
+    }
+    match s.parse::<f64>() {
+        Ok(n) if n.is_nan() => None,
+        Ok(n) if n == 0.0 => Some("0".to_owned()),
+        Ok(n) => Some(n.to_string()),
+        Err(_) => None,
+    }
+}
+
+/// Reads a file of patterns, one on each line, for commands like `search`.
+///
+/// Empty lines and lines starting with `#` are skipped. Each pattern is
+/// passed to `check`, and its error is returned with the line it's on. It's
+/// an error if the file has no patterns at all.
+pub fn read_patterns<F>(path: &str, mut check: F) -> CliResult<Vec<String>>
+        where F: FnMut(&str) -> Result<(), String> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) => return fail!(format!("{}: {}", path, err)),
+    };
+    let mut patterns = vec![];
+    for (i, line) in io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Err(err) = check(line) {
+            return fail!(format!("{}: line {}: {}", path, i + 1, err));
+        }
+        patterns.push(line.to_owned());
+    }
+    if patterns.is_empty() {
+        return fail!(format!("{}: no patterns found", path));
+    }
+    Ok(patterns)
+}
+
 pub fn idx_path(csv_path: &Path) -> PathBuf {
     let mut p = csv_path.to_path_buf().into_os_string().into_string().unwrap();
     p.push_str(".idx");
@@ -189,6 +240,34 @@ impl FilenameTemplate {
     pub fn writer<P>(&self, path: P, unique_value: &str)
                  -> io::Result<csv::Writer<Box<io::Write+'static>>>
         where P: AsRef<Path>
+    {
+        let full_path = self.create_path(path, unique_value)?;
+        let spath = Some(full_path.display().to_string());
+        Config::new(&spath).writer()
+    }
+
+    /// Like `writer`, but appends to the file instead of truncating it
+    /// if it already exists.
+    pub fn append_writer<P>(&self, path: P, unique_value: &str)
+                        -> io::Result<csv::Writer<Box<io::Write+'static>>>
+        where P: AsRef<Path>
+    {
+        let full_path = self.create_path(path, unique_value)?;
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&full_path)?;
+        let spath = Some(full_path.display().to_string());
+        Ok(Config::new(&spath).from_writer(Box::new(file)))
+    }
+
+    /// Return the path of a file in directory `path` with a filename using
+    /// `unique_value` to replace the `"{}"` in the template.  Any
+    /// directories named by the template are created, but the file itself
+    /// is not.
+    pub fn create_path<P>(&self, path: P, unique_value: &str)
+                      -> io::Result<PathBuf>
+        where P: AsRef<Path>
     {
         let filename = self.filename(unique_value);
         let full_path = path.as_ref().join(filename);
@@ -198,22 +277,292 @@ impl FilenameTemplate {
             // condition.
             create_dir_all_threadsafe(parent)?;
         }
-        let spath = Some(full_path.display().to_string());
-        Config::new(&spath).writer()
+        Ok(full_path)
     }
 }
 
-impl Decodable for FilenameTemplate {
-    fn decode<D: Decoder>(d: &mut D) -> Result<FilenameTemplate, D::Error> {
-        let raw = d.read_str()?;
+impl FromStr for FilenameTemplate {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<FilenameTemplate, String> {
         let chunks = raw.split("{}").collect::<Vec<_>>();
-        if chunks.len() == 2 {
-            Ok(FilenameTemplate {
+        match chunks.len() {
+            2 => Ok(FilenameTemplate {
                 prefix: chunks[0].to_owned(),
                 suffix: chunks[1].to_owned(),
-            })
-        } else {
-            Err(d.error("The --filename argument must contain one '{}'."))
+            }),
+            1 => Err("The --filename argument must contain one '{}', \
+                      otherwise every output file would get the same \
+                      name.".to_owned()),
+            _ => Err("The --filename argument must contain exactly \
+                      one '{}'.".to_owned()),
+        }
+    }
+}
+
+impl Decodable for FilenameTemplate {
+    fn decode<D: Decoder>(d: &mut D) -> Result<FilenameTemplate, D::Error> {
+        let raw = d.read_str()?;
+        raw.parse().map_err(|err: String| d.error(&*err))
+    }
+}
+
+/// A number of bytes given on the command line, e.g., `"500"`, `"10MiB"` or
+/// `"500k"`.
+///
+/// Suffixes follow the conventions of GNU `split`: `K`, `M` and `G` (and
+/// their `KiB`, `MiB` and `GiB` spellings) are powers of 1024, whereas `KB`,
+/// `MB` and `GB` are powers of 1000. Suffixes are case insensitive.
+#[derive(Clone, Copy, Debug)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl Decodable for ByteSize {
+    fn decode<D: Decoder>(d: &mut D) -> Result<ByteSize, D::Error> {
+        let raw = d.read_str()?;
+        let split = raw.find(|c: char| !c.is_digit(10)).unwrap_or(raw.len());
+        let (digits, suffix) = raw.split_at(split);
+        let multiplier = match &*suffix.to_lowercase() {
+            "" | "b" => 1,
+            "k" | "kib" => 1 << 10,
+            "m" | "mib" => 1 << 20,
+            "g" | "gib" => 1 << 30,
+            "kb" => 1_000,
+            "mb" => 1_000_000,
+            "gb" => 1_000_000_000,
+            _ => {
+                let msg = format!("Could not parse '{}' as a size in bytes \
+                                   (unknown suffix '{}').", raw, suffix);
+                return Err(d.error(&*msg));
+            }
+        };
+        let n = match digits.parse::<u64>() {
+            Ok(n) => n,
+            Err(_) => {
+                let msg = format!("Could not parse '{}' as a size \
+                                   in bytes.", raw);
+                return Err(d.error(&*msg));
+            }
+        };
+        match n.checked_mul(multiplier) {
+            Some(n) => Ok(ByteSize(n)),
+            None => {
+                let msg = format!("The size '{}' is too large.", raw);
+                Err(d.error(&*msg))
+            }
+        }
+    }
+}
+
+/// The values of fields that are treated as NULL, in addition to empty fields.
+///
+/// Values given with `--null-value` match fields exactly, unless
+/// `ignore_case` is set. Fields that only contain whitespace are NULL too if
+/// `whitespace` is set.
+#[derive(Clone, Debug)]
+pub struct NullValues {
+    values: Vec<Vec<u8>>,
+    ignore_case: bool,
+    whitespace: bool,
+}
+
+impl NullValues {
+    pub fn new(values: &[String], ignore_case: bool) -> NullValues {
+        let values = values.iter().map(|v| {
+            if ignore_case { v.to_lowercase() } else { v.clone() }
+        });
+        NullValues {
+            values: values.map(String::into_bytes).collect(),
+            ignore_case: ignore_case,
+            whitespace: false,
+        }
+    }
+
+    pub fn whitespace(mut self, yes: bool) -> NullValues {
+        self.whitespace = yes;
+        self
+    }
+
+    pub fn is_null(&self, field: &[u8]) -> bool {
+        if field.is_empty() {
+            return true;
+        }
+        if self.whitespace
+                && str::from_utf8(field).map_or(false, |s| s.trim().is_empty()) {
+            return true;
         }
+        if !self.ignore_case {
+            return self.values.iter().any(|v| &**v == field);
+        }
+        let field = String::from_utf8_lossy(field).to_lowercase();
+        self.values.iter().any(|v| &**v == field.as_bytes())
     }
 }
+
+/// Progress draws a progress bar on stderr for commands that take a while.
+///
+/// Progress is counted in records or in bytes read. Clones share the same
+/// bar, so it can be updated from several threads. Nothing is drawn unless
+/// it's enabled and stderr is a terminal, so it never mixes with output
+/// that's piped or redirected.
+#[derive(Clone)]
+pub struct Progress(Option<Arc<Mutex<ProgressBar>>>);
+
+struct ProgressBar {
+    total: Option<u64>,
+    unit: &'static str,
+    done: u64,
+    drawn_at: Option<time::Instant>,
+}
+
+impl Progress {
+    pub fn new(
+        enabled: bool,
+        total: Option<u64>,
+        unit: &'static str,
+    ) -> Progress {
+        if !enabled || !stderr_is_tty() {
+            return Progress(None);
+        }
+        Progress(Some(Arc::new(Mutex::new(ProgressBar {
+            total: total,
+            unit: unit,
+            done: 0,
+            drawn_at: None,
+        }))))
+    }
+
+    /// A progress bar for reading `conf`, counted in records if `count` is
+    /// known (e.g., from an index) and in bytes otherwise.
+    pub fn for_input(
+        enabled: bool,
+        conf: &Config,
+        count: Option<u64>,
+    ) -> Progress {
+        match count {
+            Some(count) => Progress::new(enabled, Some(count), "records"),
+            None => Progress::new(enabled, conf.input_len(), "bytes"),
+        }
+    }
+
+    /// Adds `n` records or bytes, and redraws the bar now and then.
+    pub fn add(&self, n: u64) {
+        if let Some(ref bar) = self.0 {
+            let mut bar = bar.lock().unwrap();
+            bar.done += n;
+            let due = bar.drawn_at.map_or(true, |at| {
+                at.elapsed() >= time::Duration::from_millis(100)
+            });
+            if due {
+                bar.draw();
+            }
+        }
+    }
+
+    /// Draws the bar one last time and ends its line.
+    pub fn finish(&self) {
+        if let Some(ref bar) = self.0 {
+            let mut bar = bar.lock().unwrap();
+            if let Some(total) = bar.total {
+                bar.done = total;
+            }
+            bar.draw();
+            let _ = writeln!(&mut io::stderr());
+        }
+    }
+
+    /// Wraps `rdr` so that every byte read from it is counted.
+    pub fn reader<R: Read>(&self, rdr: R) -> ProgressReader<R> {
+        ProgressReader { inner: rdr, progress: self.clone() }
+    }
+}
+
+impl ProgressBar {
+    fn draw(&mut self) {
+        const WIDTH: u64 = 40;
+        let line = match self.total {
+            Some(total) if total > 0 => {
+                let done = ::std::cmp::min(self.done, total);
+                let filled = (done * WIDTH / total) as usize;
+                format!("\r[{}{}] {:3}% ({} of {} {})",
+                        "#".repeat(filled),
+                        " ".repeat(WIDTH as usize - filled),
+                        done * 100 / total, done, total, self.unit)
+            }
+            _ => format!("\r{} {}", self.done, self.unit),
+        };
+        let _ = io::stderr().write_all(line.as_bytes());
+        self.drawn_at = Some(time::Instant::now());
+    }
+}
+
+pub struct ProgressReader<R> {
+    inner: R,
+    progress: Progress,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.progress.add(n as u64);
+        Ok(n)
+    }
+}
+
+/// Recorder keeps a copy of every byte read from `inner`.
+///
+/// It's only used to read the header row with a CSV reader, so it never
+/// grows much bigger than the CSV reader's buffer. The rest of the input can
+/// then be copied as is with `copy_from`.
+pub struct Recorder<R> {
+    inner: R,
+    seen: Vec<u8>,
+}
+
+impl<R: Read> Recorder<R> {
+    pub fn new(inner: R) -> Recorder<R> {
+        Recorder { inner: inner, seen: vec![] }
+    }
+
+    /// Writes all of the input from the byte offset `start` to `wtr`.
+    pub fn copy_from<W: Write>(self, mut start: usize, wtr: &mut W)
+                              -> io::Result<()> {
+        let Recorder { mut inner, seen } = self;
+        // The position may be in the middle of a `\r\n` line terminator.
+        if start > 0 && seen.get(start - 1) == Some(&b'\r')
+                && seen.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+        // The CSV reader has already read past `start`, so write what it has
+        // read before copying the rest of the input.
+        if start < seen.len() {
+            wtr.write_all(&seen[start..])?;
+        }
+        io::copy(&mut inner, wtr)?;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.seen.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(unix)]
+fn stderr_is_tty() -> bool {
+    use libc;
+    unsafe { libc::isatty(libc::STDERR_FILENO) == 1 }
+}
+
+#[cfg(not(unix))]
+fn stderr_is_tty() -> bool {
+    false
+}
@@ -0,0 +1,175 @@
+use workdir::Workdir;
+
+fn apply(name: &str, args: &[&str]) -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", vec![
+        svec!["name", "other"],
+        svec!["  Ann  Lee ", " X "],
+        svec!["élodie", "y"],
+    ]);
+    let mut cmd = wrk.command("apply");
+    cmd.args(args).arg("in.csv");
+    wrk.read_stdout(&mut cmd)
+}
+
+fn applied_names(name: &str, op: &str) -> Vec<String> {
+    apply(name, &[op, "name"]).into_iter().skip(1).map(|r| {
+        assert_eq!(r.len(), 2);
+        r[0].clone()
+    }).collect()
+}
+
+#[test]
+fn apply_upper() {
+    assert_eq!(applied_names("apply_upper", "upper"),
+               svec!["  ANN  LEE ", "ÉLODIE"]);
+}
+
+#[test]
+fn apply_lower() {
+    assert_eq!(applied_names("apply_lower", "lower"),
+               svec!["  ann  lee ", "élodie"]);
+}
+
+#[test]
+fn apply_trim() {
+    assert_eq!(applied_names("apply_trim", "trim"),
+               svec!["Ann  Lee", "élodie"]);
+}
+
+#[test]
+fn apply_ltrim() {
+    assert_eq!(applied_names("apply_ltrim", "ltrim"),
+               svec!["Ann  Lee ", "élodie"]);
+}
+
+#[test]
+fn apply_rtrim() {
+    assert_eq!(applied_names("apply_rtrim", "rtrim"),
+               svec!["  Ann  Lee", "élodie"]);
+}
+
+#[test]
+fn apply_squeeze() {
+    assert_eq!(applied_names("apply_squeeze", "squeeze"),
+               svec![" Ann Lee ", "élodie"]);
+}
+
+#[test]
+fn apply_len() {
+    assert_eq!(applied_names("apply_len", "len"), svec!["11", "6"]);
+}
+
+#[test]
+fn apply_chain() {
+    let got = apply("apply_chain", &["trim,lower", "name,other"]);
+    let expected = vec![
+        svec!["name", "other"],
+        svec!["ann  lee", "x"],
+        svec!["élodie", "y"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn apply_new_column() {
+    let got = apply("apply_new_column",
+                    &["--new-column", "len", "trim,len", "name"]);
+    let expected = vec![
+        svec!["name", "other", "len"],
+        svec!["  Ann  Lee ", " X ", "8"],
+        svec!["élodie", "y", "6"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn apply_new_column_many_columns() {
+    let wrk = Workdir::new("apply_new_column_many_columns");
+    wrk.create("in.csv", vec![svec!["a", "b"]]);
+    let mut cmd = wrk.command("apply");
+    cmd.args(&["--new-column", "c", "len", "a,b", "in.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn apply_unknown_operation() {
+    let wrk = Workdir::new("apply_unknown_operation");
+    wrk.create("in.csv", vec![svec!["a", "b"]]);
+    let mut cmd = wrk.command("apply");
+    cmd.args(&["trim,reverse", "a", "in.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+fn datefmt(name: &str, args: &[&str]) -> (Workdir, ::std::process::Command) {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", vec![
+        svec!["date"],
+        svec!["2017-03-04"],
+        svec!["04/03/2017 13:30"],
+        svec!["March 4, 2017"],
+        svec![""],
+        svec!["2017-03-04T10:00:00+0200"],
+    ]);
+    let mut cmd = wrk.command("apply");
+    cmd.args(&["datefmt", "date", "in.csv"])
+       .args(&["--formats", "%Y-%m-%dT%H:%M:%S%z"])
+       .args(&["--formats", "%Y-%m-%d"])
+       .args(&["--formats", "%d/%m/%Y %H:%M"])
+       .args(args);
+    (wrk, cmd)
+}
+
+#[test]
+fn apply_datefmt_formats() {
+    let (wrk, mut cmd) = datefmt("apply_datefmt_formats", &[
+        "--output-format", "%Y-%m-%d %H:%M",
+    ]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["date"],
+        svec!["2017-03-04 00:00"],
+        svec!["2017-03-04 13:30"],
+        svec!["March 4, 2017"],
+        svec![""],
+        svec!["2017-03-04 08:00"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn apply_datefmt_timezone() {
+    let (wrk, mut cmd) = datefmt("apply_datefmt_timezone", &[
+        "--timezone", "-05:00",
+    ]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1], svec!["2017-03-04T05:00:00Z"]);
+    assert_eq!(got[2], svec!["2017-03-04T18:30:00Z"]);
+    assert_eq!(got[5], svec!["2017-03-04T08:00:00Z"]);
+}
+
+#[test]
+fn apply_datefmt_strict() {
+    let (wrk, mut cmd) = datefmt("apply_datefmt_strict", &["--strict"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn apply_datefmt_strict_all_parsed() {
+    let (wrk, mut cmd) = datefmt("apply_datefmt_strict_all_parsed", &[
+        "--strict", "--formats", "%B %d, %Y", "--output-format", "%d.%m.%Y",
+    ]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["date"],
+        svec!["04.03.2017"],
+        svec!["04.03.2017"],
+        svec!["04.03.2017"],
+        svec![""],
+        svec!["04.03.2017"],
+    ];
+    assert_eq!(got, expected);
+}
@@ -0,0 +1,47 @@
+use std::fs;
+use std::io::Write;
+use std::process;
+
+use workdir::Workdir;
+
+fn behead(name: &str, data: &str, args: &[&str]) -> String {
+    let wrk = Workdir::new(name);
+    fs::File::create(wrk.path("in.csv")).unwrap()
+        .write_all(data.as_bytes()).unwrap();
+    let mut cmd = wrk.command("behead");
+    cmd.args(args).arg("in.csv");
+    let o = wrk.output(&mut cmd);
+    String::from_utf8(o.stdout).unwrap()
+}
+
+#[test]
+fn behead_multiline() {
+    let got = behead("behead_multiline",
+                     "h1,\"h\n2\"\na,\"b\nc\"\nd,e\n", &[]);
+    assert_eq!(got, "a,\"b\nc\"\nd,e\n");
+}
+
+#[test]
+fn behead_preserves_quoting() {
+    let got = behead("behead_preserves_quoting",
+                     "h1;h2\r\n\"a\";b\r\n\"c;d\";\"\"\r\n",
+                     &["--delimiter", ";"]);
+    assert_eq!(got, "\"a\";b\r\n\"c;d\";\"\"\r\n");
+}
+
+#[test]
+fn behead_only_headers() {
+    let got = behead("behead_only_headers", "h1,h2", &[]);
+    assert_eq!(got, "");
+}
+
+#[test]
+fn behead_stdin() {
+    let wrk = Workdir::new("behead_stdin");
+    let mut cmd = wrk.command("behead");
+    cmd.stdin(process::Stdio::piped()).stdout(process::Stdio::piped());
+    let mut child = cmd.spawn().unwrap();
+    child.stdin.take().unwrap().write_all(b"h\n1\n2\n").unwrap();
+    let o = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8(o.stdout).unwrap(), "1\n2\n");
+}
@@ -128,3 +128,65 @@ fn cat_cols_pad() {
                                         rows1, rows2, pad);
     assert_eq!(got, expected);
 }
+
+#[test]
+fn cat_rows_headers_mismatch() {
+    let wrk = Workdir::new("cat_rows_headers_mismatch");
+    wrk.create("in1.csv", vec![svec!["h1", "h2"], svec!["a", "b"]]);
+    wrk.create("in2.csv", vec![svec!["h1", "h3"], svec!["y", "z"]]);
+
+    // Without --check-headers, the later headers are ignored.
+    let mut cmd = wrk.command("cat");
+    cmd.arg("rows").arg("in1.csv").arg("in2.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "h1,h2\na,b\ny,z");
+
+    let mut cmd = wrk.command("cat");
+    cmd.arg("rows").arg("--check-headers").arg("in1.csv").arg("in2.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn cat_rows_flexible() {
+    let wrk = Workdir::new("cat_rows_flexible");
+    wrk.create("in1.csv", vec![svec!["h1", "h2"], svec!["a", "b"]]);
+    wrk.create("in2.csv", vec![svec!["h1", "h2", "h3"], svec!["x", "y", "z"]]);
+
+    let mut cmd = wrk.command("cat");
+    cmd.arg("rows").arg("--flexible").arg("in1.csv").arg("in2.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "h1,h2\na,b\nx,y,z");
+}
+
+#[test]
+fn cat_rows_group() {
+    let wrk = Workdir::new("cat_rows_group");
+    wrk.create("in1.csv", vec![svec!["h1", "h2"], svec!["a", "b"]]);
+    wrk.create("in2.csv", vec![svec!["h1", "h2"], svec!["y", "z"]]);
+
+    let mut cmd = wrk.command("cat");
+    cmd.arg("rows").args(&["--group", "file"]).arg("in1.csv").arg("in2.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["file", "h1", "h2"],
+        svec!["in1.csv", "a", "b"],
+        svec!["in2.csv", "y", "z"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn cat_cols_pad_headers() {
+    let rows1 = vec![svec!["h1", "h2"], svec!["a", "b"]];
+    let rows2 = vec![svec!["h3"], svec!["x"], svec!["y"], svec!["z"]];
+
+    let expected = vec![
+        svec!["h1", "h2", "h3"],
+        svec!["a", "b", "x"],
+        svec!["", "", "y"],
+        svec!["", "", "z"],
+    ];
+    let got: Vec<Vec<String>> = run_cat("cat_cols_pad_headers", "columns",
+                                        rows1, rows2, pad);
+    assert_eq!(got, expected);
+}
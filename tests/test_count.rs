@@ -60,3 +60,43 @@ fn prop_count_indexed_headers() {
     }
     qcheck(p as fn(CsvData) -> bool);
 }
+
+#[test]
+fn count_indexed_agrees() {
+    let wrk = Workdir::new("count_indexed_agrees");
+    let rows: Vec<Vec<String>> =
+        (0..1500).map(|i| vec![i.to_string(), "a,\"b\"".to_owned()]).collect();
+    wrk.create("in.csv", rows);
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("in.csv");
+    let streamed: usize = wrk.stdout(&mut cmd);
+
+    let mut cmd = wrk.command("index");
+    cmd.arg("in.csv");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("in.csv");
+    let indexed: usize = wrk.stdout(&mut cmd);
+    assert_eq!(streamed, 1499);
+    assert_eq!(streamed, indexed);
+}
+
+#[test]
+fn count_human() {
+    let wrk = Workdir::new("count_human");
+    let rows: Vec<Vec<String>> =
+        (0..1235).map(|i| vec![i.to_string()]).collect();
+    wrk.create("in.csv", rows);
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("--human").arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "1,234");
+
+    let mut cmd = wrk.command("count");
+    cmd.args(&["--human", "--no-headers"]).arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "1,235");
+}
@@ -0,0 +1,157 @@
+use workdir::Workdir;
+
+fn data(headers: bool) -> Vec<Vec<String>> {
+    let mut rows = vec![
+        svec!["b", "1"],
+        svec!["a", "2"],
+        svec!["b", "1"],
+        svec!["a", "3"],
+        svec!["b", "1.0"],
+    ];
+    if headers { rows.insert(0, svec!["letter", "number"]); }
+    rows
+}
+
+#[test]
+fn dedup() {
+    let wrk = Workdir::new("dedup");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("dedup");
+    cmd.arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["letter", "number"],
+        svec!["b", "1"],
+        svec!["a", "2"],
+        svec!["a", "3"],
+        svec!["b", "1.0"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn dedup_no_headers() {
+    let wrk = Workdir::new("dedup_no_headers");
+    let mut rows = data(false);
+    rows.insert(0, svec!["a", "2"]);
+    wrk.create("in.csv", rows);
+
+    let mut cmd = wrk.command("dedup");
+    cmd.arg("--no-headers").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["a", "2"],
+        svec!["b", "1"],
+        svec!["a", "3"],
+        svec!["b", "1.0"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn dedup_select() {
+    let wrk = Workdir::new("dedup_select");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("dedup");
+    cmd.args(&["--select", "letter"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["letter", "number"],
+        svec!["b", "1"],
+        svec!["a", "2"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn dedup_numeric() {
+    let wrk = Workdir::new("dedup_numeric");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("dedup");
+    cmd.arg("--numeric").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["letter", "number"],
+        svec!["b", "1"],
+        svec!["a", "2"],
+        svec!["a", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn dedup_numeric_special() {
+    let wrk = Workdir::new("dedup_numeric_special");
+    wrk.create("in.csv", vec![
+        svec!["n"], svec!["inf"], svec!["Infinity"], svec!["1e999"],
+        svec!["-0.0"], svec!["0"], svec!["9e15"], svec!["9000000000000000"],
+        svec!["NaN"], svec!["NaN"],
+    ]);
+
+    let mut cmd = wrk.command("dedup");
+    cmd.arg("--numeric").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["n"], svec!["inf"], svec!["-0.0"], svec!["9e15"], svec!["NaN"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn dedup_sorted() {
+    let wrk = Workdir::new("dedup_sorted");
+    wrk.create("in.csv", vec![
+        svec!["letter", "number"],
+        svec!["a", "1"],
+        svec!["a", "1"],
+        svec!["a", "2"],
+        svec!["b", "2"],
+        svec!["b", "2"],
+        // Not adjacent to the first occurrence, so it's kept.
+        svec!["a", "1"],
+    ]);
+
+    let mut cmd = wrk.command("dedup");
+    cmd.arg("--sorted").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["letter", "number"],
+        svec!["a", "1"],
+        svec!["a", "2"],
+        svec!["b", "2"],
+        svec!["a", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn dedup_sorted_select_numeric() {
+    let wrk = Workdir::new("dedup_sorted_select_numeric");
+    wrk.create("in.csv", vec![
+        svec!["n", "name"],
+        svec!["1", "one"],
+        svec!["1.0", "also one"],
+        svec!["01", "still one"],
+        svec!["2", "two"],
+    ]);
+
+    let mut cmd = wrk.command("dedup");
+    cmd.args(&["--sorted", "--numeric", "--select", "n"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["n", "name"],
+        svec!["1", "one"],
+        svec!["2", "two"],
+    ];
+    assert_eq!(got, expected);
+}
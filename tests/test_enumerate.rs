@@ -0,0 +1,98 @@
+use workdir::Workdir;
+
+fn data(headers: bool) -> Vec<Vec<String>> {
+    let mut rows = vec![
+        svec!["a", "b"],
+        svec!["c", "d"],
+    ];
+    if headers { rows.insert(0, svec!["h1", "h2"]); }
+    rows
+}
+
+#[test]
+fn enumerate_counter() {
+    let wrk = Workdir::new("enumerate_counter");
+    wrk.create("in.csv", data(true));
+    let mut cmd = wrk.command("enumerate");
+    cmd.arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["index", "h1", "h2"],
+        svec!["0", "a", "b"],
+        svec!["1", "c", "d"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn enumerate_start_column_name() {
+    let wrk = Workdir::new("enumerate_start_column_name");
+    wrk.create("in.csv", data(true));
+    let mut cmd = wrk.command("enumerate");
+    cmd.args(&["--start", "1", "--column-name", "row"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["row", "h1", "h2"],
+        svec!["1", "a", "b"],
+        svec!["2", "c", "d"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn enumerate_no_headers() {
+    let wrk = Workdir::new("enumerate_no_headers");
+    wrk.create("in.csv", data(false));
+    let mut cmd = wrk.command("enumerate");
+    cmd.arg("--no-headers").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["0", "a", "b"],
+        svec!["1", "c", "d"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn enumerate_constant() {
+    let wrk = Workdir::new("enumerate_constant");
+    wrk.create("in.csv", data(true));
+    let mut cmd = wrk.command("enumerate");
+    cmd.args(&["--constant", "x", "-c", "source"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["source", "h1", "h2"],
+        svec!["x", "a", "b"],
+        svec!["x", "c", "d"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn enumerate_uuid() {
+    let wrk = Workdir::new("enumerate_uuid");
+    wrk.create("in.csv", data(true));
+    let mut cmd = wrk.command("enumerate");
+    cmd.arg("--uuid").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["uuid", "h1", "h2"]);
+    for row in &got[1..] {
+        assert_eq!(row[0].len(), 36);
+        assert_eq!(&row[0][14..15], "4");
+    }
+    assert!(got[1][0] != got[2][0]);
+}
+
+#[test]
+fn enumerate_constant_uuid() {
+    let wrk = Workdir::new("enumerate_constant_uuid");
+    wrk.create("in.csv", data(true));
+    let mut cmd = wrk.command("enumerate");
+    cmd.args(&["--constant", "x", "--uuid"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
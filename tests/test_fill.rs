@@ -0,0 +1,127 @@
+use workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["group", "a", "b"],
+        svec!["x", "1", ""],
+        svec!["x", "", "p"],
+        svec!["x", "", ""],
+        svec!["y", "", ""],
+        svec!["y", "2", "q"],
+        svec!["y", "", ""],
+    ]
+}
+
+#[test]
+fn fill_forward() {
+    let wrk = Workdir::new("fill_forward");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("fill");
+    cmd.arg("a").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["group", "a", "b"],
+        svec!["x", "1", ""],
+        svec!["x", "1", "p"],
+        svec!["x", "1", ""],
+        svec!["y", "1", ""],
+        svec!["y", "2", "q"],
+        svec!["y", "2", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fill_backfill() {
+    let wrk = Workdir::new("fill_backfill");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("fill");
+    cmd.arg("--backfill").arg("a,b").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["group", "a", "b"],
+        svec!["x", "1", "p"],
+        svec!["x", "2", "p"],
+        svec!["x", "2", "q"],
+        svec!["y", "2", "q"],
+        svec!["y", "2", "q"],
+        svec!["y", "", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fill_groupby() {
+    let wrk = Workdir::new("fill_groupby");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("fill");
+    cmd.args(&["--groupby", "group"]).arg("a,b").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["group", "a", "b"],
+        svec!["x", "1", ""],
+        svec!["x", "1", "p"],
+        svec!["x", "1", "p"],
+        svec!["y", "", ""],
+        svec!["y", "2", "q"],
+        svec!["y", "2", "q"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fill_groupby_backfill() {
+    let wrk = Workdir::new("fill_groupby_backfill");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("fill");
+    cmd.args(&["--groupby", "group", "--backfill"])
+       .arg("a,b").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["group", "a", "b"],
+        svec!["x", "1", "p"],
+        svec!["x", "", "p"],
+        svec!["x", "", ""],
+        svec!["y", "2", "q"],
+        svec!["y", "2", "q"],
+        svec!["y", "", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fill_default() {
+    let wrk = Workdir::new("fill_default");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("fill");
+    cmd.args(&["--groupby", "group", "--default", "0"])
+       .arg("b").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["group", "a", "b"],
+        svec!["x", "1", "0"],
+        svec!["x", "", "p"],
+        svec!["x", "", "p"],
+        svec!["y", "", "0"],
+        svec!["y", "2", "q"],
+        svec!["y", "", "q"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fill_no_headers() {
+    let wrk = Workdir::new("fill_no_headers");
+    wrk.create("in.csv", vec![svec!["a", "1"], svec!["", ""]]);
+    let mut cmd = wrk.command("fill");
+    cmd.arg("--no-headers").arg("1").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["a", "1"], svec!["a", ""]];
+    assert_eq!(got, expected);
+}
@@ -91,3 +91,47 @@ fn prop_fixlengths_explicit_len() {
     }
     qcheck(p as fn(Vec<CsvRecord>, usize) -> TestResult);
 }
+
+fn setup_ragged(name: &str) -> Workdir {
+    let rows = vec![
+        svec!["h1", "h2", "h3"],
+        svec!["a"],
+        svec!["b", "c", "", ""],
+        svec!["d", "e", "f", "g"],
+    ];
+    let wrk = Workdir::new(name).flexible(true);
+    wrk.create("in.csv", rows);
+    wrk
+}
+
+#[test]
+fn fixlengths_length_pad_truncate() {
+    let wrk = setup_ragged("fixlengths_length_pad_truncate");
+    let mut cmd = wrk.command("fixlengths");
+    cmd.args(&["--length", "3"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2", "h3"],
+        svec!["a", "", ""],
+        svec!["b", "c", ""],
+        svec!["d", "e", "f"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fixlengths_no_truncate() {
+    let wrk = setup_ragged("fixlengths_no_truncate");
+    let mut cmd = wrk.command("fixlengths");
+    cmd.args(&["--length", "4", "--no-truncate"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1], svec!["a", "", "", ""]);
+
+    let mut cmd = wrk.command("fixlengths");
+    cmd.args(&["--length", "3", "--no-truncate"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("Record 4 has 4 fields"), "{}", stderr);
+}
@@ -82,3 +82,38 @@ h2  st...\
 ";
     assert_eq!(got, expected.to_string());
 }
+
+#[test]
+fn flatten_field_separator() {
+    let (wrk, mut cmd) = setup("flatten_field_separator");
+    cmd.args(&["--field-separator", ": "])
+       .args(&["--record-separator", "--"])
+       .args(&["--condense", "3"]);
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+h1: abc...
+h2: ghi...
+--
+h1: mno...
+h2: stu...\
+";
+    assert_eq!(got, expected.to_string());
+}
+
+#[test]
+fn flatten_field_separator_no_headers() {
+    let (wrk, mut cmd) = setup("flatten_field_separator_no_headers");
+    cmd.args(&["-f", "="]).args(&["-r", ""]).arg("--no-headers");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+0=h1
+1=h2
+0=abcdef
+1=ghijkl
+0=mnopqr
+1=stuvwx\
+";
+    assert_eq!(got, expected.to_string());
+}
@@ -69,3 +69,127 @@ fn fmt_quote_always() {
 \"mnopqr\",\"stuvwx\"";
     assert_eq!(got, expected.to_string());
 }
+
+fn setup_special(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", vec![
+        svec!["h1", "h2"],
+        svec!["a,b", "say \"hi\""],
+        svec!["c\\d", "e"],
+    ]);
+    wrk
+}
+
+#[test]
+fn fmt_quote_never() {
+    let wrk = setup_special("fmt_quote_never");
+    let mut cmd = wrk.command("fmt");
+    cmd.arg("--quote-never").arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+h1,h2
+a,b,say \"hi\"
+c\\d,e";
+    assert_eq!(got, expected.to_string());
+}
+
+#[test]
+fn fmt_quote_never_escape() {
+    let wrk = setup_special("fmt_quote_never_escape");
+    let mut cmd = wrk.command("fmt");
+    cmd.args(&["--quote-never", "--escape", "\\"]).arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+h1,h2
+a\\,b,say \\\"hi\\\"
+c\\\\d,e";
+    assert_eq!(got, expected.to_string());
+}
+
+#[test]
+fn fmt_quote_always_never() {
+    let (wrk, mut cmd) = setup("fmt_quote_always_never");
+    cmd.args(&["--quote-always", "--quote-never"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn fmt_escape_roundtrip() {
+    let wrk = setup_special("fmt_escape_roundtrip");
+    let mut cmd = wrk.command("fmt");
+    cmd.args(&["--quote", "'", "--escape", "#"]).arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "\
+h1,h2
+'a,b',say \"hi\"
+c\\d,e");
+
+    let mut cmd = wrk.command("fmt");
+    cmd.args(&["--quote-always", "--escape", "#"]).arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "\
+\"h1\",\"h2\"
+\"a,b\",\"say #\"hi#\"\"
+\"c\\d\",\"e\"");
+
+    let mut cmd = wrk.command("fmt");
+    cmd.args(&["--quote-always", "--escape", "#"])
+       .args(&["--output", "escaped.csv"]).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("input");
+    cmd.args(&["--escape", "#"]).arg("escaped.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["a,b", "say \"hi\""],
+        svec!["c\\d", "e"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fmt_crlf_embedded_newline() {
+    let wrk = Workdir::new("fmt_crlf_embedded_newline");
+    wrk.create("in.csv", vec![
+        svec!["h1", "h2"],
+        svec!["a\nb", "c"],
+    ]);
+    let mut cmd = wrk.command("fmt");
+    cmd.args(&["--crlf", "--out-delimiter", ";"]).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    let got = cmd.output().unwrap().stdout;
+    assert_eq!(got, b"h1;h2\r\n\"a\nb\";c\r\n".to_vec());
+}
+
+#[test]
+fn fmt_terminator() {
+    let wrk = Workdir::new("fmt_terminator");
+    wrk.create("in.csv", vec![
+        svec!["h1", "h2"],
+        svec!["a\nb", "c|d"],
+    ]);
+    let mut cmd = wrk.command("fmt");
+    cmd.args(&["--terminator", "|", "-t", "\t"]).arg("in.csv");
+    let got = cmd.output().unwrap().stdout;
+    assert_eq!(got, b"h1\th2|a\nb\t\"c|d\"|".to_vec());
+
+    let mut cmd = wrk.command("fmt");
+    cmd.args(&["--terminator", "\\r\\n"]).arg("in.csv");
+    let got = cmd.output().unwrap().stdout;
+    assert_eq!(got, b"h1,h2\r\n\"a\nb\",c|d\r\n".to_vec());
+}
+
+#[test]
+fn fmt_terminator_invalid() {
+    let (wrk, mut cmd) = setup("fmt_terminator_invalid");
+    cmd.args(&["--terminator", "ab"]);
+    wrk.assert_err(&mut cmd);
+
+    let (wrk, mut cmd) = setup("fmt_terminator_crlf");
+    cmd.args(&["--terminator", "|", "--crlf"]);
+    wrk.assert_err(&mut cmd);
+}
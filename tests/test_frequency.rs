@@ -79,6 +79,59 @@ fn frequency_nulls() {
     assert_eq!(got, expected);
 }
 
+#[test]
+fn frequency_null_value() {
+    let (wrk, mut cmd) = setup("frequency_null_value");
+    cmd.args(&["--limit", "0"]).args(&["--select", "h1"])
+       .args(&["--null-value", "b"]);
+
+    let mut got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    got.sort();
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["h1", "(NULL)", "1"],
+        svec!["h1", "(NULL)", "2"],
+        svec!["h1", "a", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_null_value_no_nulls() {
+    let (wrk, mut cmd) = setup("frequency_null_value_no_nulls");
+    cmd.args(&["--limit", "0"]).args(&["--select", "h2"])
+       .args(&["--null-value", "x", "--null-value", "Y"])
+       .arg("--ignore-case").arg("--no-nulls");
+
+    let mut got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    got.sort();
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["h2", "z", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_null_value_sorted_by_combined_count() {
+    let wrk = Workdir::new("frequency_null_value_sorted_by_combined_count");
+    wrk.create("in.csv", vec![
+        svec!["h"], svec!["a"], svec!["a"], svec!["NULL"], svec!["n/a"],
+        svec![""], svec!["N/A"],
+    ]);
+    let mut cmd = wrk.command("frequency");
+    cmd.args(&["--null-value", "NULL", "--null-value", "n/a"])
+       .arg("--ignore-case").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["h", "(NULL)", "4"],
+        svec!["h", "a", "2"],
+    ];
+    assert_eq!(got, expected);
+}
+
 #[test]
 fn frequency_limit() {
     let (wrk, mut cmd) = setup("frequency_limit");
@@ -108,6 +161,436 @@ fn frequency_asc() {
     assert_eq!(got, expected);
 }
 
+#[test]
+fn frequency_limit_ties() {
+    let (wrk, mut cmd) = setup("frequency_limit_ties");
+    cmd.args(&["--limit", "2"]).args(&["--select", "h1"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["h1", "a", "3"],
+        svec!["h1", "(NULL)", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_asc_ties() {
+    let (wrk, mut cmd) = setup("frequency_asc_ties");
+    cmd.args(&["--limit", "0"]).args(&["--select", "h1"]).arg("--asc");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["h1", "(NULL)", "1"],
+        svec!["h1", "(NULL)", "1"],
+        svec!["h1", "b", "1"],
+        svec!["h1", "a", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+fn frequency_other_data(name: &str, args: &[&str], use_index: bool)
+                        -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    let rows = vec![
+        svec!["h"],
+        svec!["a"], svec!["b"], svec!["a"], svec!["c"],
+        svec!["a"], svec!["b"], svec!["d"], svec!["e"],
+    ];
+    if use_index {
+        wrk.create_indexed("in.csv", rows);
+    } else {
+        wrk.create("in.csv", rows);
+    }
+    let mut cmd = wrk.command("frequency");
+    cmd.args(&["--other", "--jobs", "4"]).args(args).arg("in.csv");
+    wrk.read_stdout(&mut cmd)
+}
+
+#[test]
+fn frequency_other() {
+    for &use_index in &[false, true] {
+        let got = frequency_other_data("frequency_other", &["--limit", "2"],
+                                       use_index);
+        let expected = vec![
+            svec!["field", "value", "count"],
+            svec!["h", "a", "3"],
+            svec!["h", "b", "2"],
+            svec!["h", "Other (3)", "3"],
+        ];
+        assert_eq!(got, expected);
+    }
+}
+
+#[test]
+fn frequency_other_asc() {
+    let got = frequency_other_data("frequency_other_asc",
+                                   &["--limit", "3", "--asc"], false);
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["h", "c", "1"],
+        svec!["h", "d", "1"],
+        svec!["h", "e", "1"],
+        svec!["h", "Other (2)", "5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_other_not_needed() {
+    let got = frequency_other_data("frequency_other_not_needed",
+                                   &["--limit", "5"], false);
+    assert_eq!(got.len(), 6);
+    assert_eq!(got[5], svec!["h", "e", "1"]);
+}
+
+#[test]
+fn frequency_percentage() {
+    let (wrk, mut cmd) = setup("frequency_percentage");
+    cmd.arg("--percentage").args(&["--select", "h1"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count", "percentage"],
+        svec!["h1", "a", "3", "50.00"],
+        svec!["h1", "(NULL)", "1", "16.67"],
+        svec!["h1", "(NULL)", "1", "16.67"],
+        svec!["h1", "b", "1", "16.67"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_percentage_no_nulls() {
+    let (wrk, mut cmd) = setup("frequency_percentage_no_nulls");
+    cmd.args(&["--percentage", "--no-nulls", "--decimals", "1"])
+       .args(&["--select", "h1"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count", "percentage"],
+        svec!["h1", "a", "3", "60.0"],
+        svec!["h1", "(NULL)", "1", "20.0"],
+        svec!["h1", "b", "1", "20.0"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_percentage_other_indexed() {
+    let got = frequency_other_data("frequency_percentage_other_indexed",
+                                   &["--limit", "2", "--percentage",
+                                     "--decimals", "0"], true);
+    let expected = vec![
+        svec!["field", "value", "count", "percentage"],
+        svec!["h", "a", "3", "38"],
+        svec!["h", "b", "2", "25"],
+        svec!["h", "Other (3)", "3", "38"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_percentage_empty_column() {
+    let wrk = Workdir::new("frequency_percentage_empty_column");
+    wrk.create("in.csv", vec![svec!["h1", "h2"], svec!["", "a"]]);
+    let mut cmd = wrk.command("frequency");
+    cmd.args(&["--percentage", "--no-nulls"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count", "percentage"],
+        svec!["h2", "a", "1", "100.00"],
+    ];
+    assert_eq!(got, expected);
+}
+
+fn frequency_combine_data(name: &str, args: &[&str], use_index: bool)
+                          -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    let rows = vec![
+        svec!["country", "device", "id"],
+        svec!["fr", "mobile", "1"],
+        svec!["us", "desktop", "2"],
+        svec!["fr", "mobile", "3"],
+        svec!["us", "mobile", "4"],
+        svec!["fr", "", "5"],
+        svec!["us", "desktop", "6"],
+        svec!["fr", "mobile", "7"],
+    ];
+    if use_index {
+        wrk.create_indexed("in.csv", rows);
+    } else {
+        wrk.create("in.csv", rows);
+    }
+    let mut cmd = wrk.command("frequency");
+    cmd.args(&["--combine", "--select", "country,device", "--jobs", "4"])
+       .args(args).arg("in.csv");
+    wrk.read_stdout(&mut cmd)
+}
+
+#[test]
+fn frequency_combine() {
+    for &use_index in &[false, true] {
+        let got = frequency_combine_data("frequency_combine", &[], use_index);
+        let expected = vec![
+            svec!["country", "device", "count"],
+            svec!["fr", "mobile", "3"],
+            svec!["us", "desktop", "2"],
+            svec!["fr", "(NULL)", "1"],
+            svec!["us", "mobile", "1"],
+        ];
+        assert_eq!(got, expected);
+    }
+}
+
+#[test]
+fn frequency_combine_limit_other() {
+    let got = frequency_combine_data("frequency_combine_limit_other",
+                                     &["--limit", "1", "--other",
+                                       "--no-nulls"], true);
+    let expected = vec![
+        svec!["country", "device", "count"],
+        svec!["fr", "mobile", "3"],
+        svec!["Other (2)", "", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+fn frequency_sort_data(name: &str, args: &[&str]) -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    let rows = vec![
+        svec!["status", "method"],
+        svec!["200", "GET"], svec!["404", "GET"], svec!["200", "POST"],
+        svec!["500", "PUT"], svec!["", "GET"], svec!["200", "DELETE"],
+        svec!["30", "POST"], svec!["404", "GET"],
+    ];
+    wrk.create("in.csv", rows);
+    let mut cmd = wrk.command("frequency");
+    cmd.args(args).arg("in.csv");
+    wrk.read_stdout(&mut cmd)
+}
+
+#[test]
+fn frequency_sort_value_numeric() {
+    let got = frequency_sort_data("frequency_sort_value_numeric",
+                                  &["--sort", "value", "--select", "status"]);
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["status", "30", "1"],
+        svec!["status", "200", "3"],
+        svec!["status", "404", "2"],
+        svec!["status", "500", "1"],
+        svec!["status", "(NULL)", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_sort_value_strings_reverse() {
+    let got = frequency_sort_data("frequency_sort_value_strings_reverse",
+                                  &["--sort", "value", "--select", "method",
+                                    "--reverse"]);
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["method", "PUT", "1"],
+        svec!["method", "POST", "2"],
+        svec!["method", "GET", "4"],
+        svec!["method", "DELETE", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_sort_value_limit() {
+    let got = frequency_sort_data("frequency_sort_value_limit",
+                                  &["--sort", "value", "--select", "status",
+                                    "--limit", "2"]);
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["status", "200", "3"],
+        svec!["status", "404", "2"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_sort_count_reverse() {
+    let got = frequency_sort_data("frequency_sort_count_reverse",
+                                  &["--select", "method", "--sort", "count",
+                                    "--reverse"]);
+    let expected = vec![
+        svec!["field", "value", "count"],
+        svec!["method", "PUT", "1"],
+        svec!["method", "DELETE", "1"],
+        svec!["method", "POST", "2"],
+        svec!["method", "GET", "4"],
+    ];
+    assert_eq!(got, expected);
+}
+
+fn frequency_weight_data(name: &str, weights: &[&str], args: &[&str],
+                         use_index: bool) -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    let mut rows = vec![svec!["event", "count"]];
+    let events = ["click", "view", "click", "buy", "view", "click"];
+    for (event, weight) in events.iter().zip(weights) {
+        rows.push(vec![event.to_string(), weight.to_string()]);
+    }
+    if use_index {
+        wrk.create_indexed("in.csv", rows);
+    } else {
+        wrk.create("in.csv", rows);
+    }
+    let mut cmd = wrk.command("frequency");
+    cmd.args(&["--select", "event", "--weight", "count", "--jobs", "4"])
+       .args(args).arg("in.csv");
+    wrk.read_stdout(&mut cmd)
+}
+
+#[test]
+fn frequency_weight() {
+    for &use_index in &[false, true] {
+        let got = frequency_weight_data(
+            "frequency_weight", &["3", "10", "4", "1", "2", "x"], &[],
+            use_index);
+        let expected = vec![
+            svec!["field", "value", "count"],
+            svec!["event", "view", "12"],
+            svec!["event", "click", "7"],
+            svec!["event", "buy", "1"],
+        ];
+        assert_eq!(got, expected);
+    }
+}
+
+#[test]
+fn frequency_weight_missing_one() {
+    for &use_index in &[false, true] {
+        let got = frequency_weight_data(
+            "frequency_weight_missing_one", &["3", "10", "", "1", "2", "x"],
+            &["--weight-missing-one"], use_index);
+        let expected = vec![
+            svec!["field", "value", "count"],
+            svec!["event", "view", "12"],
+            svec!["event", "click", "5"],
+            svec!["event", "buy", "1"],
+        ];
+        assert_eq!(got, expected);
+    }
+}
+
+#[test]
+fn frequency_weight_fractional() {
+    for &use_index in &[false, true] {
+        let got = frequency_weight_data(
+            "frequency_weight_fractional",
+            &["0.5", "2", "0.25", "1", "2", "1"], &["--percentage"],
+            use_index);
+        let expected = vec![
+            svec!["field", "value", "count", "percentage"],
+            svec!["event", "view", "4", "59.26"],
+            svec!["event", "click", "1.75", "25.93"],
+            svec!["event", "buy", "1", "14.81"],
+        ];
+        assert_eq!(got, expected);
+    }
+}
+
+/// Returns shuffled rows where the value `vN` occurs about `2000 / N` times.
+fn zipf_rows() -> Vec<Vec<String>> {
+    let mut values = vec![];
+    for i in 1..301 {
+        for _ in 0..(2000 / i) {
+            values.push(format!("v{}", i));
+        }
+    }
+    // A small LCG is enough to shuffle the values reproducibly.
+    let mut seed: u64 = 42;
+    for i in (1..values.len()).rev() {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        values.swap(i, (seed >> 33) as usize % (i + 1));
+    }
+    let mut rows = vec![svec!["h"]];
+    rows.extend(values.into_iter().map(|v| vec![v]));
+    rows
+}
+
+#[test]
+fn frequency_approx_top() {
+    for &use_index in &[false, true] {
+        let wrk = Workdir::new("frequency_approx_top");
+        if use_index {
+            wrk.create_indexed("in.csv", zipf_rows());
+        } else {
+            wrk.create("in.csv", zipf_rows());
+        }
+        let mut cmd = wrk.command("frequency");
+        cmd.args(&["--jobs", "4", "in.csv"]);
+        let exact: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+
+        let mut cmd = wrk.command("frequency");
+        cmd.args(&["--approx", "100", "--jobs", "4", "in.csv"]);
+        let approx: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+
+        assert_eq!(approx[0], svec!["field", "value", "count_lower",
+                                    "count_upper"]);
+        assert_eq!(approx.len(), exact.len());
+        for (a, e) in approx[1..].iter().zip(&exact[1..]) {
+            assert_eq!(a[1], e[1]);
+            let lower: u64 = a[2].parse().unwrap();
+            let upper: u64 = a[3].parse().unwrap();
+            let count: u64 = e[2].parse().unwrap();
+            assert!(lower <= count && count <= upper,
+                    "{}: {} not in [{}, {}]", e[1], count, lower, upper);
+        }
+    }
+}
+
+#[test]
+fn frequency_memory() {
+    let wrk = Workdir::new("frequency_memory");
+    wrk.create("in.csv", zipf_rows());
+    let mut cmd = wrk.command("frequency");
+    cmd.args(&["--limit", "3", "--jobs", "1", "in.csv"]);
+    let exact: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+
+    // Below the bound, the counts are exact.
+    let mut cmd = wrk.command("frequency");
+    cmd.args(&["--memory", "300", "--limit", "3", "--jobs", "1", "in.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, exact);
+
+    let mut cmd = wrk.command("frequency");
+    cmd.args(&["--memory", "20", "--limit", "3", "--jobs", "1", "in.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["field", "value", "count_lower", "count_upper"]);
+    assert_eq!(got[1][1], "v1");
+    let lower: u64 = got[1][2].parse().unwrap();
+    let upper: u64 = got[1][3].parse().unwrap();
+    assert!(lower <= 2000 && 2000 <= upper, "[{}, {}]", lower, upper);
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("approximate"), "{}", stderr);
+}
+
+#[test]
+fn frequency_approx_exact_when_small() {
+    let (wrk, mut cmd) = setup("frequency_approx_exact_when_small");
+    cmd.args(&["--approx", "5", "--select", "h1"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count_lower", "count_upper"],
+        svec!["h1", "a", "3", "3"],
+        svec!["h1", "(NULL)", "1", "1"],
+        svec!["h1", "(NULL)", "1", "1"],
+        svec!["h1", "b", "1", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
 #[test]
 fn frequency_select() {
     let (wrk, mut cmd) = setup("frequency_select");
@@ -0,0 +1,130 @@
+use std::fs;
+use std::io::Write;
+
+use workdir::Workdir;
+
+fn fromjson(name: &str, data: &str, args: &[&str]) -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    fs::File::create(wrk.path("in.json")).unwrap()
+        .write_all(data.as_bytes()).unwrap();
+    let mut cmd = wrk.command("fromjson");
+    cmd.args(args).arg("in.json");
+    wrk.read_stdout(&mut cmd)
+}
+
+#[test]
+fn fromjson_ndjson_heterogeneous() {
+    let got = fromjson("fromjson_ndjson_heterogeneous", "\
+{\"b\": 1, \"a\": \"x\"}
+
+{\"c\": true, \"a\": \"y, \\\"z\\\"\"}
+{\"b\": null, \"d\": 1.5}
+", &[]);
+    let expected = vec![
+        svec!["b", "a", "c", "d"],
+        svec!["1", "x", "", ""],
+        svec!["", "y, \"z\"", "true", ""],
+        svec!["", "", "", "1.5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fromjson_array_nested() {
+    let got = fromjson("fromjson_array_nested", "\
+[
+  {\"id\": 1, \"tags\": [\"a\", \"b\"], \"loc\": {\"city\": \"Paris\"}},
+  {\"id\": 2}
+]
+", &[]);
+    let expected = vec![
+        svec!["id", "tags", "loc"],
+        svec!["1", "[\"a\",\"b\"]", "{\"city\":\"Paris\"}"],
+        svec!["2", "", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fromjson_flatten() {
+    let got = fromjson("fromjson_flatten", "\
+{\"id\": 1, \"loc\": {\"city\": \"Paris\", \"geo\": {\"lat\": 48}}}
+{\"id\": 2, \"tags\": [\"a\"], \"loc\": {\"zip\": \"75001\"}}
+", &["--flatten"]);
+    let expected = vec![
+        svec!["id", "loc.city", "loc.geo.lat", "tags.0", "loc.zip"],
+        svec!["1", "Paris", "48", "", ""],
+        svec!["2", "", "", "a", "75001"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fromjson_flatten_empty_containers() {
+    let got = fromjson("fromjson_flatten_empty_containers", "\
+{\"id\": 1, \"m\": {}, \"tags\": []}
+{\"id\": 2, \"m\": {\"k\": {}}, \"tags\": [[]]}
+", &["--flatten"]);
+    let expected = vec![
+        svec!["id", "m", "tags", "m.k", "tags.0"],
+        svec!["1", "{}", "[]", "", ""],
+        svec!["2", "", "", "{}", "[]"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn fromjson_flatten_collision() {
+    let wrk = Workdir::new("fromjson_flatten_collision");
+    fs::File::create(wrk.path("in.json")).unwrap()
+        .write_all(b"{\"a.b\": 1, \"a\": {\"b\": 2}}\n").unwrap();
+    let mut cmd = wrk.command("fromjson");
+    cmd.arg("in.json");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["a.b", "a"], svec!["1", "{\"b\":2}"]]);
+
+    let mut cmd = wrk.command("fromjson");
+    cmd.arg("--flatten").arg("in.json");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn fromjson_invalid() {
+    let wrk = Workdir::new("fromjson_invalid");
+    fs::File::create(wrk.path("in.json")).unwrap()
+        .write_all(b"{\"a\": 1}\n[1, 2]\n").unwrap();
+    let mut cmd = wrk.command("fromjson");
+    cmd.arg("in.json");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn fromjson_number_text() {
+    let got = fromjson("fromjson_number_text", "\
+[{\"a\": 1.0, \"b\": [1e300, -0.50], \"c\": -2}]
+", &[]);
+    let expected = vec![
+        svec!["a", "b", "c"],
+        svec!["1.0", "[1e300,-0.50]", "-2"],
+    ];
+    assert_eq!(got, expected);
+
+    let got = fromjson("fromjson_number_text_flatten", "\
+{\"a\": {\"x\": 2.50E+3}, \"b\":1e-7}
+", &["--flatten"]);
+    assert_eq!(got, vec![svec!["a.x", "b"], svec!["2.50E+3", "1e-7"]]);
+}
+
+#[test]
+fn fromjson_empty() {
+    let wrk = Workdir::new("fromjson_empty");
+    for (i, data) in ["", "[]", "\n\n"].iter().enumerate() {
+        fs::File::create(wrk.path("in.json")).unwrap()
+            .write_all(data.as_bytes()).unwrap();
+        let mut cmd = wrk.command("fromjson");
+        cmd.arg("in.json");
+        let got = cmd.output().unwrap();
+        assert!(got.status.success(), "input {}", i);
+        assert!(got.stdout.is_empty(), "input {}", i);
+    }
+}
@@ -54,9 +54,9 @@ h3";
 }
 
 #[test]
-fn headers_intersect() {
-    let (wrk, mut cmd) = setup("headers_intersect");
-    cmd.arg("in2.csv").arg("--intersect");
+fn headers_union() {
+    let (wrk, mut cmd) = setup("headers_union");
+    cmd.arg("in2.csv").arg("--union");
 
     let got: String = wrk.stdout(&mut cmd);
     let expected = "\
@@ -65,3 +65,28 @@ h2
 h3";
     assert_eq!(got, expected.to_string());
 }
+
+#[test]
+fn headers_intersect() {
+    let (wrk, mut cmd) = setup("headers_intersect");
+    wrk.create("in3.csv", vec![svec!["h3", "h2", "h1"], svec!["x", "y", "z"]]);
+    cmd.arg("in3.csv").arg("--intersect");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+h1
+h2";
+    assert_eq!(got, expected.to_string());
+
+    let mut cmd = wrk.command("headers");
+    cmd.args(&["in1.csv", "in2.csv", "in3.csv", "--intersect"]);
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "h2".to_string());
+}
+
+#[test]
+fn headers_intersect_union() {
+    let (wrk, mut cmd) = setup("headers_intersect_union");
+    cmd.args(&["in2.csv", "--intersect", "--union"]);
+    wrk.assert_err(&mut cmd);
+}
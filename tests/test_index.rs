@@ -25,3 +25,76 @@ fn future_time(ft: FileTime) -> FileTime {
     let secs = ft.seconds_relative_to_1970();
     FileTime::from_seconds_since_1970(secs + 10_000, 0)
 }
+
+#[test]
+fn index_check() {
+    let wrk = Workdir::new("index_check");
+    wrk.create_indexed("in.csv", vec![svec!["h"], svec!["a"], svec!["b"]]);
+
+    let mut cmd = wrk.command("index");
+    cmd.arg("--check").arg("--print-count").arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "2");
+}
+
+#[test]
+fn index_check_missing() {
+    let wrk = Workdir::new("index_check_missing");
+    wrk.create("in.csv", vec![svec!["h"], svec!["a"]]);
+
+    let mut cmd = wrk.command("index");
+    cmd.arg("--check").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn index_check_outdated() {
+    let wrk = Workdir::new("index_check_outdated");
+    wrk.create_indexed("in.csv", vec![svec!["h"], svec!["a"]]);
+
+    let md = fs::metadata(&wrk.path("in.csv.idx")).unwrap();
+    set_file_times(
+        &wrk.path("in.csv"),
+        future_time(FileTime::from_last_modification_time(&md)),
+        future_time(FileTime::from_last_access_time(&md)),
+    ).unwrap();
+
+    let mut cmd = wrk.command("index");
+    cmd.arg("--check").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn index_check_count() {
+    let wrk = Workdir::new("index_check_count");
+    wrk.create_indexed("in.csv", vec![svec!["h"], svec!["a"]]);
+    let md = fs::metadata(&wrk.path("in.csv.idx")).unwrap();
+
+    // Change the data without making it newer than the index.
+    wrk.create("in.csv", vec![svec!["h"], svec!["a"], svec!["b"]]);
+    set_file_times(
+        &wrk.path("in.csv"),
+        FileTime::from_last_modification_time(&md),
+        FileTime::from_last_access_time(&md),
+    ).unwrap();
+
+    let mut cmd = wrk.command("index");
+    cmd.arg("--check").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("The index has 1 records, but the CSV file has 2"),
+            "{}", stderr);
+}
+
+#[test]
+fn index_print_count() {
+    let wrk = Workdir::new("index_print_count");
+    wrk.create("in.csv", vec![svec!["h"], svec!["a"], svec!["b"]]);
+
+    let mut cmd = wrk.command("index");
+    cmd.arg("--print-count").arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "2");
+    assert!(fs::metadata(&wrk.path("in.csv.idx")).is_ok());
+}
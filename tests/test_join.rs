@@ -166,6 +166,29 @@ fn join_cross() {
     assert_eq!(got, expected);
 }
 
+#[test]
+fn join_cross_without_columns() {
+    let wrk = Workdir::new("join_cross_without_columns");
+    wrk.create("letters.csv",
+               vec![svec!["h1", "h2"], svec!["a", "b"], svec!["c", "d"]]);
+    wrk.create("numbers.csv",
+               vec![svec!["h3"], svec!["1"], svec!["2"], svec!["3"]]);
+
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--cross", "letters.csv", "numbers.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2", "h3"],
+        svec!["a", "b", "1"],
+        svec!["a", "b", "2"],
+        svec!["a", "b", "3"],
+        svec!["c", "d", "1"],
+        svec!["c", "d", "2"],
+        svec!["c", "d", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
 #[test]
 fn join_cross_no_headers() {
     let wrk = Workdir::new("join_cross_no_headers");
@@ -184,3 +207,937 @@ fn join_cross_no_headers() {
     ];
     assert_eq!(got, expected);
 }
+
+fn setup_many_to_many(name: &str, headers: bool) -> Workdir {
+    let mut left = vec![
+        svec!["a", "1", "x"],
+        svec!["b", "2", "y"],
+        svec!["a", "3", "z"],
+    ];
+    let mut right = vec![
+        svec!["a", "first"],
+        svec!["c", "only right"],
+        svec!["a", "second"],
+    ];
+    if headers { left.insert(0, svec!["key", "n", "letter"]); }
+    if headers { right.insert(0, svec!["key", "word"]); }
+
+    let wrk = Workdir::new(name);
+    wrk.create("left.csv", left);
+    wrk.create("right.csv", right);
+    wrk
+}
+
+fn join_many_to_many(
+    name: &str,
+    flag: &str,
+    headers: bool,
+    rows: Vec<Vec<String>>,
+) {
+    let wrk = setup_many_to_many(name, headers);
+    let mut cmd = wrk.command("join");
+    cmd.arg(flag);
+    if headers {
+        cmd.args(&["key", "left.csv", "key", "right.csv"]);
+    } else {
+        cmd.args(&["--no-headers", "1", "left.csv", "1", "right.csv"]);
+    }
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+
+    let mut expected = vec![];
+    if headers {
+        expected.push(svec!["key", "n", "letter", "key", "word"]);
+    }
+    expected.extend(rows.into_iter());
+    assert_eq!(got, expected);
+}
+
+fn many_to_many_left() -> Vec<Vec<String>> {
+    vec![
+        svec!["a", "1", "x", "a", "first"],
+        svec!["a", "1", "x", "a", "second"],
+        svec!["b", "2", "y", "", ""],
+        svec!["a", "3", "z", "a", "first"],
+        svec!["a", "3", "z", "a", "second"],
+    ]
+}
+
+fn many_to_many_right() -> Vec<Vec<String>> {
+    vec![
+        svec!["a", "1", "x", "a", "first"],
+        svec!["a", "3", "z", "a", "first"],
+        svec!["", "", "", "c", "only right"],
+        svec!["a", "1", "x", "a", "second"],
+        svec!["a", "3", "z", "a", "second"],
+    ]
+}
+
+fn many_to_many_full() -> Vec<Vec<String>> {
+    let mut rows = many_to_many_left();
+    rows.push(svec!["", "", "", "c", "only right"]);
+    rows
+}
+
+#[test]
+fn join_outer_left_many_to_many() {
+    join_many_to_many("join_outer_left_many_to_many",
+                      "--left", true, many_to_many_left());
+}
+
+#[test]
+fn join_outer_left_many_to_many_no_headers() {
+    join_many_to_many("join_outer_left_many_to_many_no_headers",
+                      "--left", false, many_to_many_left());
+}
+
+#[test]
+fn join_outer_right_many_to_many() {
+    join_many_to_many("join_outer_right_many_to_many",
+                      "--right", true, many_to_many_right());
+}
+
+#[test]
+fn join_outer_right_many_to_many_no_headers() {
+    join_many_to_many("join_outer_right_many_to_many_no_headers",
+                      "--right", false, many_to_many_right());
+}
+
+#[test]
+fn join_outer_full_many_to_many() {
+    join_many_to_many("join_outer_full_many_to_many",
+                      "--full", true, many_to_many_full());
+}
+
+#[test]
+fn join_outer_full_many_to_many_no_headers() {
+    join_many_to_many("join_outer_full_many_to_many_no_headers",
+                      "--full", false, many_to_many_full());
+}
+
+#[test]
+fn join_inner_two_columns() {
+    let sales = vec![
+        svec!["year", "region", "sales"],
+        svec!["2016", "east", "10"],
+        svec!["2016", "west", "20"],
+        svec!["2017", "east", "30"],
+    ];
+    let targets = vec![
+        svec!["region", "year", "target"],
+        svec!["east", "2016", "15"],
+        svec!["east", "2018", "40"],
+        svec!["west", "2017", "25"],
+    ];
+
+    let wrk = Workdir::new("join_inner_two_columns");
+    wrk.create("sales.csv", sales);
+    wrk.create("targets.csv", targets);
+
+    let mut cmd = wrk.command("join");
+    cmd.args(&["year,region", "sales.csv", "year,region", "targets.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["year", "region", "sales", "region", "year", "target"],
+        svec!["2016", "east", "10", "east", "2016", "15"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_inner_two_columns_no_collision() {
+    // Concatenating the keys would make both of these rows "abc".
+    let a = vec![svec!["ab", "c"]];
+    let b = vec![svec!["a", "bc"]];
+
+    let wrk = Workdir::new("join_inner_two_columns_no_collision");
+    wrk.create("a.csv", a);
+    wrk.create("b.csv", b);
+
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--no-headers", "1,2", "a.csv", "1,2", "b.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, Vec::<Vec<String>>::new());
+}
+
+#[test]
+fn join_different_number_of_columns() {
+    let wrk = setup("join_different_number_of_columns", true);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["city,state", "cities.csv", "city", "places.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+fn setup_messy(name: &str) -> Workdir {
+    let left = vec![
+        svec!["country", "capital"],
+        svec!["USA", "Washington"],
+        svec!["", "Nowhere"],
+        svec!["France", "Paris"],
+    ];
+    let right = vec![
+        svec!["country", "continent"],
+        svec!["usa", "North America"],
+        svec!["  ", "Unknown"],
+        svec!["FRANCE", "Europe"],
+    ];
+
+    let wrk = Workdir::new(name);
+    wrk.create("left.csv", left);
+    wrk.create("right.csv", right);
+    wrk
+}
+
+#[test]
+fn join_case_sensitive() {
+    let wrk = setup_messy("join_case_sensitive");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["country", "left.csv", "country", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["country", "capital", "country", "continent"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_ignore_case() {
+    for flag in &["--ignore-case", "--no-case"] {
+        let wrk = setup_messy("join_ignore_case");
+        let mut cmd = wrk.command("join");
+        cmd.arg(flag)
+           .args(&["country", "left.csv", "country", "right.csv"]);
+
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        let expected = vec![
+            svec!["country", "capital", "country", "continent"],
+            svec!["USA", "Washington", "usa", "North America"],
+            svec!["France", "Paris", "FRANCE", "Europe"],
+        ];
+        assert_eq!(got, expected);
+    }
+}
+
+#[test]
+fn join_nulls() {
+    let wrk = setup_messy("join_nulls");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--nulls", "country", "left.csv", "country", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["country", "capital", "country", "continent"],
+        svec!["", "Nowhere", "  ", "Unknown"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_nulls_ignore_case() {
+    let wrk = setup_messy("join_nulls_ignore_case");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--nulls", "--ignore-case"])
+       .args(&["country", "left.csv", "country", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["country", "capital", "country", "continent"],
+        svec!["USA", "Washington", "usa", "North America"],
+        svec!["", "Nowhere", "  ", "Unknown"],
+        svec!["France", "Paris", "FRANCE", "Europe"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_outer_left_without_nulls() {
+    let wrk = setup_messy("join_outer_left_without_nulls");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--left", "--ignore-case"])
+       .args(&["country", "left.csv", "country", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["country", "capital", "country", "continent"],
+        svec!["USA", "Washington", "usa", "North America"],
+        svec!["", "Nowhere", "", ""],
+        svec!["France", "Paris", "FRANCE", "Europe"],
+    ];
+    assert_eq!(got, expected);
+}
+
+fn setup_semi(name: &str) -> Workdir {
+    let left = vec![
+        svec!["id", "name"],
+        svec!["a", "first"],
+        svec!["b", "second"],
+        svec!["a", "third"],
+        svec!["", "fourth"],
+        svec!["C", "fifth"],
+    ];
+    let right = vec![
+        svec!["id", "value"],
+        svec!["a", "1"],
+        svec!["a", "2"],
+        svec!["c", "3"],
+        svec!["c", "4"],
+        svec!["", "5"],
+    ];
+
+    let wrk = Workdir::new(name);
+    wrk.create("left.csv", left);
+    wrk.create("right.csv", right);
+    wrk
+}
+
+#[test]
+fn join_semi() {
+    let wrk = setup_semi("join_semi");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--semi", "id", "left.csv", "id", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name"],
+        svec!["a", "first"],
+        svec!["a", "third"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_semi_no_case_nulls() {
+    let wrk = setup_semi("join_semi_no_case_nulls");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--semi", "--no-case", "--nulls"])
+       .args(&["id", "left.csv", "id", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name"],
+        svec!["a", "first"],
+        svec!["a", "third"],
+        svec!["", "fourth"],
+        svec!["C", "fifth"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_anti() {
+    let wrk = setup_semi("join_anti");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--anti", "id", "left.csv", "id", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name"],
+        svec!["b", "second"],
+        svec!["", "fourth"],
+        svec!["C", "fifth"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_anti_no_case_nulls() {
+    let wrk = setup_semi("join_anti_no_case_nulls");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--anti", "--no-case", "--nulls"])
+       .args(&["id", "left.csv", "id", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name"],
+        svec!["b", "second"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_semi_anti_exclusive() {
+    for flags in &[["--semi", "--anti"], ["--semi", "--left"],
+                   ["--anti", "--cross"]] {
+        let wrk = setup_semi("join_semi_anti_exclusive");
+        let mut cmd = wrk.command("join");
+        cmd.args(flags).args(&["id", "left.csv", "id", "right.csv"]);
+        wrk.assert_err(&mut cmd);
+    }
+}
+
+#[test]
+fn join_prefix_duplicates() {
+    let wrk = setup("join_prefix_duplicates", true);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--prefix-left", "l_", "--prefix-right", "r_"])
+       .args(&["city", "cities.csv", "city", "places.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["l_city", "state", "r_city", "place"]);
+    assert_eq!(got.len(), 4);
+}
+
+#[test]
+fn join_prefix_one_side() {
+    let wrk = setup("join_prefix_one_side", true);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--full", "--prefix-right", "places."])
+       .args(&["city", "cities.csv", "city", "places.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["city", "state", "places.city", "place"]);
+}
+
+#[test]
+fn join_prefix_all() {
+    let wrk = setup("join_prefix_all", true);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--cross", "--prefix-all"])
+       .args(&["--prefix-left", "l_", "--prefix-right", "r_"])
+       .args(&["city", "cities.csv", "city", "places.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["l_city", "l_state", "r_city", "r_place"]);
+    assert_eq!(got.len(), 17);
+}
+
+#[test]
+fn join_prefix_no_headers() {
+    let wrk = setup("join_prefix_no_headers", false);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--no-headers", "--prefix-left", "l_"])
+       .args(&["1", "cities.csv", "1", "places.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["Boston", "MA", "Boston", "Logan Airport"]);
+    assert_eq!(got.len(), 3);
+}
+
+fn setup_sorted(name: &str) -> Workdir {
+    let left = vec![
+        svec!["key", "left"],
+        svec!["", "l0"],
+        svec!["a", "l1"],
+        svec!["b", "l2"],
+        svec!["b", "l3"],
+        svec!["d", "l4"],
+        svec!["e", "l5"],
+    ];
+    let right = vec![
+        svec!["key", "right"],
+        svec!["", "r0"],
+        svec!["b", "r1"],
+        svec!["b", "r2"],
+        svec!["c", "r3"],
+        svec!["e", "r4"],
+        svec!["f", "r5"],
+    ];
+
+    let wrk = Workdir::new(name);
+    wrk.create("left.csv", left);
+    wrk.create("right.csv", right);
+    wrk
+}
+
+#[test]
+fn join_sorted_agrees() {
+    for flag in &[None, Some("--left"), Some("--right"), Some("--full")] {
+        for nulls in &[false, true] {
+            let wrk = setup_sorted("join_sorted_agrees");
+            let run = |sorted: bool| {
+                let mut cmd = wrk.command("join");
+                if let Some(flag) = *flag {
+                    cmd.arg(flag);
+                }
+                if *nulls {
+                    cmd.arg("--nulls");
+                }
+                if sorted {
+                    cmd.arg("--sorted");
+                }
+                cmd.args(&["key", "left.csv", "key", "right.csv"]);
+                let mut rows: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+                rows.sort();
+                rows
+            };
+            assert_eq!(run(true), run(false), "{:?} {}", flag, nulls);
+        }
+    }
+}
+
+#[test]
+fn join_sorted_inner() {
+    let wrk = setup_sorted("join_sorted_inner");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--sorted", "key", "left.csv", "key", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["key", "left", "key", "right"],
+        svec!["b", "l2", "b", "r1"],
+        svec!["b", "l2", "b", "r2"],
+        svec!["b", "l3", "b", "r1"],
+        svec!["b", "l3", "b", "r2"],
+        svec!["e", "l5", "e", "r4"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_sorted_no_case() {
+    let wrk = Workdir::new("join_sorted_no_case");
+    wrk.create("left.csv", vec![
+        svec!["key", "left"], svec!["A", "l1"], svec!["b", "l2"],
+    ]);
+    wrk.create("right.csv", vec![
+        svec!["key", "right"], svec!["a", "r1"], svec!["B", "r2"],
+    ]);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--sorted", "--no-case"])
+       .args(&["key", "left.csv", "key", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["key", "left", "key", "right"],
+        svec!["A", "l1", "a", "r1"],
+        svec!["b", "l2", "B", "r2"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_sorted_out_of_order() {
+    let wrk = Workdir::new("join_sorted_out_of_order");
+    wrk.create("left.csv", vec![
+        svec!["key"], svec!["a"], svec!["b"],
+    ]);
+    wrk.create("right.csv", vec![
+        svec!["key"], svec!["a"], svec!["c"], svec!["b"],
+    ]);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--sorted", "--full"])
+       .args(&["key", "left.csv", "key", "right.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    let got = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(got.contains("second input"), "{}", got);
+    assert!(got.contains("record 3"), "{}", got);
+}
+
+#[test]
+fn join_numeric() {
+    let wrk = Workdir::new("join_numeric");
+    wrk.create("left.csv", vec![
+        svec!["id", "left"],
+        svec!["7", "seven"],
+        svec!["1", "one"],
+        svec!["1000", "thousand"],
+        svec!["2.5", "two and a half"],
+        svec!["0", "zero"],
+    ]);
+    wrk.create("right.csv", vec![
+        svec!["id", "right"],
+        svec!["007", "a"],
+        svec!["1.0", "b"],
+        svec![" +1e3 ", "c"],
+        svec!["2.50", "d"],
+        svec!["-0", "e"],
+        svec!["abc", "f"],
+    ]);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--numeric", "id", "left.csv", "id", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "left", "id", "right"],
+        svec!["7", "seven", "007", "a"],
+        svec!["1", "one", "1.0", "b"],
+        svec!["1000", "thousand", " +1e3 ", "c"],
+        svec!["2.5", "two and a half", "2.50", "d"],
+        svec!["0", "zero", "-0", "e"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_numeric_mixed() {
+    let wrk = Workdir::new("join_numeric_mixed");
+    wrk.create("left.csv", vec![
+        svec!["id", "left"],
+        svec!["1", "one"],
+        svec!["2", "two"],
+        svec!["3", "three"],
+    ]);
+    wrk.create("right.csv", vec![
+        svec!["id", "right"],
+        svec!["01", "a"],
+        svec!["ABC", "b"],
+        svec!["", "c"],
+        svec!["3", "d"],
+        svec!["2x", "e"],
+    ]);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--numeric", "--left"])
+       .args(&["id", "left.csv", "id", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "left", "id", "right"],
+        svec!["1", "one", "01", "a"],
+        svec!["2", "two", "", ""],
+        svec!["3", "three", "3", "d"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_not_numeric() {
+    let wrk = Workdir::new("join_not_numeric");
+    wrk.create("left.csv", vec![svec!["id"], svec!["7"]]);
+    wrk.create("right.csv", vec![svec!["id"], svec!["007"]]);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["id", "left.csv", "id", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["id", "id"]]);
+}
+
+fn setup_chain(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create("sales.csv", vec![
+        svec!["customer", "product", "name"],
+        svec!["c1", "p1", "first"],
+        svec!["c2", "p2", "second"],
+        svec!["c1", "p3", "third"],
+    ]);
+    wrk.create("customers.csv", vec![
+        svec!["customer", "name"],
+        svec!["c1", "Alice"],
+        svec!["c2", "Bob"],
+    ]);
+    wrk.create("products.csv", vec![
+        svec!["product", "name"],
+        svec!["p1", "Apple"],
+        svec!["p2", "Pear"],
+        svec!["p2", "Plum"],
+    ]);
+    wrk
+}
+
+#[test]
+fn join_and_inner() {
+    let wrk = setup_chain("join_and_inner");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["customer", "sales.csv", "customer", "customers.csv"])
+       .args(&["--and", "product", "products.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["customer", "product", "name", "customer", "name",
+              "product", "name"],
+        svec!["c1", "p1", "first", "c1", "Alice", "p1", "Apple"],
+        svec!["c2", "p2", "second", "c2", "Bob", "p2", "Pear"],
+        svec!["c2", "p2", "second", "c2", "Bob", "p2", "Plum"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_and_left_prefix() {
+    let wrk = setup_chain("join_and_left_prefix");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--left", "--prefix-right", "r_"])
+       .args(&["customer", "sales.csv", "customer", "customers.csv"])
+       .args(&["--and", "product", "products.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["customer", "product", "name", "r_customer", "r_name",
+              "r_product", "r_name"],
+        svec!["c1", "p1", "first", "c1", "Alice", "p1", "Apple"],
+        svec!["c2", "p2", "second", "c2", "Bob", "p2", "Pear"],
+        svec!["c2", "p2", "second", "c2", "Bob", "p2", "Plum"],
+        svec!["c1", "p3", "third", "c1", "Alice", "", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_and_prefix_collisions() {
+    let wrk = setup_chain("join_and_prefix_collisions");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--prefix-left", "l_"])
+       .args(&["customer", "sales.csv", "customer", "customers.csv"])
+       .args(&["--and", "product", "products.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["l_customer", "product", "l_name", "customer",
+                             "name", "product", "name"]);
+
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--prefix-left", "l_", "--prefix-right", "r_"])
+       .args(&["customer", "sales.csv", "customer", "customers.csv"])
+       .args(&["--and", "product", "products.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["l_customer", "product", "l_name",
+                             "r_customer", "r_name", "r_product", "r_name"]);
+}
+
+#[test]
+fn join_and_only_inner_left() {
+    let wrk = setup_chain("join_and_only_inner_left");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--full"])
+       .args(&["customer", "sales.csv", "customer", "customers.csv"])
+       .args(&["--and", "product", "products.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+fn setup_range(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create("values.csv", vec![
+        svec!["value", "name"],
+        svec!["5", "a"],
+        svec!["10", "b"],
+        svec!["25", "c"],
+        svec!["x", "d"],
+        svec!["20.5", "e"],
+    ]);
+    wrk.create("ranges.csv", vec![
+        svec!["low", "high", "range"],
+        svec!["10", "20", "second"],
+        svec!["0", "10", "first"],
+        svec!["?", "30", "broken"],
+        svec!["5", "50", "wide"],
+    ]);
+    wrk
+}
+
+#[test]
+fn join_range() {
+    let wrk = setup_range("join_range");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--range", "value", "values.csv", "low,high", "ranges.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["value", "name", "low", "high", "range"],
+        svec!["5", "a", "0", "10", "first"],
+        svec!["5", "a", "5", "50", "wide"],
+        // Both ends of a range are included.
+        svec!["10", "b", "10", "20", "second"],
+        svec!["10", "b", "0", "10", "first"],
+        svec!["10", "b", "5", "50", "wide"],
+        svec!["25", "c", "5", "50", "wide"],
+        svec!["20.5", "e", "5", "50", "wide"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_range_left() {
+    let wrk = setup_range("join_range_left");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--range", "--left"])
+       .args(&["value", "values.csv", "low,high", "ranges.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 9);
+    assert_eq!(got[7], svec!["x", "d", "", "", ""]);
+}
+
+#[test]
+fn join_range_many() {
+    // Compares with a brute force search, with a few wide ranges among many
+    // narrow ones, which mustn't hide the narrow ones after them.
+    let wrk = Workdir::new("join_range_many");
+    let mut ranges = vec![];
+    for i in 0..300 {
+        let low = (i * 37) % 1000;
+        let width = if i % 50 == 0 { 900 } else { (i * 13) % 20 };
+        ranges.push((low, low + width, format!("r{}", i)));
+    }
+    let mut rows = vec![svec!["low", "high", "range"]];
+    for &(low, high, ref name) in &ranges {
+        rows.push(vec![low.to_string(), high.to_string(), name.clone()]);
+    }
+    wrk.create("ranges.csv", rows);
+    let values: Vec<i32> = (0..100).map(|i| (i * 53) % 1100).collect();
+    let mut rows = vec![svec!["value"]];
+    rows.extend(values.iter().map(|v| vec![v.to_string()]));
+    wrk.create("values.csv", rows);
+
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--range", "value", "values.csv", "low,high", "ranges.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+
+    let mut expected = vec![svec!["value", "low", "high", "range"]];
+    for &v in &values {
+        for &(low, high, ref name) in &ranges {
+            if low <= v && v <= high {
+                expected.push(vec![
+                    v.to_string(), low.to_string(), high.to_string(),
+                    name.clone(),
+                ]);
+            }
+        }
+    }
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_range_needs_two_columns() {
+    let wrk = setup_range("join_range_needs_two_columns");
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--range", "value", "values.csv", "low", "ranges.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+fn setup_unmatched(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create("left.csv", vec![
+        svec!["id", "left"],
+        svec!["a", "l1"],
+        svec!["b", "l2"],
+        svec!["a", "l3"],
+        svec!["c", "l4"],
+        svec!["c", "l5"],
+    ]);
+    wrk.create("right.csv", vec![
+        svec!["id", "right"],
+        svec!["a", "r1"],
+        svec!["d", "r2"],
+        svec!["a", "r3"],
+        svec!["d", "r4"],
+        svec!["e", "r5"],
+    ]);
+    wrk
+}
+
+fn unmatched_expected() -> (String, String) {
+    let left = "id,left\nb,l2\nc,l4\nc,l5\n";
+    let right = "id,right\nd,r2\nd,r4\ne,r5\n";
+    (left.to_owned(), right.to_owned())
+}
+
+#[test]
+fn join_unmatched() {
+    for flag in &[None, Some("--left"), Some("--right"), Some("--full")] {
+        let wrk = setup_unmatched("join_unmatched");
+        let mut cmd = wrk.command("join");
+        if let Some(flag) = *flag {
+            cmd.arg(flag);
+        }
+        cmd.args(&["--unmatched-left", "unmatched-left.csv"])
+           .args(&["--unmatched-right", "unmatched-right.csv"])
+           .args(&["id", "left.csv", "id", "right.csv"]);
+        let joined: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+
+        // The join itself isn't affected.
+        let mut cmd = wrk.command("join");
+        if let Some(flag) = *flag {
+            cmd.arg(flag);
+        }
+        cmd.args(&["id", "left.csv", "id", "right.csv"]);
+        let expected: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        assert_eq!(joined, expected, "{:?}", flag);
+
+        let (left, right) = unmatched_expected();
+        let got: String = wrk.from_str(&wrk.path("unmatched-left.csv"));
+        assert_eq!(got, left, "{:?}", flag);
+        let got: String = wrk.from_str(&wrk.path("unmatched-right.csv"));
+        assert_eq!(got, right, "{:?}", flag);
+    }
+}
+
+#[test]
+fn join_unmatched_sorted() {
+    let wrk = setup_unmatched("join_unmatched_sorted");
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--select", "id", "left.csv", "--output", "left-s.csv"]);
+    wrk.run(&mut cmd);
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--select", "id", "right.csv", "--output", "right-s.csv"]);
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--sorted", "--left"])
+       .args(&["--unmatched-left", "unmatched-left.csv"])
+       .args(&["--unmatched-right", "unmatched-right.csv"])
+       .args(&["id", "left-s.csv", "id", "right-s.csv"]);
+    wrk.run(&mut cmd);
+
+    let (left, right) = unmatched_expected();
+    let got: String = wrk.from_str(&wrk.path("unmatched-left.csv"));
+    assert_eq!(got, left);
+    let got: String = wrk.from_str(&wrk.path("unmatched-right.csv"));
+    assert_eq!(got, right);
+}
+
+#[test]
+fn join_unmatched_invalid_keeps_files() {
+    let wrk = setup_unmatched("join_unmatched_invalid_keeps_files");
+    for flag in &["--semi", "--range"] {
+        wrk.create("unmatched-left.csv", vec![svec!["keep"]]);
+        let mut cmd = wrk.command("join");
+        cmd.arg(flag)
+           .args(&["--unmatched-left", "unmatched-left.csv"])
+           .args(&["id", "left.csv", "id", "right.csv"]);
+        wrk.assert_err(&mut cmd);
+
+        let got: String = wrk.from_str(&wrk.path("unmatched-left.csv"));
+        assert_eq!(got, "keep\n");
+    }
+}
+
+#[test]
+fn join_unicode_case() {
+    let wrk = Workdir::new("join_unicode_case");
+    wrk.create("left.csv", vec![
+        svec!["key", "left"],
+        svec!["STRASSE", "l1"],
+        svec!["É", "l2"],
+        svec!["ΣΊΣΥΦΟΣ", "l3"],
+        svec!["İ", "l4"],
+        svec!["I", "l5"],
+    ]);
+    wrk.create("right.csv", vec![
+        svec!["key", "right"],
+        svec!["straße", "r1"],
+        svec!["é", "r2"],
+        svec!["σίσυφος", "r3"],
+        // İ folds to i followed by U+0307, not to i.
+        svec!["i\u{307}", "r4"],
+        // Dotless i doesn't fold to, or from, anything else.
+        svec!["ı", "r5"],
+    ]);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--unicode-case", "key", "left.csv", "key", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["key", "left", "key", "right"],
+        svec!["STRASSE", "l1", "straße", "r1"],
+        svec!["É", "l2", "é", "r2"],
+        svec!["ΣΊΣΥΦΟΣ", "l3", "σίσυφος", "r3"],
+        svec!["İ", "l4", "i\u{307}", "r4"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_no_case_no_fold() {
+    let wrk = Workdir::new("join_no_case_no_fold");
+    wrk.create("left.csv", vec![svec!["key"], svec!["STRASSE"], svec!["É"]]);
+    wrk.create("right.csv", vec![svec!["key"], svec!["straße"], svec!["é"]]);
+    let mut cmd = wrk.command("join");
+    cmd.args(&["--no-case", "key", "left.csv", "key", "right.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["key", "key"],
+        svec!["É", "é"],
+    ];
+    assert_eq!(got, expected);
+}
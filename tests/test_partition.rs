@@ -222,3 +222,117 @@ CA,San Francisco
 CO,Denver
 ");
 }
+
+#[test]
+fn partition_max_open() {
+    let wrk = Workdir::new("partition_max_open");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.args(&["--max-open", "1"])
+        .arg("state")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    part_eq!(wrk, "CA.csv", "\
+state,city
+CA,San Francisco
+");
+    part_eq!(wrk, "NY.csv", "\
+state,city
+NY,Manhatten
+NY,Buffalo
+");
+    part_eq!(wrk, "TX.csv", "\
+state,city
+TX,Dallas
+TX,Fort Worth
+");
+}
+
+#[test]
+fn partition_max_open_without_headers() {
+    let wrk = Workdir::new("partition_max_open_without_headers");
+    wrk.create("in.csv", data(false));
+
+    let mut cmd = wrk.command("partition");
+    cmd.args(&["--max-open", "2", "--no-headers"])
+        .arg("1")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    part_eq!(wrk, "NY.csv", "\
+NY,Manhatten
+NY,Buffalo
+");
+    part_eq!(wrk, "TX.csv", "\
+TX,Dallas
+TX,Fort Worth
+");
+}
+
+#[test]
+fn partition_max_open_zero() {
+    let wrk = Workdir::new("partition_max_open_zero");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.args(&["--max-open", "0"])
+        .arg("state")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn partition_limit() {
+    let wrk = Workdir::new("partition_limit");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.args(&["--limit", "3"])
+        .arg("state")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+    assert!(wrk.path("TX.csv").exists());
+}
+
+#[test]
+fn partition_limit_exceeded() {
+    let wrk = Workdir::new("partition_limit_exceeded");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.args(&["--limit", "2"])
+        .arg("state")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+    assert!(!wrk.path("TX.csv").exists());
+}
+
+#[test]
+fn partition_sanitizes_slashes() {
+    let wrk = Workdir::new("partition_sanitizes_slashes");
+    wrk.create("in.csv", vec![
+        svec!["path", "n"],
+        svec!["../up", "1"],
+        svec!["a/b", "2"],
+    ]);
+
+    let mut cmd = wrk.command("partition");
+    cmd.arg("path").arg(&wrk.path("out")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    part_eq!(wrk, "out/up.csv", "\
+path,n
+../up,1
+");
+    part_eq!(wrk, "out/ab.csv", "\
+path,n
+a/b,2
+");
+}
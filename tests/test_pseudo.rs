@@ -0,0 +1,84 @@
+use workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["sender", "recipient", "amount"],
+        svec!["alice", "bob", "1"],
+        svec!["bob", "carol", "2"],
+        svec!["alice", "", "3"],
+        svec!["carol", "alice", "4"],
+    ]
+}
+
+#[test]
+fn pseudo_tokens() {
+    let wrk = Workdir::new("pseudo_tokens");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("pseudo");
+    cmd.arg("sender,recipient").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["sender", "recipient", "amount"],
+        svec!["sender-0", "recipient-0", "1"],
+        svec!["sender-1", "recipient-1", "2"],
+        svec!["sender-0", "", "3"],
+        svec!["sender-2", "recipient-2", "4"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn pseudo_tokens_shared() {
+    let wrk = Workdir::new("pseudo_tokens_shared");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("pseudo");
+    cmd.args(&["--shared", "--prefix", "user"])
+       .arg("sender,recipient").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["sender", "recipient", "amount"],
+        svec!["user-0", "user-1", "1"],
+        svec!["user-1", "user-2", "2"],
+        svec!["user-0", "", "3"],
+        svec!["user-2", "user-0", "4"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn pseudo_hash() {
+    let wrk = Workdir::new("pseudo_hash");
+    wrk.create("in.csv", vec![
+        svec!["v"],
+        svec!["The quick brown fox jumps over the lazy dog"],
+        svec![""],
+    ]);
+    let hash = |salt: &str| {
+        let mut cmd = wrk.command("pseudo");
+        cmd.args(&["--hash", "--salt", salt, "v", "in.csv"]);
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        got
+    };
+
+    // The HMAC-SHA256 of the value with the key 'key'.
+    let got = hash("key");
+    assert_eq!(got, vec![
+        svec!["v"],
+        svec!["f7bc83f430538424b13298e6aa6fb143\
+               ef4d59a14946175997479dbc2d1a3cd8"],
+        svec![""],
+    ]);
+    assert_eq!(got, hash("key"));
+    assert!(got != hash("other"));
+}
+
+#[test]
+fn pseudo_hash_no_salt() {
+    let wrk = Workdir::new("pseudo_hash_no_salt");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("pseudo");
+    cmd.args(&["--hash", "sender", "in.csv"]);
+    wrk.assert_err(&mut cmd);
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::io::Write;
+
+use workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["h1", "h2", "h3"],
+        svec!["a", "b", "c"],
+    ]
+}
+
+#[test]
+fn rename_positional() {
+    let wrk = Workdir::new("rename_positional");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("rename");
+    cmd.arg(r#"x,"y, z",w"#).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["x", "y, z", "w"],
+        svec!["a", "b", "c"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn rename_count_mismatch() {
+    let wrk = Workdir::new("rename_count_mismatch");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("rename");
+    cmd.arg("x,y").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn rename_ignore_extra() {
+    let wrk = Workdir::new("rename_ignore_extra");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("rename");
+    cmd.arg("--ignore-extra").arg("x,y").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["x", "y", "h3"]);
+}
+
+#[test]
+fn rename_map() {
+    let wrk = Workdir::new("rename_map");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("rename");
+    cmd.args(&["--map", "h3:z,h1:x"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["x", "h2", "z"],
+        svec!["a", "b", "c"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn rename_map_unknown_column() {
+    let wrk = Workdir::new("rename_map_unknown_column");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("rename");
+    cmd.args(&["--map", "h4:z"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn rename_no_headers() {
+    let wrk = Workdir::new("rename_no_headers");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("rename");
+    cmd.arg("--no-headers").arg("x,y,z").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["x", "y", "z"],
+        svec!["h1", "h2", "h3"],
+        svec!["a", "b", "c"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn rename_map_repeated() {
+    let wrk = Workdir::new("rename_map_repeated");
+    wrk.create("in.csv", vec![
+        svec!["h1", "h2", "h1"],
+        svec!["a", "b", "c"],
+    ]);
+    let mut cmd = wrk.command("rename");
+    cmd.args(&["--map", "h1=x", "--map", "\"h2:y, z\""]).arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "x,\"y, z\",x\na,b,c");
+
+    let mut cmd = wrk.command("rename");
+    cmd.args(&["--map", "h1[1]=x"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["h1", "h2", "x"]);
+
+    let mut cmd = wrk.command("rename");
+    cmd.args(&["--map", "h1[2]=x"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn rename_raw_rows() {
+    let wrk = Workdir::new("rename_raw_rows");
+    fs::File::create(wrk.path("in.csv")).unwrap()
+        .write_all(b"h1;h2\r\n\"1\";2\r\n\"a;b\";\"\"\n").unwrap();
+    let mut cmd = wrk.command("rename");
+    cmd.args(&["-d", ";", "x,y;z", "in.csv"]);
+    let got = cmd.output().unwrap().stdout;
+    assert_eq!(String::from_utf8(got).unwrap(),
+               "x;\"y;z\"\n\"1\";2\r\n\"a;b\";\"\"\n");
+}
@@ -0,0 +1,112 @@
+use workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["name", "phone"],
+        svec!["Ann", "555.123.4567"],
+        svec!["Bob 555.987", "555.987.6543"],
+        svec!["Cid", "unknown"],
+    ]
+}
+
+#[test]
+fn replace_captures() {
+    let wrk = Workdir::new("replace_captures");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("replace");
+    cmd.arg(r"(\d+)\.(\d+)\.(\d+)").arg("($1) $2-$3").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "phone"],
+        svec!["Ann", "(555) 123-4567"],
+        svec!["Bob 555.987", "(555) 987-6543"],
+        svec!["Cid", "unknown"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn replace_select() {
+    let wrk = Workdir::new("replace_select");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("replace");
+    cmd.args(&["--select", "name"]).arg(r"\.").arg("-").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "phone"],
+        svec!["Ann", "555.123.4567"],
+        svec!["Bob 555-987", "555.987.6543"],
+        svec!["Cid", "unknown"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn replace_literal_ignore_case() {
+    let wrk = Workdir::new("replace_literal_ignore_case");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("replace");
+    cmd.args(&["--literal", "--ignore-case"])
+       .arg("UNKNOWN.").arg("$1").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[3], svec!["Cid", "unknown"]);
+
+    // -F is short for --literal, like in search.
+    let mut cmd = wrk.command("replace");
+    cmd.args(&["-F", "-i"]).arg("UNKNOWN").arg("$1").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[3], svec!["Cid", "$1"]);
+}
+
+#[test]
+fn replace_no_headers() {
+    let wrk = Workdir::new("replace_no_headers");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("replace");
+    cmd.arg("--no-headers").arg("^[a-z]+$").arg("").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["", ""]);
+    assert_eq!(got[3], svec!["Cid", ""]);
+}
+
+#[test]
+fn replace_first() {
+    let wrk = Workdir::new("replace_first");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("replace");
+    cmd.args(&["--first", "--select", "phone"])
+       .arg(r"\.").arg(",").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "phone"],
+        svec!["Ann", "555,123.4567"],
+        svec!["Bob 555.987", "555,987.6543"],
+        svec!["Cid", "unknown"],
+    ];
+    assert_eq!(got, expected);
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert!(got.contains("\"555,123.4567\""), "{}", got);
+}
+
+#[test]
+fn replace_dry_run() {
+    let wrk = Workdir::new("replace_dry_run");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("replace");
+    cmd.args(&["--dry-run", "--output", "out.csv"])
+       .arg(r"\.").arg("-").arg("in.csv");
+    wrk.run(&mut cmd);
+
+    let output = cmd.output().unwrap();
+    assert!(output.stdout.is_empty());
+    assert_eq!(String::from_utf8(output.stderr).unwrap(),
+               "3 fields would be changed.\n");
+    assert!(!wrk.path("out.csv").exists());
+}
@@ -0,0 +1,71 @@
+use workdir::Workdir;
+
+fn data(headers: bool) -> Vec<Vec<String>> {
+    let mut rows = vec![
+        svec!["a", "1"],
+        svec!["b", "2"],
+        svec!["c", "3"],
+        svec!["d", "4"],
+    ];
+    if headers { rows.insert(0, svec!["letter", "number"]); }
+    rows
+}
+
+fn reversed(headers: bool) -> Vec<Vec<String>> {
+    let mut rows = vec![
+        svec!["d", "4"],
+        svec!["c", "3"],
+        svec!["b", "2"],
+        svec!["a", "1"],
+    ];
+    if headers { rows.insert(0, svec!["letter", "number"]); }
+    rows
+}
+
+fn reverse(name: &str, headers: bool, use_index: bool) {
+    let wrk = Workdir::new(name);
+    if use_index {
+        wrk.create_indexed("in.csv", data(headers));
+    } else {
+        wrk.create("in.csv", data(headers));
+    }
+
+    let mut cmd = wrk.command("reverse");
+    cmd.arg("in.csv");
+    if !headers { cmd.arg("--no-headers"); }
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, reversed(headers));
+}
+
+#[test]
+fn reverse_headers_no_index() {
+    reverse("reverse_headers_no_index", true, false);
+}
+
+#[test]
+fn reverse_headers_index() {
+    reverse("reverse_headers_index", true, true);
+}
+
+#[test]
+fn reverse_no_headers_no_index() {
+    reverse("reverse_no_headers_no_index", false, false);
+}
+
+#[test]
+fn reverse_no_headers_index() {
+    reverse("reverse_no_headers_index", false, true);
+}
+
+#[test]
+fn reverse_only_headers_index() {
+    let wrk = Workdir::new("reverse_only_headers_index");
+    wrk.create_indexed("in.csv", vec![svec!["letter", "number"]]);
+
+    let mut cmd = wrk.command("reverse");
+    cmd.arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["letter", "number"]]);
+}
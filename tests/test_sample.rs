@@ -0,0 +1,133 @@
+use workdir::Workdir;
+
+fn data(nrows: usize) -> Vec<Vec<String>> {
+    let mut rows = vec![svec!["n", "square"]];
+    for i in 0..nrows {
+        rows.push(vec![i.to_string(), (i * i).to_string()]);
+    }
+    rows
+}
+
+fn sample_seeded(wrk: &Workdir, size: &str, seed: &str) -> String {
+    let mut cmd = wrk.command("sample");
+    cmd.args(&["--seed", seed]).arg(size).arg("in.csv");
+    wrk.stdout(&mut cmd)
+}
+
+#[test]
+fn sample_seed_reproducible() {
+    let wrk = Workdir::new("sample_seed_reproducible");
+    wrk.create("in.csv", data(100));
+
+    let first = sample_seeded(&wrk, "10", "42");
+    let second = sample_seeded(&wrk, "10", "42");
+    assert_eq!(first, second);
+    assert_eq!(first.lines().count(), 11);
+    assert_eq!(first.lines().next(), Some("n,square"));
+}
+
+#[test]
+fn sample_seed_reproducible_idx() {
+    let wrk = Workdir::new("sample_seed_reproducible_idx");
+    // Small enough relative to the input to use random access.
+    wrk.create_indexed("in.csv", data(100));
+
+    let first = sample_seeded(&wrk, "5", "42");
+    let second = sample_seeded(&wrk, "5", "42");
+    assert_eq!(first, second);
+    assert_eq!(first.lines().count(), 6);
+    assert_eq!(first.lines().next(), Some("n,square"));
+}
+
+#[test]
+fn sample_seed_differs() {
+    let wrk = Workdir::new("sample_seed_differs");
+    wrk.create("in.csv", data(100));
+
+    let first = sample_seeded(&wrk, "10", "1");
+    let second = sample_seeded(&wrk, "10", "2");
+    assert!(first != second);
+}
+
+#[test]
+fn sample_larger_than_input() {
+    let wrk = Workdir::new("sample_larger_than_input");
+    wrk.create("in.csv", data(3));
+
+    let got: Vec<Vec<String>> = {
+        let mut cmd = wrk.command("sample");
+        cmd.args(&["--seed", "7", "10", "in.csv"]);
+        wrk.read_stdout(&mut cmd)
+    };
+    assert_eq!(got, data(3));
+}
+
+fn sample_rate(wrk: &Workdir, rate: &str, seed: &str) -> String {
+    let mut cmd = wrk.command("sample");
+    cmd.args(&["--seed", seed, "--rate", rate]).arg("in.csv");
+    wrk.stdout(&mut cmd)
+}
+
+#[test]
+fn sample_rate_reproducible() {
+    let wrk = Workdir::new("sample_rate_reproducible");
+    wrk.create("in.csv", data(1000));
+
+    let first = sample_rate(&wrk, "0.1", "42");
+    let second = sample_rate(&wrk, "0.1", "42");
+    assert_eq!(first, second);
+    assert!(first != sample_rate(&wrk, "0.1", "43"));
+    assert_eq!(first.lines().next(), Some("n,square"));
+
+    // The records are kept in their original order.
+    let ns: Vec<usize> = first.lines().skip(1)
+        .map(|line| line.split(',').next().unwrap().parse().unwrap())
+        .collect();
+    assert!(ns.windows(2).all(|w| w[0] < w[1]));
+    assert!(ns.len() > 50 && ns.len() < 150, "{}", ns.len());
+}
+
+#[test]
+fn sample_rate_bounds() {
+    let wrk = Workdir::new("sample_rate_bounds");
+    wrk.create("in.csv", data(10));
+
+    assert_eq!(sample_rate(&wrk, "0", "1"), "n,square");
+    assert_eq!(sample_rate(&wrk, "1", "1").lines().count(), 11);
+
+    let mut cmd = wrk.command("sample");
+    cmd.args(&["--rate", "1.5", "in.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn sample_stratify() {
+    let wrk = Workdir::new("sample_stratify");
+    let mut rows = vec![svec!["n", "group"]];
+    for i in 0..100 {
+        rows.push(vec![i.to_string(), "a".to_string()]);
+    }
+    for i in 100..102 {
+        rows.push(vec![i.to_string(), "b".to_string()]);
+    }
+    for i in 102..150 {
+        rows.push(vec![i.to_string(), "c".to_string()]);
+    }
+    wrk.create("in.csv", rows);
+
+    let run = |seed: &str| {
+        let mut cmd = wrk.command("sample");
+        cmd.args(&["--seed", seed, "--stratify", "group", "5", "in.csv"]);
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        got
+    };
+    let got = run("42");
+    assert_eq!(got[0], svec!["n", "group"]);
+    let groups: Vec<&str> = got[1..].iter().map(|r| &*r[1]).collect();
+    assert_eq!(groups, vec!["a", "a", "a", "a", "a", "b", "b",
+                            "c", "c", "c", "c", "c"]);
+    // The smaller group contributes all of its records.
+    assert_eq!(got[6], svec!["100", "b"]);
+    assert_eq!(got[7], svec!["101", "b"]);
+    assert_eq!(got, run("42"));
+}
@@ -1,3 +1,6 @@
+use std::fs;
+use std::io::Write;
+
 use workdir::Workdir;
 
 fn data(headers: bool) -> Vec<Vec<String>> {
@@ -149,3 +152,413 @@ fn search_invert_match_no_headers() {
     ];
     assert_eq!(got, expected);
 }
+
+#[test]
+fn search_count() {
+    let wrk = Workdir::new("search_count");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("search");
+    cmd.arg("--count").arg("^foo").arg("data.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "2");
+}
+
+#[test]
+fn search_count_select_invert() {
+    let wrk = Workdir::new("search_count_select_invert");
+    wrk.create("data.csv", data(false));
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--count", "--invert-match", "--no-headers"])
+       .args(&["--select", "1"])
+       .arg("^foo").arg("data.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "2");
+}
+
+#[test]
+fn search_flag() {
+    let wrk = Workdir::new("search_flag");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--flag", "matched", "--select", "h2"])
+       .arg("^foo").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2", "matched"],
+        svec!["foobar", "barfoo", "0"],
+        svec!["a", "b", "0"],
+        svec!["barfoo", "foobar", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn search_flag_no_headers() {
+    let wrk = Workdir::new("search_flag_no_headers");
+    wrk.create("data.csv", data(false));
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--flag", "matched", "--no-headers"])
+       .arg("^foo").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["foobar", "barfoo", "1"],
+        svec!["a", "b", "0"],
+        svec!["barfoo", "foobar", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn search_flag_invert() {
+    let wrk = Workdir::new("search_flag_invert");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--flag", "matched", "--invert-match"])
+       .arg("^foo").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2", "matched"],
+        svec!["foobar", "barfoo", "0"],
+        svec!["a", "b", "2"],
+        svec!["barfoo", "foobar", "0"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn search_patterns() {
+    let wrk = Workdir::new("search_patterns");
+    wrk.create("data.csv", vec![
+        svec!["h1", "h2"],
+        svec!["foo", "x"],
+        svec!["BAR", "y"],
+        svec!["baz", "z"],
+        svec!["#quux", "w"],
+    ]);
+    wrk.create("patterns.txt", vec![
+        svec!["# identifiers"],
+        svec!["^foo$"],
+        svec![""],
+        svec!["bar"],
+        svec!["#quux"],
+    ]);
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--patterns", "patterns.txt", "-i"]).arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["foo", "x"],
+        svec!["BAR", "y"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn search_patterns_exact() {
+    let wrk = Workdir::new("search_patterns_exact");
+    wrk.create("data.csv", data(true));
+    wrk.create("patterns.txt", vec![svec!["foo"], svec!["a"]]);
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--exact", "--patterns", "patterns.txt", "data.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h1", "h2"], svec!["a", "b"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn search_patterns_bad_line() {
+    let wrk = Workdir::new("search_patterns_bad_line");
+    wrk.create("data.csv", data(true));
+    wrk.create("patterns.txt", vec![
+        svec!["foo"], svec!["# comment"], svec!["ba(r"],
+    ]);
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--patterns", "patterns.txt", "data.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("patterns.txt: line 3:"), "{}", stderr);
+}
+
+#[test]
+fn search_literal() {
+    let wrk = Workdir::new("search_literal");
+    wrk.create("data.csv", vec![
+        svec!["h1"],
+        svec!["aXb"],
+        svec!["a.b"],
+        svec!["A.B"],
+    ]);
+    let mut cmd = wrk.command("search");
+    cmd.arg("a.b").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["aXb"], svec!["a.b"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.arg("--literal").arg("a.b").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["a.b"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["-F", "-i"]).arg("a.b").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["a.b"], svec!["A.B"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["-F", "-i", "-x"]).arg("A.").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["-F", "-i", "-x", "--where", "h1:A.b", "data.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["a.b"], svec!["A.B"]]);
+}
+
+#[test]
+fn search_literal_patterns() {
+    let wrk = Workdir::new("search_literal_patterns");
+    wrk.create("data.csv", vec![
+        svec!["h1"],
+        svec!["foo(bar)"],
+        svec!["foobar"],
+        svec!["1.2.3.4"],
+        svec!["1x2x3x4"],
+    ]);
+    wrk.create("patterns.txt", vec![svec!["foo(bar)"], svec!["1.2.3.4"]]);
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--literal", "--patterns", "patterns.txt", "data.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h1"], svec!["foo(bar)"], svec!["1.2.3.4"]];
+    assert_eq!(got, expected);
+}
+
+fn setup_where(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create("data.csv", vec![
+        svec!["status", "region name"],
+        svec!["error", "eu-west"],
+        svec!["ok", "eu-west"],
+        svec!["error", "us-east"],
+        svec!["errors", "eu-north"],
+    ]);
+    wrk
+}
+
+#[test]
+fn search_where() {
+    let wrk = setup_where("search_where");
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--where", "status:^error$"])
+       .args(&["--where", "\"region name\":^eu-"])
+       .arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["status", "region name"],
+        svec!["error", "eu-west"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn search_where_regex_invert() {
+    let wrk = setup_where("search_where_regex_invert");
+    let mut cmd = wrk.command("search");
+    cmd.args(&["-v", "-w", "1:error"]).arg("eu-").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["status", "region name"],
+        svec!["ok", "eu-west"],
+        svec!["error", "us-east"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn search_where_unknown_column() {
+    let wrk = setup_where("search_where_unknown_column");
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--where", "region:^eu-"]).arg("data.csv");
+    wrk.assert_err(&mut cmd);
+
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("The available columns are: status, region name"),
+            "{}", stderr);
+}
+
+#[test]
+fn search_limit() {
+    let wrk = Workdir::new("search_limit");
+    wrk.create("data.csv", vec![
+        svec!["h1"], svec!["a1"], svec!["b"], svec!["a2"], svec!["a3"],
+    ]);
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--limit", "2", "^a", "data.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["a1"], svec!["a2"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--limit", "0", "^a", "data.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 4);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--limit", "2", "--count", "^a", "data.csv"]);
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "2");
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--limit", "5", "--count", "-v", "^a", "data.csv"]);
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "1");
+}
+
+#[test]
+fn search_limit_flag() {
+    let wrk = Workdir::new("search_limit_flag");
+    wrk.create("data.csv", vec![
+        svec!["h1"], svec!["a1"], svec!["b"], svec!["a2"], svec!["a3"],
+    ]);
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--limit", "2", "--flag", "m", "--output", "out.csv"])
+       .args(&["^a", "data.csv"]);
+    wrk.run(&mut cmd);
+
+    let got: String = wrk.from_str(&wrk.path("out.csv"));
+    // The rows after the limit are still written, but aren't flagged.
+    assert_eq!(got, "h1,m\na1,1\nb,0\na2,3\na3,0\n");
+}
+
+fn setup_lines(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create("data.csv", vec![
+        svec!["h1"],
+        svec!["foo\nbar"],
+        svec!["foobar"],
+        svec!["é"],
+    ]);
+    wrk
+}
+
+#[test]
+fn search_multiline() {
+    let wrk = setup_lines("search_multiline");
+    let mut cmd = wrk.command("search");
+    cmd.arg("^bar$").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.arg("--multiline").arg("^bar$").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["foo\nbar"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--multiline", "--exact"]).arg("bar").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"]]);
+}
+
+#[test]
+fn search_dot_matches_newline() {
+    let wrk = setup_lines("search_dot_matches_newline");
+    let mut cmd = wrk.command("search");
+    cmd.arg("o.b").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["foobar"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.arg("--dot-matches-newline").arg("o.b").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["foo\nbar"], svec!["foobar"]]);
+}
+
+#[test]
+fn search_no_unicode() {
+    let wrk = setup_lines("search_no_unicode");
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--exact", r"\w"]).arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["é"]]);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--no-unicode", "--exact", r"\w"]).arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"]]);
+}
+
+#[test]
+fn search_invalid_utf8() {
+    let wrk = Workdir::new("search_invalid_utf8");
+    let data: &[u8] = b"h1,h2\nab\xffcd,x\nabcd,y\n";
+    fs::File::create(wrk.path("data.csv")).unwrap().write_all(data).unwrap();
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--no-unicode", "b.c"]).arg("data.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "h1,h2\nab\u{fffd}cd,x");
+
+    let mut cmd = wrk.command("search");
+    cmd.arg("cd,?").arg("data.csv");
+    wrk.run(&mut cmd);
+    let got = cmd.output().unwrap().stdout;
+    assert_eq!(got, data.to_vec());
+}
+
+#[test]
+fn search_exact() {
+    let wrk = Workdir::new("search_exact");
+    wrk.create("data.csv", vec![
+        svec!["h1", "h2"],
+        svec!["foo", "x"],
+        svec!["foobar", "y"],
+        svec!["bar", "foo|bar"],
+    ]);
+    let mut cmd = wrk.command("search");
+    cmd.arg("--exact").arg("foo|bar").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["foo", "x"],
+        svec!["bar", "foo|bar"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn search_invert_match_select() {
+    let wrk = Workdir::new("search_invert_match_select");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--invert-match", "--exact", "--select", "h1,h2"])
+       .arg("foobar").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["a", "b"],
+    ];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(&["--invert-match", "--select", "h2"])
+       .arg("^foo").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["foobar", "barfoo"],
+        svec!["a", "b"],
+    ];
+    assert_eq!(got, expected);
+}
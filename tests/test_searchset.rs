@@ -0,0 +1,122 @@
+use std::fs;
+use std::io::Write;
+
+use workdir::Workdir;
+
+fn setup(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create("data.csv", vec![
+        svec!["h1", "h2"],
+        svec!["foobar", "barfoo"],
+        svec!["a", "b"],
+        svec!["barfoo", "quux"],
+        svec!["xyz", "QUUX"],
+    ]);
+    let mut f = fs::File::create(wrk.path("patterns.txt")).unwrap();
+    f.write_all(b"^foo\n\nquux$\r\n").unwrap();
+    wrk
+}
+
+#[test]
+fn searchset() {
+    let wrk = setup("searchset");
+    let mut cmd = wrk.command("searchset");
+    cmd.arg("patterns.txt").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["foobar", "barfoo"],
+        svec!["barfoo", "quux"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn searchset_ignore_case_select() {
+    let wrk = setup("searchset_ignore_case_select");
+    let mut cmd = wrk.command("searchset");
+    cmd.args(&["--ignore-case", "--select", "h2"])
+       .arg("patterns.txt").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["barfoo", "quux"],
+        svec!["xyz", "QUUX"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn searchset_invert_match() {
+    let wrk = setup("searchset_invert_match");
+    let mut cmd = wrk.command("searchset");
+    cmd.arg("--invert-match").arg("patterns.txt").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["a", "b"],
+        svec!["xyz", "QUUX"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn searchset_flag() {
+    let wrk = setup("searchset_flag");
+    let mut f = fs::File::create(wrk.path("patterns.txt")).unwrap();
+    f.write_all(b"^foo\nfoo$\nquux$\n").unwrap();
+    let mut cmd = wrk.command("searchset");
+    cmd.args(&["--flag", "matched"]).arg("patterns.txt").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2", "matched"],
+        svec!["foobar", "barfoo", "1;2"],
+        svec!["a", "b", "0"],
+        svec!["barfoo", "quux", "2;3"],
+        svec!["xyz", "QUUX", "0"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn searchset_comments() {
+    let wrk = setup("searchset_comments");
+    let mut f = fs::File::create(wrk.path("patterns.txt")).unwrap();
+    f.write_all(b"# Starts with foo.\n^foo\n#quux$\n").unwrap();
+    let mut cmd = wrk.command("searchset");
+    cmd.arg("patterns.txt").arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2"],
+        svec!["foobar", "barfoo"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn searchset_no_patterns() {
+    let wrk = setup("searchset_no_patterns");
+    let mut f = fs::File::create(wrk.path("patterns.txt")).unwrap();
+    f.write_all(b"\n# Nothing here.\n").unwrap();
+    let mut cmd = wrk.command("searchset");
+    cmd.arg("patterns.txt").arg("data.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn searchset_invalid_pattern_line() {
+    let wrk = setup("searchset_invalid_pattern_line");
+    let mut f = fs::File::create(wrk.path("patterns.txt")).unwrap();
+    f.write_all(b"^foo\n\n(quux\n").unwrap();
+    let mut cmd = wrk.command("searchset");
+    cmd.arg("patterns.txt").arg("data.csv");
+    wrk.assert_err(&mut cmd);
+
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("patterns.txt: line 3:"), "{}", stderr);
+}
@@ -1,3 +1,6 @@
+use std::fs;
+use std::io::Write;
+
 use workdir::Workdir;
 
 macro_rules! select_test {
@@ -98,12 +101,174 @@ select_test!(select_duplicate_range_reverse, "h1-h2,h2-h1", "1-2,2-1",
              ["h1", "h2", "h2", "h1"], ["a", "b", "b", "a"]);
 
 select_test!(select_range_no_end, "h4-", "4-", ["h4", "h1"], ["d", "e"]);
-select_test!(select_range_no_start, "-h2", "-2", ["h1", "h2"], ["a", "b"]);
+select_test!(select_range_no_start, "-h2", "1-2", ["h1", "h2"], ["a", "b"]);
 select_test!(select_range_no_end_cat, "h4-,h1", "4-,1",
              ["h4", "h1", "h1"], ["d", "e", "a"]);
-select_test!(select_range_no_start_cat, "-h2,h1[1]", "-2,5",
+select_test!(select_range_no_start_cat, "-h2,h1[1]", "1-2,5",
              ["h1", "h2", "h1"], ["a", "b", "e"]);
 
+select_test!(select_regex, "/^h[0-9]$/", "1,2,4,5",
+             ["h1", "h2", "h4", "h1"], ["a", "b", "d", "e"]);
+select_test!(select_regex_cat, r"h4,/\[/,h1-h2", "4,3,1-2",
+             ["h4", "h[]3", "h1", "h2"], ["d", "c", "a", "b"]);
+select_test!(select_regex_escaped_slash, r"/[\/\[]/", "3",
+             ["h[]3"], ["c"]);
+select_test!(select_regex_not, "!/1/", "!1,5",
+             ["h2", "h[]3", "h4"], ["b", "c", "d"]);
+
+#[test]
+fn select_regex_allow_empty() {
+    let wrk = Workdir::new("select_regex_allow_empty");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("select");
+    cmd.arg("--allow-empty").arg("h2,/z/").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h2"], svec!["b"]]);
+}
+
+#[test]
+fn select_regex_wide() {
+    let wrk = Workdir::new("select_regex_wide");
+    wrk.create("data.csv", vec![
+        svec!["id", "metric_2021_01", "name", "metric_2021_02"],
+        svec!["1", "a", "b", "c"],
+    ]);
+    let mut cmd = wrk.command("select");
+    cmd.arg("/^metric_/,id").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![
+        svec!["metric_2021_01", "metric_2021_02", "id"],
+        svec!["a", "c", "1"],
+    ]);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("!/^metric_/").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["id", "name"], svec!["1", "b"]]);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("--no-headers").arg("/^metric_/").arg("data.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+select_test!(select_reorder_duplicate, "3,1,1,2", "3,1,1,2",
+             ["h[]3", "h1", "h1", "h2"], ["c", "a", "a", "b"]);
+
+select_test!(select_rest_start, "*,h1", "*,1",
+             ["h2", "h[]3", "h4", "h1", "h1"], ["b", "c", "d", "e", "a"]);
+select_test!(select_rest_middle, "h4,*,h2", "4,*,2",
+             ["h4", "h1", "h[]3", "h1", "h2"], ["d", "a", "c", "e", "b"]);
+select_test!(select_rest_end, "h4,*", "4,*",
+             ["h4", "h1", "h2", "h[]3", "h1"], ["d", "a", "b", "c", "e"]);
+select_test!(select_rest_duplicate, "h1[1],*", "5,*",
+             ["h1", "h1", "h2", "h[]3", "h4"], ["e", "a", "b", "c", "d"]);
+select_test!(select_rest_range, "h4-h2,*", "4-2,*",
+             ["h4", "h[]3", "h2", "h1", "h1"], ["d", "c", "b", "a", "e"]);
+select_test_err!(select_err_rest_twice, "*,h1,*");
+
+fn dedup_data() -> Vec<Vec<String>> {
+    vec![
+        svec!["id", "x", "id", "x"],
+        svec!["1", "a", "1", "a"],
+        svec!["2", "b", "2", "c"],
+    ]
+}
+
+#[test]
+fn select_dedup() {
+    let wrk = Workdir::new("select_dedup");
+    wrk.create("data.csv", dedup_data());
+    let mut cmd = wrk.command("select");
+    cmd.arg("--dedup").arg("x,1-").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["x", "id"], svec!["a", "1"], svec!["b", "2"]]);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("--dedup").arg("--no-headers").arg("1-").arg("data.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn select_dedup_by_value() {
+    let wrk = Workdir::new("select_dedup_by_value");
+    wrk.create("data.csv", dedup_data());
+    let mut cmd = wrk.command("select");
+    cmd.arg("--dedup-by-value").arg("1-").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![
+        svec!["id", "x", "x"],
+        svec!["1", "a", "a"],
+        svec!["2", "b", "c"],
+    ]);
+}
+
+#[test]
+fn select_not_flag() {
+    let wrk = Workdir::new("select_not_flag");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("select");
+    cmd.arg("--not").arg("h2,h4").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1", "h[]3", "h1"], svec!["a", "c", "e"]]);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("--not").arg("2-4").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1", "h1"], svec!["a", "e"]]);
+}
+
+#[test]
+fn select_not_empty() {
+    let wrk = Workdir::new("select_not_empty");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("select");
+    cmd.arg("!1-").arg("data.csv");
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("--not").arg("--allow-empty").arg("1-").arg("data.csv");
+    wrk.run(&mut cmd);
+}
+
+#[test]
+fn select_ignore_case() {
+    let wrk = Workdir::new("select_ignore_case");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("select");
+    cmd.arg("--ignore-case").arg("H4,H1[1]-h2").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![
+        svec!["h4", "h1", "h4", "h[]3", "h2"],
+        svec!["d", "e", "d", "c", "b"],
+    ]);
+}
+
+select_test!(select_from_end, "h1[1]", "-1", ["h1"], ["e"]);
+select_test!(select_from_end_cat, "h4,h1[0]", "-2,1",
+             ["h4", "h1"], ["d", "a"]);
+select_test!(select_not_from_end, "!h1[1]", "!-1",
+             ["h1", "h2", "h[]3", "h4"], ["a", "b", "c", "d"]);
+select_test!(select_range_from_end, "h1--2", "1--2",
+             ["h1", "h2", "h[]3", "h4"], ["a", "b", "c", "d"]);
+select_test!(select_range_from_end_both, "-3--1", "-3--1",
+             ["h[]3", "h4", "h1"], ["c", "d", "e"]);
+select_test!(select_range_from_end_no_end, "-2-", "-2-",
+             ["h4", "h1"], ["d", "e"]);
+select_test!(select_range_from_end_invert, "!-1--1", "!-1--1",
+             ["h1", "h2", "h[]3", "h4"], ["a", "b", "c", "d"]);
+
+select_test_err!(select_err_case_sensitive, "H1");
+
+#[test]
+fn select_err_from_end_oob() {
+    let wrk = Workdir::new("select_err_from_end_oob");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("select");
+    cmd.arg("--").arg("-6--1").arg("data.csv");
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("Selector index -6 is out of bounds"),
+            "{}", stderr);
+}
 select_test_err!(select_err_unknown_header, "dne");
 select_test_err!(select_err_oob_low, "0");
 select_test_err!(select_err_oob_high, "6");
@@ -114,3 +279,29 @@ select_test_err!(select_err_idx_not_int_2, "h1[a]");
 select_test_err!(select_err_unclosed_quote, r#""h1"#);
 select_test_err!(select_err_unclosed_bracket, r#""h1"[1"#);
 select_test_err!(select_err_expected_end_of_field, "a-b-");
+select_test_err!(select_err_regex_no_match, "h2,/z/");
+select_test_err!(select_err_regex_unclosed, "/h1");
+select_test_err!(select_err_regex_invalid, "/(/");
+select_test_err!(select_err_regex_range, "/h1/-h2");
+
+#[test]
+fn select_pad() {
+    let wrk = Workdir::new("select_pad");
+    fs::File::create(wrk.path("data.csv")).unwrap()
+        .write_all(b"a,b,c,d\n1,2\n1,2,3,4\n1,2,3,4,5,6\n").unwrap();
+    let mut cmd = wrk.command("select");
+    cmd.arg("--pad").arg("d,2-3").arg("data.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "d,b,c\n,2,\n4,2,3\n4,2,3");
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("--pad").arg("1-6").arg("data.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "a,b,c,d,,\n1,2,,,,\n1,2,3,4,,\n1,2,3,4,5,6");
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("d,2-3").arg("data.csv");
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("record 1") && stderr.contains("2 fields"),
+            "{}", stderr);
+}
@@ -142,3 +142,58 @@ fn slice_index_withindex() {
 fn slice_index_no_headers_withindex() {
     test_index("slice_index_no_headers_withindex", 1, "b", false, true);
 }
+
+fn test_slice_args(name: &str, args: &[&str], expected: &[&str],
+                   use_index: bool) {
+    let (wrk, mut cmd) = setup(name, true, use_index);
+    cmd.args(args);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let mut expected = expected.iter()
+                               .map(|&s| vec![s.to_owned()])
+                               .collect::<Vec<Vec<String>>>();
+    expected.insert(0, svec!["header"]);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn slice_negative_start() {
+    for &use_index in &[false, true] {
+        test_slice_args("slice_negative_start", &["--start", "-2"],
+                        &["d", "e"], use_index);
+        test_slice_args("slice_negative_start", &["--start", "-10"],
+                        &["a", "b", "c", "d", "e"], use_index);
+    }
+}
+
+#[test]
+fn slice_negative_end() {
+    for &use_index in &[false, true] {
+        test_slice_args("slice_negative_end", &["--start", "1", "--end", "-1"],
+                        &["b", "c", "d"], use_index);
+        test_slice_args("slice_negative_end", &["--start=-3", "--end=-1"],
+                        &["c", "d"], use_index);
+    }
+}
+
+#[test]
+fn slice_negative_index() {
+    for &use_index in &[false, true] {
+        test_slice_args("slice_negative_index", &["--index", "-1"],
+                        &["e"], use_index);
+        test_slice_args("slice_negative_index", &["--index", "-6"],
+                        &[], use_index);
+    }
+}
+
+#[test]
+fn slice_start_len() {
+    for &use_index in &[false, true] {
+        test_slice_args("slice_start_len", &["--start", "1", "--len", "2"],
+                        &["b", "c"], use_index);
+        test_slice_args("slice_start_len", &["--start", "-2", "--len", "5"],
+                        &["d", "e"], use_index);
+        test_slice_args("slice_start_len", &["--len", "2"],
+                        &["a", "b"], use_index);
+    }
+}
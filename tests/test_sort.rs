@@ -1,15 +1,16 @@
 use std::cmp;
+use std::fs;
 
 use workdir::Workdir;
 
 use {Csv, CsvData, qcheck};
 
-fn prop_sort(name: &str, rows: CsvData, headers: bool) -> bool {
+fn prop_sort(name: &str, rows: CsvData, headers: bool, args: &[&str]) -> bool {
     let wrk = Workdir::new(name);
     wrk.create("in.csv", rows.clone());
 
     let mut cmd = wrk.command("sort");
-    cmd.arg("in.csv");
+    cmd.arg("in.csv").args(args);
     if !headers { cmd.arg("--no-headers"); }
 
     let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
@@ -27,7 +28,7 @@ fn prop_sort(name: &str, rows: CsvData, headers: bool) -> bool {
 #[test]
 fn prop_sort_headers() {
     fn p(rows: CsvData) -> bool {
-        prop_sort("prop_sort_headers", rows, true)
+        prop_sort("prop_sort_headers", rows, true, &[])
     }
     qcheck(p as fn(CsvData) -> bool);
 }
@@ -35,7 +36,25 @@ fn prop_sort_headers() {
 #[test]
 fn prop_sort_no_headers() {
     fn p(rows: CsvData) -> bool {
-        prop_sort("prop_sort_no_headers", rows, false)
+        prop_sort("prop_sort_no_headers", rows, false, &[])
+    }
+    qcheck(p as fn(CsvData) -> bool);
+}
+
+#[test]
+fn prop_sort_max_mem_headers() {
+    fn p(rows: CsvData) -> bool {
+        let name = "prop_sort_max_mem_headers";
+        prop_sort(name, rows, true, &["--max-mem", "64"])
+    }
+    qcheck(p as fn(CsvData) -> bool);
+}
+
+#[test]
+fn prop_sort_max_mem_no_headers() {
+    fn p(rows: CsvData) -> bool {
+        let name = "prop_sort_max_mem_no_headers";
+        prop_sort(name, rows, false, &["--max-mem", "64"])
     }
     qcheck(p as fn(CsvData) -> bool);
 }
@@ -70,11 +89,37 @@ fn sort_numeric() {
     let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
     let expected = vec![
         svec!["N", "S"],
-        //Non-numerics should be put first
-        svec!["LETTER", "b"],
         svec!["1", "d"],
         svec!["2", "c"],
         svec!["10", "a"],
+        //Non-numerics should be put last
+        svec!["LETTER", "b"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_numeric_non_numbers_next_column() {
+    let wrk = Workdir::new("sort_numeric_non_numbers_next_column");
+    wrk.create("in.csv", vec![
+        svec!["N", "M"],
+        svec!["x", "3"],
+        svec!["y", "1"],
+        svec!["2", "z"],
+        svec!["z", "2"],
+    ]);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["-N", "-R", "-s", "N,M"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["N", "M"],
+        svec!["2", "z"],
+        // Fields that aren't numbers tie, so the next column decides.
+        svec!["x", "3"],
+        svec!["z", "2"],
+        svec!["y", "1"],
     ];
     assert_eq!(got, expected);
 }
@@ -97,12 +142,12 @@ fn sort_numeric_non_natural() {
     let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
     let expected = vec![
         svec!["N", "S"],
-        //Non-numerics should be put first
-        svec!["LETTER", "c"],
         svec!["3.33", "e"],
         svec!["5", "b"],
         svec!["7.4", "d"],
         svec!["8.33", "a"],
+        //Non-numerics should be put last
+        svec!["LETTER", "c"],
     ];
     assert_eq!(got, expected);
 }
@@ -143,3 +188,394 @@ pub fn iter_cmp<A, L, R>(mut a: L, mut b: R) -> cmp::Ordering
         }
     }
 }
+
+fn max_mem_data() -> Vec<Vec<String>> {
+    vec![
+        svec!["N", "S"],
+        svec!["10", "a"],
+        svec!["2", "b"],
+        svec!["2.0", "c"],
+        svec!["1", "d"],
+        svec!["LETTER", "e"],
+        svec!["02", "f"],
+    ]
+}
+
+#[test]
+fn sort_max_mem_numeric_reverse() {
+    let wrk = Workdir::new("sort_max_mem_numeric_reverse");
+    wrk.create("in.csv", max_mem_data());
+    fs::create_dir(wrk.path("tmp")).unwrap();
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--max-mem", "1", "-N", "-R", "--stable"])
+       .arg("--tmp-dir").arg(wrk.path("tmp"))
+       .args(&["--select", "N"])
+       .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["N", "S"],
+        svec!["10", "a"],
+        // Equal values stay in their original order.
+        svec!["2", "b"],
+        svec!["2.0", "c"],
+        svec!["02", "f"],
+        svec!["1", "d"],
+        svec!["LETTER", "e"],
+    ];
+    assert_eq!(got, expected);
+
+    // All of the sorted runs were cleaned up.
+    let left: Vec<_> = fs::read_dir(wrk.path("tmp")).unwrap().collect();
+    assert!(left.is_empty());
+}
+
+#[test]
+fn sort_max_mem_select() {
+    let wrk = Workdir::new("sort_max_mem_select");
+    wrk.create("in.csv", max_mem_data());
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--max-mem", "20", "--select", "S", "-R"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let mut expected = max_mem_data();
+    expected[1..].reverse();
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_max_mem_cleanup_on_error() {
+    let wrk = Workdir::new("sort_max_mem_cleanup_on_error").flexible(true);
+    wrk.create("in.csv", vec![
+        svec!["N", "S"],
+        svec!["2", "a"],
+        svec!["1", "b"],
+        svec!["ragged"],
+    ]);
+    fs::create_dir(wrk.path("tmp")).unwrap();
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--max-mem", "1"])
+       .arg("--tmp-dir").arg(wrk.path("tmp"))
+       .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    let left: Vec<_> = fs::read_dir(wrk.path("tmp")).unwrap().collect();
+    assert!(left.is_empty());
+}
+
+#[test]
+fn sort_max_mem_zero() {
+    let wrk = Workdir::new("sort_max_mem_zero");
+    wrk.create("in.csv", max_mem_data());
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--max-mem", "0"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn sort_natural() {
+    let wrk = Workdir::new("sort_natural");
+    wrk.create("in.csv", vec![
+        svec!["item10"],
+        svec!["item2"],
+        svec!["item1b"],
+        svec!["item01"],
+        svec!["item1"],
+        svec!["2.10"],
+        svec!["2.9"],
+        svec!["item"],
+    ]);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--natural", "--no-headers"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["2.9"],
+        svec!["2.10"],
+        svec!["item"],
+        svec!["item01"],
+        svec!["item1"],
+        svec!["item1b"],
+        svec!["item2"],
+        svec!["item10"],
+    ];
+    assert_eq!(got, expected);
+}
+
+fn stable_data() -> Vec<Vec<String>> {
+    vec![
+        svec!["k", "v"],
+        svec!["b", "1"],
+        svec!["a", "2"],
+        svec!["b", "3"],
+        svec!["a", "4"],
+        svec!["b", "5"],
+        svec!["a", "6"],
+    ]
+}
+
+#[test]
+fn sort_stable() {
+    let wrk = Workdir::new("sort_stable");
+    wrk.create("in.csv", stable_data());
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--stable", "--select", "k"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+
+    // The sort is stable without the flag too.
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--select", "k"]).arg("in.csv");
+    assert_eq!(got, wrk.read_stdout::<Vec<Vec<String>>>(&mut cmd));
+
+    let expected = vec![
+        svec!["k", "v"],
+        svec!["a", "2"],
+        svec!["a", "4"],
+        svec!["a", "6"],
+        svec!["b", "1"],
+        svec!["b", "3"],
+        svec!["b", "5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_stable_reverse() {
+    let wrk = Workdir::new("sort_stable_reverse");
+    wrk.create("in.csv", stable_data());
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--stable", "-R", "--select", "k"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["k", "v"],
+        svec!["b", "1"],
+        svec!["b", "3"],
+        svec!["b", "5"],
+        svec!["a", "2"],
+        svec!["a", "4"],
+        svec!["a", "6"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys() {
+    let wrk = Workdir::new("sort_keys");
+    wrk.create("in.csv", vec![
+        svec!["name", "n", "s"],
+        svec!["file10", "10", "x"],
+        svec!["file2", "9", "y"],
+        svec!["file2", "10", "z"],
+        svec!["file10", "9", "w"],
+    ]);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--key", "name:v", "-k", "n:n"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "n", "s"],
+        svec!["file2", "9", "y"],
+        svec!["file2", "10", "z"],
+        svec!["file10", "9", "w"],
+        svec!["file10", "10", "x"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys_lexical() {
+    let wrk = Workdir::new("sort_keys_lexical");
+    wrk.create("in.csv", vec![
+        svec!["name", "n"],
+        svec!["file2", "10"],
+        svec!["file10", "9"],
+        svec!["file10", "10"],
+    ]);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--key", "1", "--key", "2:"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "n"],
+        svec!["file10", "10"],
+        svec!["file10", "9"],
+        svec!["file2", "10"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys_reverse() {
+    let wrk = Workdir::new("sort_keys_reverse");
+    wrk.create("in.csv", vec![
+        svec!["region", "revenue", "id"],
+        svec!["west", "5", "1"],
+        svec!["east", "10", "2"],
+        svec!["west", "20", "3"],
+        svec!["east", "n/a", "4"],
+        svec!["east", "10", "5"],
+        svec!["east", "9", "6"],
+        svec!["west", "5", "7"],
+    ]);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--key", "region", "--key", "revenue:nr"])
+       .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["region", "revenue", "id"],
+        // Ties stay in their input order.
+        svec!["east", "10", "2"],
+        svec!["east", "10", "5"],
+        svec!["east", "9", "6"],
+        svec!["east", "n/a", "4"],
+        svec!["west", "20", "3"],
+        svec!["west", "5", "1"],
+        svec!["west", "5", "7"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys_column_with_colon() {
+    let wrk = Workdir::new("sort_keys_column_with_colon");
+    wrk.create("in.csv", vec![
+        svec!["time:utc", "a:n"],
+        svec!["b", "10"],
+        svec!["a", "9"],
+        svec!["c", "9"],
+    ]);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--key", "time:utc:r"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["time:utc", "a:n"],
+        svec!["c", "9"],
+        svec!["b", "10"],
+        svec!["a", "9"],
+    ];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--key", "a:n:n", "--key", "time:utc"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["time:utc", "a:n"],
+        svec!["a", "9"],
+        svec!["c", "9"],
+        svec!["b", "10"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys_bad_type() {
+    let wrk = Workdir::new("sort_keys_bad_type");
+    wrk.create("in.csv", stable_data());
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--key", "k:x"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn sort_keys_with_select() {
+    let wrk = Workdir::new("sort_keys_with_select");
+    wrk.create("in.csv", stable_data());
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["--key", "k", "--select", "v"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+/// Checks that `sort --unique` gives the same output as `sort --stable`
+/// piped through `dedup --sorted` with the same comparison `args`.
+fn sort_unique_matches_dedup(name: &str, args: &[&str], sort_args: &[&str]) {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", max_mem_data());
+
+    let mut cmd = wrk.command("sort");
+    cmd.arg("--unique").args(args).args(sort_args).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+
+    let mut cmd = wrk.command("sort");
+    cmd.arg("--stable").args(args).args(sort_args)
+       .args(&["-o", "sorted.csv", "in.csv"]);
+    wrk.run(&mut cmd);
+    let mut cmd = wrk.command("dedup");
+    cmd.arg("--sorted").args(args).arg("sorted.csv");
+    let expected: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_unique() {
+    sort_unique_matches_dedup("sort_unique", &["-N", "-s", "N"], &[]);
+    sort_unique_matches_dedup("sort_unique_reverse",
+                              &["-N", "-s", "N"], &["-R"]);
+    sort_unique_matches_dedup("sort_unique_max_mem",
+                              &["-N", "-s", "N"], &["--max-mem", "1"]);
+
+    let wrk = Workdir::new("sort_unique_first");
+    wrk.create("in.csv", max_mem_data());
+    let mut cmd = wrk.command("sort");
+    cmd.args(&["-u", "-N", "-s", "N", "--max-mem", "20", "in.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![
+        svec!["N", "S"],
+        svec!["1", "d"],
+        svec!["2", "b"],
+        svec!["10", "a"],
+        svec!["LETTER", "e"],
+    ]);
+}
+
+#[test]
+fn sort_max_mem_matches_in_memory() {
+    let wrk = Workdir::new("sort_max_mem_matches_in_memory");
+    let mut rows = vec![svec!["n", "i"]];
+    // A small LCG gives reproducible numbers with many duplicates.
+    let mut seed: u64 = 7;
+    for i in 0..2000 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        rows.push(vec![((seed >> 33) % 500).to_string(), i.to_string()]);
+    }
+    wrk.create("in.csv", rows);
+    fs::create_dir(wrk.path("tmp")).unwrap();
+
+    let sort = |max_mem: Option<&str>| {
+        let mut cmd = wrk.command("sort");
+        cmd.args(&["-N", "-R", "--stable", "-s", "n"]);
+        if let Some(max_mem) = max_mem {
+            cmd.args(&["--max-mem", max_mem])
+               .arg("--tmp-dir").arg(wrk.path("tmp"));
+        }
+        cmd.arg("in.csv");
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        got
+    };
+    let in_memory = sort(None);
+    let external = sort(Some("1K"));
+    assert_eq!(external.len(), 2001);
+    assert_eq!(external, in_memory);
+
+    // With a run for every record, there are many more runs than can be
+    // merged at once, so they're merged in several passes.
+    let external = sort(Some("1"));
+    assert_eq!(external, in_memory);
+
+    let left: Vec<_> = fs::read_dir(wrk.path("tmp")).unwrap().collect();
+    assert!(left.is_empty());
+}
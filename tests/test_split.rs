@@ -266,3 +266,771 @@ fn split_custom_filename() {
     assert!(wrk.path("prefix-2.csv").exists());
     assert!(wrk.path("prefix-4.csv").exists());
 }
+
+#[test]
+fn split_max_size() {
+    let wrk = Workdir::new("split_max_size");
+    wrk.create("in.csv", data(true));
+
+    // The header is 6 bytes and each record is 4 bytes.
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--max-size", "14"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "0.csv", "\
+h1,h2
+a,b
+c,d
+");
+    split_eq!(wrk, "2.csv", "\
+h1,h2
+e,f
+g,h
+");
+    split_eq!(wrk, "4.csv", "\
+h1,h2
+i,j
+k,l
+");
+    assert!(!wrk.path("6.csv").exists());
+}
+
+#[test]
+fn split_max_size_no_headers() {
+    let wrk = Workdir::new("split_max_size_no_headers");
+    wrk.create("in.csv", data(false));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--no-headers", "--max-size", "12"])
+       .arg(&wrk.path("."))
+       .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "0.csv", "\
+a,b
+c,d
+e,f
+");
+    split_eq!(wrk, "3.csv", "\
+g,h
+i,j
+k,l
+");
+}
+
+#[test]
+fn split_max_size_oversized_record() {
+    let wrk = Workdir::new("split_max_size_oversized_record");
+    wrk.create("in.csv", vec![
+        svec!["h1", "h2"],
+        svec!["a", "b"],
+        svec!["this record is too big", "for any chunk"],
+        svec!["c", "d"],
+    ]);
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--max-size", "14"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "0.csv", "\
+h1,h2
+a,b
+");
+    split_eq!(wrk, "1.csv", "\
+h1,h2
+this record is too big,for any chunk
+");
+    split_eq!(wrk, "2.csv", "\
+h1,h2
+c,d
+");
+}
+
+#[test]
+fn split_max_size_suffix() {
+    let wrk = Workdir::new("split_max_size_suffix");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--max-size", "1KiB"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "0.csv", "\
+h1,h2
+a,b
+c,d
+e,f
+g,h
+i,j
+k,l
+");
+    assert!(!wrk.path("1.csv").exists());
+}
+
+#[test]
+fn split_max_size_with_size() {
+    let wrk = Workdir::new("split_max_size_with_size");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "2", "--max-size", "14"])
+       .arg(&wrk.path("."))
+       .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_custom_filename_idx() {
+    let wrk = Workdir::new("split_custom_filename_idx");
+    wrk.create_indexed("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "2"])
+       .args(&["--filename", "prefix-{}.csv"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    assert!(wrk.path("prefix-0.csv").exists());
+    assert!(wrk.path("prefix-2.csv").exists());
+    assert!(wrk.path("prefix-4.csv").exists());
+}
+
+#[test]
+fn split_filename_subdirectory() {
+    let wrk = Workdir::new("split_filename_subdirectory");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "4"])
+       .args(&["--filename", "{}/part.csv"])
+       .arg(&wrk.path("out")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "out/0/part.csv", "\
+h1,h2
+a,b
+c,d
+e,f
+g,h
+");
+    split_eq!(wrk, "out/4/part.csv", "\
+h1,h2
+i,j
+k,l
+");
+}
+
+#[test]
+fn split_filename_subdirectory_idx() {
+    let wrk = Workdir::new("split_filename_subdirectory_idx");
+    wrk.create_indexed("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "4"])
+       .args(&["--filename", "{}/part.csv"])
+       .arg(&wrk.path("out")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "out/0/part.csv", "\
+h1,h2
+a,b
+c,d
+e,f
+g,h
+");
+    split_eq!(wrk, "out/4/part.csv", "\
+h1,h2
+i,j
+k,l
+");
+}
+
+#[test]
+fn split_filename_no_placeholder() {
+    let wrk = Workdir::new("split_filename_no_placeholder");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--filename", "chunk.csv"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_tab_default_filename() {
+    let wrk = Workdir::new("split_tab_default_filename");
+    wrk.create("in.csv", data(true));
+
+    // Each row is a single field as far as a tab delimiter is concerned.
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "4", "--delimiter", r"\t"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "0.tsv", "\
+h1,h2
+a,b
+c,d
+e,f
+g,h
+");
+    split_eq!(wrk, "4.tsv", "\
+h1,h2
+i,j
+k,l
+");
+    assert!(!wrk.path("0.csv").exists());
+}
+
+#[test]
+fn split_tab_default_filename_idx() {
+    let wrk = Workdir::new("split_tab_default_filename_idx");
+    wrk.create_indexed("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "4", "--delimiter", r"\t"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    assert!(wrk.path("0.tsv").exists());
+    assert!(wrk.path("4.tsv").exists());
+    assert!(!wrk.path("0.csv").exists());
+}
+
+#[test]
+fn split_filename_two_placeholders() {
+    let wrk = Workdir::new("split_filename_two_placeholders");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--filename", "{}-{}.csv"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_pad() {
+    let wrk = Workdir::new("split_pad");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "2", "--pad", "3"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "000.csv", "\
+h1,h2
+a,b
+c,d
+");
+    split_eq!(wrk, "002.csv", "\
+h1,h2
+e,f
+g,h
+");
+    split_eq!(wrk, "004.csv", "\
+h1,h2
+i,j
+k,l
+");
+    assert!(!wrk.path("0.csv").exists());
+}
+
+#[test]
+fn split_pad_idx() {
+    let wrk = Workdir::new("split_pad_idx");
+    wrk.create_indexed("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "2", "--pad", "3"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    assert!(wrk.path("000.csv").exists());
+    assert!(wrk.path("002.csv").exists());
+    assert!(wrk.path("004.csv").exists());
+    assert!(!wrk.path("0.csv").exists());
+}
+
+#[test]
+fn split_pad_too_small() {
+    let wrk = Workdir::new("split_pad_too_small");
+    let mut rows = data(true);
+    rows.extend(data(false));
+    wrk.create("in.csv", rows);
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "5", "--pad", "1"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_pad_too_small_idx() {
+    let wrk = Workdir::new("split_pad_too_small_idx");
+    let mut rows = data(true);
+    rows.extend(data(false));
+    wrk.create_indexed("in.csv", rows);
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "5", "--pad", "1"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+    assert!(!wrk.path("0.csv").exists());
+}
+
+#[test]
+fn split_pad_too_small_chunks() {
+    let wrk = Workdir::new("split_pad_too_small_chunks");
+    let mut rows = data(true);
+    rows.extend(data(false));
+    wrk.create("in.csv", rows);
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--chunks", "12", "--pad", "1"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+    assert!(!wrk.path("0.csv").exists());
+}
+
+#[test]
+fn split_chunks() {
+    let wrk = Workdir::new("split_chunks");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--chunks", "4"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "0.csv", "\
+h1,h2
+a,b
+c,d
+");
+    split_eq!(wrk, "2.csv", "\
+h1,h2
+e,f
+g,h
+");
+    split_eq!(wrk, "4.csv", "\
+h1,h2
+i,j
+");
+    split_eq!(wrk, "5.csv", "\
+h1,h2
+k,l
+");
+    assert!(!wrk.path("6.csv").exists());
+}
+
+#[test]
+fn split_chunks_idx() {
+    let wrk = Workdir::new("split_chunks_idx");
+    wrk.create_indexed("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--chunks", "4"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "0.csv", "\
+h1,h2
+a,b
+c,d
+");
+    split_eq!(wrk, "2.csv", "\
+h1,h2
+e,f
+g,h
+");
+    split_eq!(wrk, "4.csv", "\
+h1,h2
+i,j
+");
+    split_eq!(wrk, "5.csv", "\
+h1,h2
+k,l
+");
+    assert!(!wrk.path("6.csv").exists());
+}
+
+#[test]
+fn split_chunks_more_than_records() {
+    let wrk = Workdir::new("split_chunks_more_than_records");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--chunks", "7"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_chunks_stdin() {
+    let wrk = Workdir::new("split_chunks_stdin");
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--chunks", "2"]).arg(&wrk.path("."));
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_chunks_zero() {
+    let wrk = Workdir::new("split_chunks_zero");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--chunks", "0"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_chunks_with_size() {
+    let wrk = Workdir::new("split_chunks_with_size");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--chunks", "2", "--size", "2"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+fn gunzip(wrk: &Workdir, path: &str) -> String {
+    use std::fs;
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    let file = fs::File::open(&wrk.path(path)).unwrap();
+    let mut s = String::new();
+    GzDecoder::new(file).read_to_string(&mut s).unwrap();
+    s
+}
+
+#[test]
+fn split_gzip() {
+    let wrk = Workdir::new("split_gzip");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "4", "--gzip"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    assert_eq!(gunzip(&wrk, "0.csv.gz"), "\
+h1,h2
+a,b
+c,d
+e,f
+g,h
+");
+    assert_eq!(gunzip(&wrk, "4.csv.gz"), "\
+h1,h2
+i,j
+k,l
+");
+    assert!(!wrk.path("0.csv").exists());
+}
+
+#[test]
+fn split_gzip_idx() {
+    let wrk = Workdir::new("split_gzip_idx");
+    wrk.create_indexed("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "4", "--gzip"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    assert_eq!(gunzip(&wrk, "0.csv.gz"), "\
+h1,h2
+a,b
+c,d
+e,f
+g,h
+");
+    assert_eq!(gunzip(&wrk, "4.csv.gz"), "\
+h1,h2
+i,j
+k,l
+");
+}
+
+#[test]
+fn split_gzip_filename() {
+    let wrk = Workdir::new("split_gzip_filename");
+    wrk.create("in.csv", data(false));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--no-headers", "--size", "3"])
+       .args(&["--filename", "part-{}.tsv.gz"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    assert_eq!(gunzip(&wrk, "part-0.tsv.gz"), "a\tb\nc\td\ne\tf\n");
+    assert_eq!(gunzip(&wrk, "part-3.tsv.gz"), "g\th\ni\tj\nk\tl\n");
+}
+
+#[test]
+fn split_gzip_max_size() {
+    let wrk = Workdir::new("split_gzip_max_size");
+    wrk.create("in.csv", data(true));
+
+    // --max-size limits the uncompressed size of each chunk.
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--max-size", "14", "--gzip"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    assert_eq!(gunzip(&wrk, "0.csv.gz"), "h1,h2\na,b\nc,d\n");
+    assert_eq!(gunzip(&wrk, "2.csv.gz"), "h1,h2\ne,f\ng,h\n");
+    assert_eq!(gunzip(&wrk, "4.csv.gz"), "h1,h2\ni,j\nk,l\n");
+}
+
+#[test]
+fn split_gzip_round_trip() {
+    let wrk = Workdir::new("split_gzip_round_trip");
+    wrk.create("in.csv", data(true));
+
+    for level in &["0", "9"] {
+        let outdir = format!("level{}", level);
+        let mut cmd = wrk.command("split");
+        cmd.args(&["--size", "2", "--gzip", "--compress-level", level])
+           .arg(&wrk.path(&outdir)).arg("in.csv");
+        wrk.run(&mut cmd);
+
+        let mut got: Vec<String> = vec![];
+        for start in &["0", "2", "4"] {
+            let chunk = gunzip(&wrk, &format!("{}/{}.csv.gz", outdir, start));
+            let mut lines = chunk.lines();
+            assert_eq!(lines.next(), Some("h1,h2"));
+            got.extend(lines.map(|line| line.to_owned()));
+        }
+        let expected: Vec<String> = data(false)
+            .into_iter()
+            .map(|row| row.join(","))
+            .collect();
+        assert_eq!(got, expected);
+    }
+}
+
+#[test]
+fn split_gzip_bad_level() {
+    let wrk = Workdir::new("split_gzip_bad_level");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--gzip", "--compress-level", "10"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_max_size_idx() {
+    let wrk = Workdir::new("split_max_size_idx");
+    wrk.create_indexed("in.csv", data(true));
+
+    // An index is ignored, since chunk boundaries depend on record sizes.
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--max-size", "14"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+    let stderr = String::from_utf8(cmd.output().unwrap().stderr).unwrap();
+    assert!(stderr.contains("index is not used"), "{}", stderr);
+
+    split_eq!(wrk, "0.csv", "\
+h1,h2
+a,b
+c,d
+");
+    split_eq!(wrk, "2.csv", "\
+h1,h2
+e,f
+g,h
+");
+    split_eq!(wrk, "4.csv", "\
+h1,h2
+i,j
+k,l
+");
+}
+
+#[test]
+fn split_size_bytes() {
+    let wrk = Workdir::new("split_size_bytes");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size-bytes", "14"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "0.csv", "\
+h1,h2
+a,b
+c,d
+");
+    split_eq!(wrk, "4.csv", "\
+h1,h2
+i,j
+k,l
+");
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size-bytes", "14", "--max-size", "14"])
+       .arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_max_size_lowercase_suffix() {
+    let wrk = Workdir::new("split_max_size_lowercase_suffix");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--max-size", "1kb"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.run(&mut cmd);
+    assert!(wrk.path("0.csv").exists());
+    assert!(!wrk.path("1.csv").exists());
+}
+
+#[test]
+fn split_max_size_bad_suffix() {
+    let wrk = Workdir::new("split_max_size_bad_suffix");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--max-size", "10 parsecs"]).arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn split_idx_jobs() {
+    for jobs in &["0", "1", "100"] {
+        let wrk = Workdir::new("split_idx_jobs");
+        wrk.create_indexed("in.csv", data(true));
+
+        let mut cmd = wrk.command("split");
+        cmd.args(&["--size", "4", "--jobs", jobs])
+           .arg(&wrk.path(".")).arg("in.csv");
+        wrk.run(&mut cmd);
+
+        split_eq!(wrk, "0.csv", "\
+h1,h2
+a,b
+c,d
+e,f
+g,h
+");
+        split_eq!(wrk, "4.csv", "\
+h1,h2
+i,j
+k,l
+");
+    }
+}
+
+#[test]
+fn split_manifest() {
+    let wrk = Workdir::new("split_manifest");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "4", "--manifest", "manifest.csv"])
+       .arg("out").arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "manifest.csv", "\
+path,start,count
+out/0.csv,0,4
+out/4.csv,4,2
+");
+    assert!(!wrk.path("manifest.csv.tmp").exists());
+}
+
+#[test]
+fn split_manifest_tsv() {
+    let wrk = Workdir::new("split_manifest_tsv");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "4", "--manifest", "manifest.tsv"])
+       .arg("out").arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "manifest.tsv", "\
+path\tstart\tcount
+out/0.csv\t0\t4
+out/4.csv\t4\t2
+");
+}
+
+#[test]
+fn split_manifest_idx() {
+    let wrk = Workdir::new("split_manifest_idx");
+    let mut rows = data(true);
+    rows.extend(data(false));
+    wrk.create_indexed("in.csv", rows);
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--size", "5", "--jobs", "4", "--manifest", "manifest.csv"])
+       .arg("out").arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "manifest.csv", "\
+path,start,count
+out/0.csv,0,5
+out/5.csv,5,5
+out/10.csv,10,2
+");
+}
+
+#[test]
+fn split_manifest_chunks() {
+    let wrk = Workdir::new("split_manifest_chunks");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--chunks", "4", "--manifest", "manifest.csv"])
+       .arg("out").arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "manifest.csv", "\
+path,start,count
+out/0.csv,0,2
+out/2.csv,2,2
+out/4.csv,4,1
+out/5.csv,5,1
+");
+}
+
+#[test]
+fn split_manifest_max_size() {
+    let wrk = Workdir::new("split_manifest_max_size");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("split");
+    cmd.args(&["--max-size", "18", "--manifest", "manifest.csv"])
+       .arg("out").arg("in.csv");
+    wrk.run(&mut cmd);
+
+    split_eq!(wrk, "manifest.csv", "\
+path,start,count
+out/0.csv,0,3
+out/3.csv,3,3
+");
+}
+
+#[test]
+fn split_progress_not_tty() {
+    let wrk = Workdir::new("split_progress_not_tty");
+    wrk.create_indexed("in.csv", data(true));
+    for &(dir, extra) in &[("plain", None), ("progress", Some("--progress"))] {
+        let mut cmd = wrk.command("split");
+        cmd.args(&["--size", "4"]).args(extra.iter())
+           .arg(&wrk.path(dir)).arg("in.csv");
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        assert!(output.stderr.is_empty());
+    }
+    for name in &["0.csv", "4.csv"] {
+        let plain = wrk.from_str::<String>(&wrk.path("plain").join(name));
+        let progress =
+            wrk.from_str::<String>(&wrk.path("progress").join(name));
+        assert_eq!(plain, progress);
+    }
+}
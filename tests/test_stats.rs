@@ -1,5 +1,6 @@
 use std::borrow::ToOwned;
 use std::cmp;
+use std::fs;
 use std::process;
 
 use workdir::Workdir;
@@ -91,6 +92,10 @@ fn setup<S>(name: S, rows: &[&str], headers: bool,
 fn get_field_value(wrk: &Workdir, cmd: &mut process::Command, field: &str)
                   -> String {
     if field == "median" { cmd.arg("--median"); }
+    if field == "q1" || field == "q3" { cmd.arg("--quartiles"); }
+    if field.starts_with("p") && field[1..].parse::<f64>().is_ok() {
+        cmd.arg("--percentiles").arg(&field[1..]);
+    }
     if field == "cardinality" { cmd.arg("--cardinality"); }
     if field == "mode" { cmd.arg("--mode"); }
 
@@ -127,7 +132,7 @@ stats_tests!(stats_infer_null_int_float_unicode, "type",
 stats_tests!(stats_no_mean, "mean", &["a"], "");
 stats_tests!(stats_no_stddev, "stddev", &["a"], "");
 stats_tests!(stats_no_median, "median", &["a"], "");
-stats_tests!(stats_no_mode, "mode", &["a", "b"], "N/A");
+stats_tests!(stats_mode_tie, "mode", &["b", "a"], "a");
 
 stats_tests!(stats_null_mean, "mean", &[""], "");
 stats_tests!(stats_null_stddev, "stddev", &[""], "");
@@ -170,16 +175,54 @@ stats_tests!(stats_stddev_null, "stddev", &["1", "2", "3", ""],
 stats_tests!(stats_mean_mix, "mean", &["5", "15.1", "9.9"], "10");
 stats_tests!(stats_stddev_mix, "stddev", &["1", "2.1", "2.9"],
              "0.7788880963698614");
+stats_tests!(stats_variance, "variance", &["1", "2", "3", "4"], "1.25");
+stats_tests!(stats_stddev_single, "stddev", &["5"], "0");
 
 stats_tests!(stats_cardinality, "cardinality", &["a", "b", "a"], "2");
 stats_tests!(stats_mode, "mode", &["a", "b", "a"], "a");
 stats_tests!(stats_mode_null, "mode", &["", "a", "b", "a"], "a");
+stats_tests!(stats_mode_tie_many, "mode",
+             &["c", "b", "c", "a", "b", "d"], "b");
+stats_tests!(stats_mode_single, "mode", &["z"], "z");
+stats_tests!(stats_cardinality_null, "cardinality", &["a", "", "a", ""], "2");
+stats_tests!(stats_cardinality_repeated, "cardinality",
+             &["a", "b", "c", "a", "b", "c", "a", "b", "c", "a", "b", "c",
+               "a", "b", "c", "a", "b", "c", "a", "b", "c", "a", "b", "c"],
+             "3");
 stats_tests!(stats_median, "median", &["1", "2", "3"], "2");
 stats_tests!(stats_median_null, "median", &["", "1", "2", "3"], "2");
 stats_tests!(stats_median_even, "median", &["1", "2", "3", "4"], "2.5");
 stats_tests!(stats_median_even_null, "median",
              &["", "1", "2", "3", "4"], "2.5");
 stats_tests!(stats_median_mix, "median", &["1", "2.5", "3"], "2.5");
+stats_tests!(stats_median_single, "median", &["7"], "7");
+stats_tests!(stats_median_same, "median", &["4", "4", "4", "4"], "4");
+stats_tests!(stats_median_unicode, "median", &["1", "a", "3"], "");
+
+stats_tests!(stats_q1, "q1", &["1", "2", "3", "4", "5", "6", "7"], "2");
+stats_tests!(stats_q3, "q3", &["1", "2", "3", "4", "5", "6", "7"], "6");
+stats_tests!(stats_q1_even, "q1", &["1", "2", "3", "4", "5", "6"], "2");
+stats_tests!(stats_q3_even, "q3", &["6", "5", "4", "3", "2", "1"], "5");
+stats_tests!(stats_q1_split, "q1", &["1", "2", "3", "4"], "1.5");
+stats_tests!(stats_q3_split, "q3", &["4", "3", "2", "1"], "3.5");
+stats_tests!(stats_q1_single, "q1", &["7"], "7");
+stats_tests!(stats_q3_single, "q3", &["7"], "7");
+stats_tests!(stats_q1_same, "q1", &["4", "4", "4", "4", "4"], "4");
+stats_tests!(stats_q3_same, "q3", &["4", "4", "4", "4", "4"], "4");
+stats_tests!(stats_q1_null, "q1", &["", "1", "2", "", "3"], "1");
+stats_tests!(stats_q3_null, "q3", &["", "1", "2", "", "3"], "3");
+stats_tests!(stats_q1_unicode, "q1", &["1", "a", "3"], "");
+stats_tests!(stats_q3_null_only, "q3", &[""], "");
+
+stats_tests!(stats_p0, "p0", &["3", "1", "4", "2"], "1");
+stats_tests!(stats_p100, "p100", &["3", "1", "4", "2"], "4");
+stats_tests!(stats_p50, "p50", &["3", "1", "4", "2"], "2.5");
+stats_tests!(stats_p25, "p25", &["3", "1", "4", "2"], "1.75");
+stats_tests!(stats_p90, "p90", &["10", "20", "30", "40", "50"], "46");
+stats_tests!(stats_p99_9, "p99.9", &["0", "1000"], "999");
+stats_tests!(stats_p95_single, "p95", &["7"], "7");
+stats_tests!(stats_p95_null, "p95", &["", "10", "", "20"], "19.5");
+stats_tests!(stats_p95_unicode, "p95", &["1", "a"], "");
 
 mod stats_infer_nothing {
     // Only test CSV data with headers.
@@ -213,3 +256,620 @@ mod stats_header_fields {
     stats_test_headers!(stats_header_field_name, "field", &["a"], "header");
     stats_test_no_headers!(stats_header_no_field_name, "field", &["a"], "0");
 }
+
+#[test]
+fn stats_percentiles_columns() {
+    let wrk = Workdir::new("stats_percentiles_columns");
+    wrk.create("in.csv", vec![
+        svec!["n"], svec!["1"], svec!["2"], svec!["3"], svec!["4"], svec!["5"],
+    ]);
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--percentiles", "50, 5,99.9"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(&got[0][11..], &*svec!["p50", "p5", "p99.9"]);
+    assert_eq!(&got[1][11..], &*svec!["3", "1.2", "4.996"]);
+}
+
+#[test]
+fn stats_percentiles_invalid() {
+    let wrk = Workdir::new("stats_percentiles_invalid");
+    wrk.create("in.csv", vec![svec!["n"], svec!["1"]]);
+    for bad in &["-1", "100.5", "abc", "50,", "NaN"] {
+        let mut cmd = wrk.command("stats");
+        cmd.args(&["--percentiles", bad]).arg("in.csv");
+        wrk.assert_err(&mut cmd);
+    }
+}
+
+fn stats_dates(name: &str, rows: Vec<Vec<String>>, args: &[&str])
+              -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", rows);
+    let mut cmd = wrk.command("stats");
+    cmd.args(args).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // Only keep the field, type, min and max.
+    got.into_iter().map(|r| vec![
+        r[0].clone(), r[1].clone(), r[3].clone(), r[4].clone(),
+    ]).collect()
+}
+
+#[test]
+fn stats_infer_dates() {
+    let got = stats_dates("stats_infer_dates", vec![
+        svec!["d", "dt", "mixed", "partial"],
+        svec!["2017-11-05", "2017-01-31T23:00:00+01:00", "2017-01-01", "a"],
+        svec!["2017-03-01", "2017-01-31 22:30:00", "2017-01-01 10:00:00", ""],
+        svec!["", "2017-01-31T22:45:00.5Z", "2016-12-31", "2017-01-01"],
+        svec!["2016-12-31", "2017-01-31T22:40:00", "2017-02-01", "2017-01-02"],
+    ], &["--infer-dates"]);
+    assert_eq!(got, vec![
+        svec!["field", "type", "min", "max"],
+        svec!["d", "Date", "2016-12-31", "2017-11-05"],
+        // 23:00 at UTC+1 is the earliest.
+        svec!["dt", "DateTime",
+              "2017-01-31T23:00:00+01:00", "2017-01-31T22:45:00.5Z"],
+        svec!["mixed", "DateTime", "2016-12-31", "2017-02-01"],
+        svec!["partial", "Unicode", "2017-01-01", "a"],
+    ]);
+}
+
+#[test]
+fn stats_infer_dates_off_by_default() {
+    let got = stats_dates("stats_infer_dates_off_by_default", vec![
+        svec!["d"], svec!["2017-11-05"], svec!["2016-12-31"],
+    ], &[]);
+    assert_eq!(got[1], svec!["d", "Unicode", "2016-12-31", "2017-11-05"]);
+}
+
+#[test]
+fn stats_infer_dates_numbers_mixed() {
+    let got = stats_dates("stats_infer_dates_numbers_mixed", vec![
+        svec!["d"], svec!["2017-11-05"], svec!["5"],
+    ], &["--infer-dates"]);
+    assert_eq!(got[1], svec!["d", "Unicode", "2017-11-05", "5"]);
+}
+
+#[test]
+fn stats_dates_whitelist() {
+    let got = stats_dates("stats_dates_whitelist", vec![
+        svec!["a", "b"],
+        svec!["2017-11-05", "2017-11-05"],
+    ], &["--dates-whitelist", "b"]);
+    assert_eq!(got, vec![
+        svec!["field", "type", "min", "max"],
+        svec!["a", "Unicode", "2017-11-05", "2017-11-05"],
+        svec!["b", "Date", "2017-11-05", "2017-11-05"],
+    ]);
+}
+
+#[test]
+fn stats_date_formats() {
+    let got = stats_dates("stats_date_formats", vec![
+        svec!["d"], svec!["05/11/2017"], svec!["31/12/2016 10:00"],
+        svec!["2017-01-01"],
+    ], &["--infer-dates", "--date-formats", "%d/%m/%Y;%d/%m/%Y %H:%M"]);
+    // Only the given formats are tried.
+    assert_eq!(got[1],
+               svec!["d", "Unicode", "05/11/2017", "31/12/2016 10:00"]);
+}
+
+#[test]
+fn stats_date_formats_chronological() {
+    let got = stats_dates("stats_date_formats_chronological", vec![
+        svec!["d"], svec!["05/11/2017"], svec!["31/12/2016 10:00"],
+    ], &["--infer-dates", "--date-formats", "%d/%m/%Y;%d/%m/%Y %H:%M"]);
+    assert_eq!(got[1],
+               svec!["d", "DateTime", "31/12/2016 10:00", "05/11/2017"]);
+}
+
+#[test]
+fn stats_date_formats_invalid() {
+    let wrk = Workdir::new("stats_date_formats_invalid");
+    wrk.create("in.csv", vec![svec!["d"], svec!["2017-01-01"]]);
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--date-formats", "%Y-%Q"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+fn stats_nulls_data(name: &str, args: &[&str]) -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", vec![
+        svec!["n", "s"],
+        svec!["1", "a"],
+        svec!["N/A", "NULL"],
+        svec!["", "null"],
+        svec!["3", "-"],
+        svec!["n/a", "b"],
+    ]);
+    let mut cmd = wrk.command("stats");
+    cmd.args(args).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // Only keep the field, type, sum, min, max and nullcount.
+    got.into_iter().map(|r| vec![
+        r[0].clone(), r[1].clone(), r[2].clone(), r[3].clone(), r[4].clone(),
+        r[10].clone(),
+    ]).collect()
+}
+
+#[test]
+fn stats_nullcount() {
+    let got = stats_nulls_data("stats_nullcount", &[]);
+    assert_eq!(got, vec![
+        svec!["field", "type", "sum", "min", "max", "nullcount"],
+        svec!["n", "Unicode", "", "1", "n/a", "1"],
+        svec!["s", "Unicode", "", "-", "null", "0"],
+    ]);
+}
+
+#[test]
+fn stats_null_value() {
+    let got = stats_nulls_data("stats_null_value", &[
+        "--null-value", "N/A", "--null-value", "NULL", "--null-value", "-",
+    ]);
+    assert_eq!(got, vec![
+        svec!["field", "type", "sum", "min", "max", "nullcount"],
+        // 'n/a' doesn't match exactly.
+        svec!["n", "Unicode", "", "1", "n/a", "2"],
+        svec!["s", "Unicode", "", "a", "null", "2"],
+    ]);
+}
+
+#[test]
+fn stats_null_value_ignore_case() {
+    let got = stats_nulls_data("stats_null_value_ignore_case", &[
+        "--null-value", "N/A", "--null-value", "NULL", "--null-value", "-",
+        "--ignore-case",
+    ]);
+    assert_eq!(got, vec![
+        svec!["field", "type", "sum", "min", "max", "nullcount"],
+        svec!["n", "Integer", "4", "1", "3", "3"],
+        svec!["s", "Unicode", "", "a", "b", "3"],
+    ]);
+}
+
+#[test]
+fn stats_median_percentiles_mode() {
+    let wrk = Workdir::new("stats_median_percentiles_mode");
+    wrk.create("in.csv", vec![
+        svec!["n", "s"],
+        svec!["7", "b"],
+        svec!["1", "a"],
+        svec!["3", "b"],
+        svec!["3", "c"],
+        svec!["9", "b"],
+    ]);
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--median", "--mode", "--percentiles", "25,50,75,95"])
+       .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(&got[0][11..],
+               &*svec!["median", "p25", "p50", "p75", "p95", "mode"]);
+    assert_eq!(&got[1][11..], &*svec!["3", "3", "3", "7", "8.6", "3"]);
+    // Percentiles of a non-numeric column are empty, but it still has a mode.
+    assert_eq!(&got[2][11..], &*svec!["", "", "", "", "", "b"]);
+}
+
+fn stats_json(name: &str, use_index: bool) -> String {
+    let wrk = Workdir::new(name);
+    let rows = vec![
+        svec!["n", "s", "e"],
+        svec!["1", "a\"b", ""],
+        svec!["2", "c", ""],
+        svec!["", "c", ""],
+    ];
+    if use_index {
+        wrk.create_indexed("in.csv", rows);
+    } else {
+        wrk.create("in.csv", rows);
+    }
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--json", "--mode", "--select", "n,s,e"]).arg("in.csv");
+    wrk.stdout(&mut cmd)
+}
+
+#[test]
+fn stats_json_output() {
+    let expected = "\
+{\"field\":\"n\",\"type\":\"Integer\",\"sum\":3,\"min\":1,\"max\":2,\
+\"min_length\":0,\"max_length\":1,\"mean\":1.5,\"stddev\":0.5,\
+\"variance\":0.25,\"nullcount\":1,\"mode\":null}
+{\"field\":\"s\",\"type\":\"Unicode\",\"sum\":null,\"min\":\"a\\\"b\",\
+\"max\":\"c\",\"min_length\":1,\"max_length\":3,\"mean\":null,\
+\"stddev\":null,\"variance\":null,\"nullcount\":0,\"mode\":\"c\"}
+{\"field\":\"e\",\"type\":\"NULL\",\"sum\":null,\"min\":null,\"max\":null,\
+\"min_length\":0,\"max_length\":0,\"mean\":null,\"stddev\":null,\
+\"variance\":null,\"nullcount\":3,\"mode\":null}";
+    assert_eq!(stats_json("stats_json_output", false), expected);
+    assert_eq!(stats_json("stats_json_output_index", true), expected);
+}
+
+fn stats_profile(name: &str, args: &[&str]) -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", vec![
+        svec!["n", "s"],
+        svec!["1", "a"],
+        svec!["  ", "b"],
+        svec!["2", ""],
+        svec!["", "a"],
+        svec!["1", "\t"],
+        svec!["3", "c"],
+    ]);
+    let mut cmd = wrk.command("stats");
+    cmd.arg("--cardinality").args(args).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // Only keep the field, type, nullcount and cardinality.
+    got.into_iter().map(|r| vec![
+        r[0].clone(), r[1].clone(), r[10].clone(), r[11].clone(),
+    ]).collect()
+}
+
+#[test]
+fn stats_profile_cardinality_nullcount() {
+    let got = stats_profile("stats_profile_cardinality_nullcount", &[]);
+    assert_eq!(got, vec![
+        svec!["field", "type", "nullcount", "cardinality"],
+        svec!["n", "Unicode", "1", "5"],
+        svec!["s", "Unicode", "1", "5"],
+    ]);
+}
+
+#[test]
+fn stats_profile_whitespace() {
+    let got = stats_profile("stats_profile_whitespace", &["--whitespace"]);
+    assert_eq!(got, vec![
+        svec!["field", "type", "nullcount", "cardinality"],
+        svec!["n", "Integer", "2", "4"],
+        svec!["s", "Unicode", "2", "4"],
+    ]);
+}
+
+fn group_data() -> Vec<Vec<String>> {
+    vec![
+        svec!["region", "kind", "latency"],
+        svec!["us", "a", "10"],
+        svec!["eu", "a", "20"],
+        svec!["us", "b", "30"],
+        svec!["us", "a", "50"],
+        svec!["eu", "a", "abc"],
+    ]
+}
+
+#[test]
+fn stats_group_by() {
+    let wrk = Workdir::new("stats_group_by");
+    wrk.create("in.csv", group_data());
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--group-by", "region", "--select", "latency"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let got: Vec<Vec<String>> =
+        got.into_iter().map(|r| r[..6].to_vec()).collect();
+    assert_eq!(got, vec![
+        svec!["region", "field", "type", "sum", "min", "max"],
+        svec!["us", "latency", "Integer", "90", "10", "50"],
+        svec!["eu", "latency", "Unicode", "", "20", "abc"],
+    ]);
+}
+
+#[test]
+fn stats_group_by_many() {
+    let wrk = Workdir::new("stats_group_by_many");
+    wrk.create_indexed("in.csv", group_data());
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["-g", "region,kind", "--select", "latency"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let got: Vec<Vec<String>> =
+        got.into_iter().map(|r| r[..5].to_vec()).collect();
+    assert_eq!(got, vec![
+        svec!["region", "kind", "field", "type", "sum"],
+        svec!["us", "a", "latency", "Integer", "60"],
+        svec!["eu", "a", "latency", "Unicode", ""],
+        svec!["us", "b", "latency", "Integer", "30"],
+    ]);
+}
+
+#[test]
+fn stats_group_by_json() {
+    let wrk = Workdir::new("stats_group_by_json");
+    wrk.create("in.csv", group_data());
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["-g", "region", "-s", "latency", "--json"]).arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let lines: Vec<&str> = got.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(
+        "{\"region\":\"us\",\"field\":\"latency\",\"type\":\"Integer\""));
+    assert!(lines[1].starts_with(
+        "{\"region\":\"eu\",\"field\":\"latency\",\"type\":\"Unicode\""));
+}
+
+#[test]
+fn stats_group_by_limit() {
+    let wrk = Workdir::new("stats_group_by_limit");
+    wrk.create("in.csv", group_data());
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["-g", "region", "--limit-groups", "2"]).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["-g", "region,kind", "--limit-groups", "2"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+fn stats_jobs(wrk: &Workdir, jobs: &str) -> Vec<Vec<String>> {
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--everything", "--jobs", jobs]).arg("in.csv");
+    wrk.read_stdout(&mut cmd)
+}
+
+#[test]
+fn stats_parallel_same_as_sequential() {
+    let wrk = Workdir::new("stats_parallel_same_as_sequential");
+    let mut rows = vec![svec!["int", "float", "sparse", "text"]];
+    for i in 0..24 {
+        let sparse =
+            if i < 3 || i >= 21 { i.to_string() } else { "".to_owned() };
+        rows.push(vec![
+            (i * 7 % 5).to_string(),
+            format!("{}.25", i),
+            sparse,
+            format!("t{}", i % 4),
+        ]);
+    }
+    wrk.create_indexed("in.csv", rows);
+
+    // The mean, standard deviation and variance are merged from each
+    // chunk, so they may differ from the sequential results in the last
+    // few bits.
+    let round = |rows: Vec<Vec<String>>| -> Vec<Vec<String>> {
+        rows.into_iter().map(|row| row.into_iter().enumerate().map(|(i, v)| {
+            match v.parse::<f64>() {
+                Ok(n) if i >= 7 && i <= 9 => format!("{:.10}", n),
+                _ => v,
+            }
+        }).collect()).collect()
+    };
+    let sequential = round(stats_jobs(&wrk, "1"));
+    for jobs in &["2", "3", "8"] {
+        let parallel = round(stats_jobs(&wrk, jobs));
+        assert_eq!(parallel, sequential, "jobs: {}", jobs);
+    }
+}
+
+fn stats_variance(name: &str, args: &[&str]) -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", vec![
+        svec!["a", "b"],
+        svec!["1", "5"],
+        svec!["2", ""],
+        svec!["3", ""],
+        svec!["4", ""],
+    ]);
+    let mut cmd = wrk.command("stats");
+    cmd.args(args).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // Only keep the field, stddev and variance.
+    got.into_iter().map(|r| vec![
+        r[0].clone(), r[8].clone(), r[9].clone(),
+    ]).collect()
+}
+
+#[test]
+fn stats_population_variance() {
+    let got = stats_variance("stats_population_variance", &[]);
+    assert_eq!(got, vec![
+        svec!["field", "stddev", "variance"],
+        svec!["a", "1.118033988749895", "1.25"],
+        svec!["b", "0", "0"],
+    ]);
+}
+
+#[test]
+fn stats_sample_variance() {
+    let got = stats_variance("stats_sample_variance", &["--sample"]);
+    assert_eq!(got, vec![
+        svec!["field", "stddev", "variance"],
+        svec!["a", "1.2909944487358056", "1.6666666666666667"],
+        svec!["b", "", ""],
+    ]);
+}
+
+#[test]
+fn stats_length_null_value() {
+    let wrk = Workdir::new("stats_length_null_value");
+    wrk.create("in.csv", vec![
+        svec!["n", "e"],
+        svec!["1", ""],
+        svec!["N/A", ""],
+        svec!["22", ""],
+    ]);
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--null-value", "N/A"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // Lengths are of the raw fields, even if they are NULL.
+    let got: Vec<Vec<String>> =
+        got.into_iter().map(|r| r[..7].to_vec()).collect();
+    assert_eq!(got, vec![
+        svec!["field", "type", "sum", "min", "max",
+              "min_length", "max_length"],
+        svec!["n", "Integer", "23", "1", "22", "1", "3"],
+        svec!["e", "NULL", "", "", "", "0", "0"],
+    ]);
+}
+
+#[test]
+fn stats_length_no_rows() {
+    let wrk = Workdir::new("stats_length_no_rows");
+    wrk.create("in.csv", vec![svec!["n"]]);
+    let mut cmd = wrk.command("stats");
+    cmd.arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(&got[1][5..7], &*svec!["", ""]);
+}
+
+stats_tests!(stats_sum_i64_max, "sum",
+             &["9223372036854775807", "9223372036854775807", "2"],
+             "18446744073709551616");
+stats_tests!(stats_sum_i64_min, "sum",
+             &["-9223372036854775808", "-9223372036854775808", "-1"],
+             "-18446744073709551617");
+stats_tests!(stats_sum_i64_cancel, "sum",
+             &["9223372036854775807", "1", "-9223372036854775807"], "1");
+
+#[test]
+fn stats_sum_exact_parallel() {
+    let wrk = Workdir::new("stats_sum_exact_parallel");
+    let mut rows = vec![svec!["n"]];
+    for _ in 0..10 {
+        rows.push(svec!["9223372036854775807"]);
+    }
+    wrk.create_indexed("in.csv", rows);
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--jobs", "4"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][2], "92233720368547758070");
+}
+
+fn stats_histogram(name: &str, nbins: &str) -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name);
+    let mut rows = vec![svec!["n", "same", "s"]];
+    for i in 1..11 {
+        rows.push(vec![i.to_string(), "5".to_owned(), "a".to_owned()]);
+    }
+    wrk.create_indexed("in.csv", rows);
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--histogram", nbins]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // Only keep the field and histogram.
+    got.into_iter().map(|r| vec![r[0].clone(), r[11].clone()]).collect()
+}
+
+#[test]
+fn stats_histogram_bins() {
+    let got = stats_histogram("stats_histogram_bins", "3");
+    assert_eq!(got, vec![
+        svec!["field", "histogram"],
+        svec!["n", "3|3|4"],
+        svec!["same", "10|0|0"],
+        svec!["s", ""],
+    ]);
+}
+
+#[test]
+fn stats_histogram_one_bin() {
+    let got = stats_histogram("stats_histogram_one_bin", "1");
+    assert_eq!(got[1], svec!["n", "10"]);
+}
+
+#[test]
+fn stats_histogram_zero_bins() {
+    let wrk = Workdir::new("stats_histogram_zero_bins");
+    wrk.create("in.csv", vec![svec!["n"], svec!["1"]]);
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--histogram", "0"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+fn stats_spill_data() -> Vec<Vec<String>> {
+    let mut rows = vec![svec!["n", "f", "s"]];
+    for i in 0..200 {
+        rows.push(vec![
+            ((i * 37) % 101).to_string(),
+            format!("{}.5", (i * 13) % 17),
+            format!("v{}", (i * 7) % 23),
+        ]);
+    }
+    rows
+}
+
+fn stats_spill(wrk: &Workdir, args: &[&str]) -> String {
+    let tmp = wrk.path("tmp");
+    let _ = fs::create_dir(&tmp);
+    let mut cmd = wrk.command("stats");
+    cmd.env("TMPDIR", &tmp)
+       .args(&["--everything", "--percentiles", "10,99", "--histogram", "4"])
+       .args(args)
+       .arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    // Every temporary file must have been removed.
+    assert_eq!(fs::read_dir(&tmp).unwrap().count(), 0);
+    got
+}
+
+#[test]
+fn stats_memory_limit() {
+    let wrk = Workdir::new("stats_memory_limit");
+    wrk.create("in.csv", stats_spill_data());
+    let expected = stats_spill(&wrk, &[]);
+    assert_eq!(stats_spill(&wrk, &["--memory-limit", "100"]), expected);
+    assert_eq!(stats_spill(&wrk, &["--memory-limit", "1"]), expected);
+}
+
+#[test]
+fn stats_memory_limit_many_spills() {
+    // With a limit of 1 byte, every value is spilled on its own, so this
+    // makes many more spills than are merged at once.
+    let wrk = Workdir::new("stats_memory_limit_many_spills");
+    let mut rows = vec![svec!["n", "s"]];
+    for i in 0..2000 {
+        rows.push(vec![((i * 37) % 1009).to_string(), format!("v{}", i % 97)]);
+    }
+    wrk.create("in.csv", rows);
+    let expected = stats_spill(&wrk, &[]);
+    assert_eq!(stats_spill(&wrk, &["--memory-limit", "1"]), expected);
+}
+
+#[test]
+fn stats_memory_limit_parallel() {
+    let wrk = Workdir::new("stats_memory_limit_parallel");
+    wrk.create_indexed("in.csv", stats_spill_data());
+    let expected = stats_spill(&wrk, &["--jobs", "4"]);
+    let got = stats_spill(&wrk, &["--jobs", "4", "--memory-limit", "200"]);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn stats_memory_limit_cleanup_on_error() {
+    let wrk = Workdir::new("stats_memory_limit_cleanup_on_error")
+        .flexible(true);
+    let mut rows = stats_spill_data();
+    rows.push(svec!["1"]);
+    wrk.create("in.csv", rows);
+    let tmp = wrk.path("tmp");
+    fs::create_dir(&tmp).unwrap();
+    let mut cmd = wrk.command("stats");
+    cmd.env("TMPDIR", &tmp).args(&["--median", "--memory-limit", "100"])
+       .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+    assert_eq!(fs::read_dir(&tmp).unwrap().count(), 0);
+}
+
+#[test]
+fn stats_memory_limit_zero() {
+    let wrk = Workdir::new("stats_memory_limit_zero");
+    wrk.create("in.csv", stats_spill_data());
+    let mut cmd = wrk.command("stats");
+    cmd.args(&["--memory-limit", "0"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn stats_progress_not_tty() {
+    for &use_index in &[false, true] {
+        let rows = &["1", "2", "3", "a"];
+        let (_wrk, mut cmd) =
+            setup("stats_progress_not_tty", rows, true, use_index, false);
+        let plain = cmd.output().unwrap();
+        cmd.arg("--progress");
+        let progress = cmd.output().unwrap();
+        assert!(progress.status.success());
+        assert!(progress.stderr.is_empty());
+        assert_eq!(plain.stdout, progress.stdout);
+    }
+}
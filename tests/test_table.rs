@@ -23,3 +23,83 @@ abcdefg  a    a
 a        abc  z\
 ")
 }
+
+#[test]
+fn table_condense() {
+    let wrk = Workdir::new("table_condense");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("table");
+    cmd.args(&["--condense", "3"]).arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(&*got, "\
+h1      h2   h3
+abc...  a    a
+a       abc  z\
+")
+}
+
+#[test]
+fn table_numbers_right_aligned() {
+    let wrk = Workdir::new("table_numbers_right_aligned");
+    wrk.create("in.csv", vec![
+        svec!["name", "count", "note"],
+        svec!["a", "5", "1"],
+        svec!["bcd", "-12.5", "x"],
+        svec!["e", "", "2"],
+        svec!["f", "1000", "3"],
+    ]);
+
+    let mut cmd = wrk.command("table");
+    cmd.arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(&*got, "\
+name  count  note
+a         5  1
+bcd   -12.5  x
+e            2
+f      1000  3\
+")
+}
+
+#[test]
+fn table_wide_characters() {
+    let wrk = Workdir::new("table_wide_characters");
+    wrk.create("in.csv", vec![
+        svec!["city", "n"],
+        svec!["東京", "1"],
+        svec!["Zürich", "22"],
+    ]);
+
+    let mut cmd = wrk.command("table");
+    cmd.arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(&*got, "\
+city     n
+東京     1
+Zürich  22\
+")
+}
+
+#[test]
+fn table_max_width() {
+    let wrk = Workdir::new("table_max_width");
+    wrk.create("in.csv", vec![
+        svec!["id", "description"],
+        svec!["1", "a rather long description"],
+        svec!["2", "short"],
+    ]);
+
+    let mut cmd = wrk.command("table");
+    cmd.args(&["--max-width", "17"]).arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(&*got, "\
+id  description
+ 1  a rather l...
+ 2  short\
+")
+}
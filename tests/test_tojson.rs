@@ -0,0 +1,62 @@
+use workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["name", "n"],
+        svec!["a", "1"],
+        svec!["say \"hi\"\\\nbye\t", "-2.5e3"],
+        svec!["b", "01"],
+    ]
+}
+
+#[test]
+fn tojson_objects() {
+    let wrk = Workdir::new("tojson_objects");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("tojson");
+    cmd.arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+{\"name\":\"a\",\"n\":\"1\"}
+{\"name\":\"say \\\"hi\\\"\\\\\\nbye\\t\",\"n\":\"-2.5e3\"}
+{\"name\":\"b\",\"n\":\"01\"}";
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn tojson_array_numbers() {
+    let wrk = Workdir::new("tojson_array_numbers");
+    wrk.create("in.csv", data());
+    let mut cmd = wrk.command("tojson");
+    cmd.arg("--array").arg("--numbers").arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+[{\"name\":\"a\",\"n\":1},
+{\"name\":\"say \\\"hi\\\"\\\\\\nbye\\t\",\"n\":-2.5e3},
+{\"name\":\"b\",\"n\":\"01\"}]";
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn tojson_array_empty() {
+    let wrk = Workdir::new("tojson_array_empty");
+    wrk.create("in.csv", vec![svec!["name", "n"]]);
+    let mut cmd = wrk.command("tojson");
+    cmd.arg("--array").arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "[]");
+}
+
+#[test]
+fn tojson_no_headers() {
+    let wrk = Workdir::new("tojson_no_headers");
+    wrk.create("in.csv", vec![svec!["a", "b"]]);
+    let mut cmd = wrk.command("tojson");
+    cmd.arg("--no-headers").arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "{\"field0\":\"a\",\"field1\":\"b\"}");
+}
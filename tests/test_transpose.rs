@@ -0,0 +1,69 @@
+use workdir::Workdir;
+
+fn transpose(name: &str, rows: Vec<Vec<String>>, multipass: bool)
+            -> Vec<Vec<String>> {
+    let wrk = Workdir::new(name).flexible(true);
+    wrk.create("in.csv", rows);
+
+    let mut cmd = wrk.command("transpose");
+    cmd.arg("in.csv");
+    if multipass { cmd.arg("--multipass"); }
+    wrk.read_stdout(&mut cmd)
+}
+
+macro_rules! transpose_test {
+    ($name:ident, $rows:expr, $expected:expr) => (
+        mod $name {
+            use super::transpose;
+
+            #[test]
+            fn in_memory() {
+                let name = concat!(stringify!($name), "_in_memory");
+                assert_eq!(transpose(name, $rows, false), $expected);
+            }
+
+            #[test]
+            fn multipass() {
+                let name = concat!(stringify!($name), "_multipass");
+                assert_eq!(transpose(name, $rows, true), $expected);
+            }
+        }
+    );
+}
+
+transpose_test!(transpose_square, vec![
+    svec!["a", "b", "c"],
+    svec!["1", "2", "3"],
+    svec!["x", "y", "z"],
+], vec![
+    svec!["a", "1", "x"],
+    svec!["b", "2", "y"],
+    svec!["c", "3", "z"],
+]);
+
+transpose_test!(transpose_non_square, vec![
+    svec!["h1", "h2", "h3"],
+    svec!["a", "b", "c"],
+], vec![
+    svec!["h1", "a"],
+    svec!["h2", "b"],
+    svec!["h3", "c"],
+]);
+
+transpose_test!(transpose_ragged, vec![
+    svec!["a"],
+    svec!["b", "c", "d"],
+    svec!["e", "f"],
+], vec![
+    svec!["a", "b", "e"],
+    svec!["", "c", "f"],
+    svec!["", "d", ""],
+]);
+
+#[test]
+fn transpose_multipass_stdin() {
+    let wrk = Workdir::new("transpose_multipass_stdin");
+    let mut cmd = wrk.command("transpose");
+    cmd.arg("--multipass");
+    wrk.assert_err(&mut cmd);
+}
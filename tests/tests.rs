@@ -8,6 +8,7 @@ extern crate serde_derive;
 
 extern crate csv;
 extern crate filetime;
+extern crate flate2;
 extern crate quickcheck;
 extern crate rand;
 extern crate stats;
@@ -34,23 +35,37 @@ macro_rules! rassert_eq {
 
 mod workdir;
 
+mod test_apply;
+mod test_behead;
 mod test_cat;
 mod test_count;
+mod test_dedup;
+mod test_enumerate;
+mod test_fill;
 mod test_fixlengths;
 mod test_flatten;
 mod test_fmt;
 mod test_frequency;
+mod test_fromjson;
 mod test_headers;
 mod test_index;
 mod test_join;
 mod test_partition;
+mod test_pseudo;
+mod test_rename;
+mod test_replace;
+mod test_reverse;
+mod test_sample;
 mod test_search;
+mod test_searchset;
 mod test_select;
 mod test_slice;
 mod test_sort;
 mod test_split;
 mod test_stats;
 mod test_table;
+mod test_tojson;
+mod test_transpose;
 
 fn qcheck<T: Testable>(p: T) {
     QuickCheck::new().gen(StdGen::new(thread_rng(), 5)).quickcheck(p);